@@ -0,0 +1,11 @@
+-- บันทึกเหตุการณ์ด้าน security (เพิ่มอย่างเดียว ไม่แก้ / ไม่ลบ)
+CREATE TABLE audit_log (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  actor_id UUID,                               -- ผู้กระทำ (NULL = ระบบ) ไม่ผูก FK ให้ log อยู่ต่อแม้ลบ user
+  action TEXT NOT NULL,                        -- เช่น mfa.reset, mfa.recovery_code_used
+  target_user_id UUID,                         -- user ที่ได้รับผล
+  details JSONB NOT NULL DEFAULT '{}',
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_audit_log_target ON audit_log (target_user_id, created_at DESC);
@@ -0,0 +1,3 @@
+-- สำหรับฐานข้อมูลเดิม: factor ที่ผ่านแล้วก่อนถึงขั้นตอนนี้ (เช่น password ก่อน MFA)
+ALTER TABLE auth_ceremonies
+  ADD COLUMN IF NOT EXISTS amr TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,15 @@
+-- สถานะระหว่างขั้นตอน login / ลงทะเบียนที่มีหลาย request (เก็บใน DB ให้ใช้ได้หลาย instance)
+-- - kind: passkey_register / passkey_login / mfa / totp_enroll
+-- - id เป็น token ที่ส่งให้ client (ใช้ได้ครั้งเดียว, อายุสั้น)
+CREATE TABLE auth_ceremonies (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  kind TEXT NOT NULL,
+  user_id UUID REFERENCES users(id) ON DELETE CASCADE,  -- NULL = ยังไม่รู้ว่าเป็นใคร (passkey login)
+  state JSONB,                                          -- challenge state ของ webauthn-rs / TOTP secret ที่รอยืนยัน
+  remember_me BOOLEAN NOT NULL DEFAULT FALSE,
+  amr TEXT[] NOT NULL DEFAULT '{}',                     -- factor ที่ผ่านแล้ว (mfa: factor แรก)
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_auth_ceremonies_expires ON auth_ceremonies (expires_at);
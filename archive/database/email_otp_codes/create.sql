@@ -0,0 +1,17 @@
+-- รหัส 6 หลักทางอีเมล (login แบบไม่ใช้รหัสผ่าน / step-up ก่อนทำรายการสำคัญ)
+-- - purpose: login / step_up
+-- - id เป็น otp_token ที่ส่งให้ client ใช้คู่กับรหัส
+-- - เก็บแค่ HMAC ของรหัส, กรอกผิดได้จำกัด (attempts), ใช้ได้ครั้งเดียว (used_at)
+CREATE TABLE email_otp_codes (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  purpose TEXT NOT NULL,
+  code_hash TEXT NOT NULL,
+  attempts INT NOT NULL DEFAULT 0,
+  remember_me BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  expires_at TIMESTAMPTZ NOT NULL,
+  used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_email_otp_codes_user_created ON email_otp_codes (user_id, created_at);
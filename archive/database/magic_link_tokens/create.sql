@@ -0,0 +1,16 @@
+-- ลิงก์ login ทางอีเมล (passwordless)
+-- - เก็บแค่ HMAC ของ token และ nonce (nonce อยู่ใน cookie ของ browser ที่ขอลิงก์)
+-- - ใช้ได้ครั้งเดียว (used_at) และอายุสั้น
+-- - แถวย้อนหลังใช้นับ rate limit ต่ออีเมล
+CREATE TABLE magic_link_tokens (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  token_hash TEXT UNIQUE NOT NULL,
+  nonce_hash TEXT NOT NULL,
+  remember_me BOOLEAN NOT NULL DEFAULT FALSE,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  expires_at TIMESTAMPTZ NOT NULL,
+  used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_magic_link_tokens_user_created ON magic_link_tokens (user_id, created_at);
@@ -0,0 +1,9 @@
+-- recovery code ของ MFA (ใช้ได้ครั้งละหนึ่งรหัส, แสดงให้ user เห็นครั้งเดียวตอนสร้าง)
+CREATE TABLE mfa_recovery_codes (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  code_hash TEXT NOT NULL,                     -- HMAC-SHA256 ด้วย server secret (ไม่เก็บรหัสจริง)
+  used_at TIMESTAMPTZ,                         -- NULL = ยังไม่ถูกใช้
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  UNIQUE (user_id, code_hash)
+);
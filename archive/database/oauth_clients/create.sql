@@ -0,0 +1,13 @@
+-- ตาราง client ฝั่ง backend (resource server / service account)
+-- - ใช้กับ /oauth/introspect และ /oauth/token (grant_type=client_credentials)
+CREATE TABLE oauth_clients (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  client_id TEXT UNIQUE NOT NULL,              -- ใช้เป็น username ตอนทำ HTTP Basic
+  client_secret_hash TEXT NOT NULL,            -- Argon2 PHC string (ไม่เก็บ secret จริง)
+  name TEXT NOT NULL,                          -- ชื่อบริการ
+  scopes TEXT[] NOT NULL DEFAULT '{}',         -- scope ที่อนุญาต เช่น {introspect}
+  is_active BOOLEAN NOT NULL DEFAULT TRUE,     -- ใช้ปิด client ได้
+  token_version INTEGER NOT NULL DEFAULT 1,    -- เพิ่มค่าเมื่อต้องการยกเลิก access token ที่ออกไปแล้วทั้งหมด
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,9 @@
+-- รหัสผ่านเก่าของ user (กันใช้ซ้ำ N อันล่าสุด, ตัดอันเก่ากว่านั้นทิ้งทุกครั้งที่เปลี่ยนรหัส)
+CREATE TABLE password_history (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  password_hash TEXT NOT NULL,            -- hash เดิมตามที่อยู่ใน users (Argon2id / legacy)
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now()  -- เวลาที่รหัสนี้ถูกแทนที่
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_history_user ON password_history(user_id, created_at DESC);
@@ -0,0 +1,4 @@
+-- สำหรับฐานข้อมูลเดิม: เวลา / วิธียืนยันตัวตนล่าสุดของ session (auth_time / amr ใน access token)
+ALTER TABLE refresh_tokens
+  ADD COLUMN IF NOT EXISTS auth_time TIMESTAMPTZ,
+  ADD COLUMN IF NOT EXISTS amr TEXT[] NOT NULL DEFAULT '{}';
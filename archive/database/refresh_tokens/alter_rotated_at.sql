@@ -0,0 +1,3 @@
+-- สำหรับฐานข้อมูลเดิม: เวลาที่ token ถูก rotate (แยกจาก revoke ตอน logout) ใช้คิด grace ของ refresh ซ้อนกัน
+ALTER TABLE refresh_tokens
+  ADD COLUMN IF NOT EXISTS rotated_at TIMESTAMPTZ;
@@ -0,0 +1,15 @@
+-- สำหรับฐานข้อมูลเดิม: เพิ่มข้อมูล session สำหรับ absolute session limit + remember me
+ALTER TABLE refresh_tokens
+  ADD COLUMN IF NOT EXISTS session_started_at TIMESTAMPTZ,
+  ADD COLUMN IF NOT EXISTS remember_me BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- แถวเดิม: ถือว่า session เริ่มตอนออก token นั้น (ไม่ใช่เวลารัน migration
+-- ไม่งั้นทุก session ของ user กลายเป็น session เดียวกัน ---> reuse ของ token เดียวเพิกถอนทั้งหมด)
+-- รวมฐานข้อมูลที่เคยรันเวอร์ชันที่ใส่ DEFAULT now() ไปแล้ว (session เริ่มหลังออก token ไม่ได้)
+UPDATE refresh_tokens
+  SET session_started_at = created_at
+  WHERE session_started_at IS NULL OR session_started_at > created_at;
+
+ALTER TABLE refresh_tokens
+  ALTER COLUMN session_started_at SET DEFAULT now(),
+  ALTER COLUMN session_started_at SET NOT NULL;
@@ -5,9 +5,15 @@ CREATE TABLE refresh_tokens (
   user_agent TEXT,                        -- อุปกรณ์/เบราว์เซอร์
   ip INET,                                -- ไอพีล่าสุด
   created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
-  expires_at TIMESTAMPTZ NOT NULL,        -- อายุของ refresh token
+  expires_at TIMESTAMPTZ NOT NULL,        -- อายุของ refresh token (idle timeout แบบ sliding)
+  session_started_at TIMESTAMPTZ NOT NULL DEFAULT now(), -- เวลา login ครั้งแรกของ session (ส่งต่อทุกครั้งที่ rotate)
+  remember_me BOOLEAN NOT NULL DEFAULT FALSE,            -- login แบบ "จดจำฉัน" หรือไม่
+  auth_time TIMESTAMPTZ,                  -- เวลายืนยันตัวตนล่าสุดของ session (login / step-up)
+  amr TEXT[] NOT NULL DEFAULT '{}',       -- วิธีที่ใช้ยืนยันตัวตน เช่น {password,totp,mfa}
+  rotated_at TIMESTAMPTZ,                 -- ถูก rotate เป็น token ใหม่เมื่อไร (ใช้คิด grace ของ refresh ที่ซ้อนกัน)
   revoked_at TIMESTAMPTZ                  -- ถ้าถูกเพิกถอน
 );
 
 CREATE INDEX IF NOT EXISTS idx_refresh_user ON refresh_tokens(user_id);
 CREATE INDEX IF NOT EXISTS idx_refresh_exp  ON refresh_tokens(expires_at);
+CREATE INDEX IF NOT EXISTS idx_refresh_session ON refresh_tokens(user_id, session_started_at);
@@ -0,0 +1,3 @@
+-- สำหรับฐานข้อมูลเดิม: time step ล่าสุดของ TOTP ที่ใช้ไปแล้ว (กันใช้รหัสเดิมซ้ำในช่วง 30 วินาที)
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS mfa_totp_last_step BIGINT;
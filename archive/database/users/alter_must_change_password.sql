@@ -0,0 +1,3 @@
+-- สำหรับฐานข้อมูลเดิม: admin สั่งให้เปลี่ยนรหัสผ่านตอน login ครั้งถัดไป
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
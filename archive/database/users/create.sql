@@ -12,12 +12,14 @@ CREATE TABLE users (
     is_active BOOLEAN NOT NULL DEFAULT TRUE,          -- ใช้ปิดบัญชีได้
     token_version INTEGER NOT NULL DEFAULT 1,         -- ใช้สำหรับ JWT: เพิ่มค่าเมื่อ force logout ทั้งระบบ
     password_changed_at TIMESTAMPTZ,                  -- เวลาที่ผู้ใช้เปลี่ยนรหัสผ่านล่าสุด (ตรวจ iat ของ JWT)
+    must_change_password BOOLEAN NOT NULL DEFAULT FALSE, -- ต้องเปลี่ยนรหัสผ่านก่อนใช้งานอื่น (admin สั่ง / reset)
     email_verified_at TIMESTAMPTZ,                    -- เวลาที่ผู้ใช้ยืนยันอีเมลแล้ว (NULL = ยังไม่ยืนยัน)
     failed_login_attempts INTEGER NOT NULL DEFAULT 0, -- จำนวนครั้งที่ login ล้มเหลว
     locked_until TIMESTAMPTZ,                         -- ถ้ามีค่า = บัญชีถูกล็อกชั่วคราว
     last_login_at TIMESTAMPTZ,                        -- เวลาที่ login สำเร็จครั้งล่าสุด
     mfa_enabled BOOLEAN NOT NULL DEFAULT FALSE,       -- เปิดใช้ MFA หรือไม่
-    mfa_totp_secret BYTEA,                            -- เก็บ TOTP secret (ควรเข้ารหัสด้วย pgcrypto)
+    mfa_totp_secret BYTEA,                            -- TOTP secret (เข้ารหัสแบบ envelope ในแอป ดู ENCRYPTION_KEYS)
+    mfa_totp_last_step BIGINT,                        -- time step ของ TOTP ที่ใช้ล่าสุด (กันใช้ซ้ำ)
     created_at TIMESTAMPTZ NOT NULL DEFAULT now(),    -- วันที่สมัคร
     updated_at TIMESTAMPTZ NOT NULL DEFAULT now()     -- อัปเดตล่าสุด
 );
@@ -0,0 +1,13 @@
+-- passkey (WebAuthn) ของ user
+-- - user handle ที่ส่งให้ authenticator = users.id (ใช้หา user ตอน login แบบไม่กรอก username)
+CREATE TABLE webauthn_credentials (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+  credential_id BYTEA UNIQUE NOT NULL,         -- credential id จาก authenticator
+  passkey JSONB NOT NULL,                      -- Passkey ของ webauthn-rs (public key + sign counter)
+  name TEXT NOT NULL,                          -- ชื่อที่ user ตั้งเอง เช่น "MacBook"
+  created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+  last_used_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_webauthn_credentials_user ON webauthn_credentials (user_id);
@@ -0,0 +1,37 @@
+use serde_json::Value;
+use sqlx::PgExecutor;
+use tracing::{Instrument, info};
+use uuid::Uuid;
+
+use crate::app::result::AppResult;
+use crate::app::telemetry::db_span;
+
+/*
+|---------------------------------
+| บันทึกเหตุการณ์ด้าน security ลง audit_log (+ log บรรทัดเดียวกัน)
+| - ส่ง transaction มาได้ ให้ audit record commit พร้อมการเปลี่ยนแปลงจริง
+| - action ใช้รูปแบบ "<หมวด>.<เหตุการณ์>" เช่น mfa.reset
+|---------------------------------
+*/
+pub async fn record<'e, E: PgExecutor<'e>>(
+    db: E,
+    actor_id: Option<Uuid>,
+    action: &'static str,
+    target_user_id: Option<Uuid>,
+    details: Value,
+) -> AppResult<()> {
+    sqlx::query!(
+        "INSERT INTO audit_log (actor_id, action, target_user_id, details) VALUES ($1, $2, $3, $4)",
+        actor_id,
+        action,
+        target_user_id,
+        details
+    )
+    .execute(db)
+    .instrument(db_span("INSERT audit_log"))
+    .await?;
+
+    info!(action, actor_id = ?actor_id, target_user_id = ?target_user_id, "audit");
+
+    Ok(())
+}
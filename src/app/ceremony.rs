@@ -0,0 +1,180 @@
+use chrono::{Duration, Utc};
+use serde::{Serialize, de::DeserializeOwned};
+use sqlx::PgPool;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::app::result::AppResult;
+use crate::app::telemetry::db_span;
+use crate::utils::env::env_i64;
+
+// ขั้นตอนที่ต้องจำสถานะข้าม request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CeremonyKind {
+    PasskeyRegister,
+    PasskeyLogin,
+    // รหัสผ่านผ่านแล้ว รอ factor ที่สอง
+    Mfa,
+    // TOTP secret ที่สร้างแล้ว รอ user ยืนยันด้วยรหัสแรก
+    TotpEnroll,
+}
+
+impl CeremonyKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::PasskeyRegister => "passkey_register",
+            Self::PasskeyLogin => "passkey_login",
+            Self::Mfa => "mfa",
+            Self::TotpEnroll => "totp_enroll",
+        }
+    }
+}
+
+pub struct Ceremony<T> {
+    pub user_id: Option<Uuid>,
+    pub state: Option<T>,
+    pub remember_me: bool,
+    // factor ที่ผ่านแล้วก่อนถึงขั้นตอนนี้ (amr)
+    pub amr: Vec<String>,
+}
+
+/*
+|---------------------------------
+| เก็บสถานะ ceremony ลงตาราง auth_ceremonies (ใช้ได้หลาย instance)
+| - id ที่ส่งให้ client ใช้ได้ครั้งเดียว (take = DELETE ... RETURNING)
+| - AUTH_CEREMONY_TTL_SECS (ค่าเริ่มต้น 300)
+| - แถวที่หมดอายุถูกลบตอนสร้างแถวใหม่
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct CeremonyStore {
+    ttl: Duration,
+}
+
+impl CeremonyStore {
+    pub fn from_env() -> AppResult<Self> {
+        let secs = env_i64("AUTH_CEREMONY_TTL_SECS", 300)?.clamp(30, 1800);
+        Ok(Self { ttl: Duration::seconds(secs) })
+    }
+
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub async fn create<T: Serialize>(
+        &self,
+        db: &PgPool,
+        kind: CeremonyKind,
+        user_id: Option<Uuid>,
+        state: Option<&T>,
+        remember_me: bool,
+        amr: &[&str],
+    ) -> AppResult<Uuid> {
+        let state = state.map(serde_json::to_value).transpose()?;
+
+        sqlx::query!("DELETE FROM auth_ceremonies WHERE expires_at < now()")
+            .execute(db)
+            .instrument(db_span("DELETE auth_ceremonies"))
+            .await?;
+
+        let id = sqlx::query_scalar!(
+            "INSERT INTO auth_ceremonies (kind, user_id, state, remember_me, amr, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING id",
+            kind.as_str(),
+            user_id,
+            state,
+            remember_me,
+            &amr.iter().map(|m| m.to_string()).collect::<Vec<_>>(),
+            Utc::now() + self.ttl
+        )
+        .fetch_one(db)
+        .instrument(db_span("INSERT auth_ceremonies"))
+        .await?;
+
+        Ok(id)
+    }
+
+    // แทนที่ state ของ ceremony ที่ยังไม่หมดอายุ (ไม่ต่ออายุ) ---> user_id ของ ceremony นั้น
+    pub async fn set_state<T: Serialize>(
+        &self,
+        db: &PgPool,
+        id: Uuid,
+        kind: CeremonyKind,
+        state: &T,
+    ) -> AppResult<Option<Uuid>> {
+        let state = serde_json::to_value(state)?;
+
+        let row = sqlx::query_scalar!(
+            "UPDATE auth_ceremonies SET state = $3
+             WHERE id = $1 AND kind = $2 AND expires_at > now()
+             RETURNING user_id",
+            id,
+            kind.as_str(),
+            state
+        )
+        .fetch_optional(db)
+        .instrument(db_span("UPDATE auth_ceremonies"))
+        .await?;
+
+        Ok(row.flatten())
+    }
+
+    // อ่านโดยไม่ลบ (ขั้นตอนที่ให้ลองใหม่ได้จนหมดอายุ เช่น กรอกรหัส TOTP ผิด)
+    pub async fn get<T: DeserializeOwned>(
+        &self,
+        db: &PgPool,
+        id: Uuid,
+        kind: CeremonyKind,
+    ) -> AppResult<Option<Ceremony<T>>> {
+        let row = sqlx::query!(
+            "SELECT user_id, state, remember_me, amr FROM auth_ceremonies
+             WHERE id = $1 AND kind = $2 AND expires_at > now()",
+            id,
+            kind.as_str()
+        )
+        .fetch_optional(db)
+        .instrument(db_span("SELECT auth_ceremonies"))
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(Ceremony {
+            user_id: row.user_id,
+            state: row.state.map(serde_json::from_value).transpose()?,
+            remember_me: row.remember_me,
+            amr: row.amr,
+        }))
+    }
+
+    pub async fn take<T: DeserializeOwned>(
+        &self,
+        db: &PgPool,
+        id: Uuid,
+        kind: CeremonyKind,
+    ) -> AppResult<Option<Ceremony<T>>> {
+        let row = sqlx::query!(
+            "DELETE FROM auth_ceremonies
+             WHERE id = $1 AND kind = $2 AND expires_at > now()
+             RETURNING user_id, state, remember_me, amr",
+            id,
+            kind.as_str()
+        )
+        .fetch_optional(db)
+        .instrument(db_span("DELETE auth_ceremonies"))
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(Ceremony {
+            user_id: row.user_id,
+            state: row.state.map(serde_json::from_value).transpose()?,
+            remember_me: row.remember_me,
+            amr: row.amr,
+        }))
+    }
+}
@@ -0,0 +1,131 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{HeaderMap, request::Parts};
+use sqlx::types::ipnet::IpNet;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::app::state::AppState;
+use crate::utils::env::env_list;
+
+/*
+|---------------------------------
+| proxy ที่เชื่อ X-Forwarded-For ได้
+| - TRUSTED_PROXIES: IP หรือ CIDR คั่นด้วย comma (เช่น "10.0.0.0/8,127.0.0.1")
+| - ไม่ตั้ง ---> ไม่อ่าน header เลย ใช้ peer address ของ connection อย่างเดียว
+|---------------------------------
+*/
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    pub fn from_env() -> AppResult<Self> {
+        env_list("TRUSTED_PROXIES")
+            .iter()
+            .map(|p| {
+                p.parse::<IpNet>()
+                    .or_else(|_| p.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| AppError::BadRequest(format!("TRUSTED_PROXIES: invalid address \"{p}\"")))
+            })
+            .collect::<AppResult<_>>()
+            .map(Self)
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(&ip))
+    }
+
+    /*
+    |---------------------------------
+    | IP ของ client จริง
+    | - peer ไม่ใช่ proxy ที่เชื่อ ---> peer (header ปลอมได้ ไม่อ่าน)
+    | - peer เป็น proxy ---> ไล่ X-Forwarded-For จากขวา ข้าม proxy ที่เชื่อ เอาตัวแรกที่ไม่ใช่
+    |   (ทางซ้ายของตัวนั้น client ใส่เองได้)
+    |---------------------------------
+    */
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        let mut client = peer;
+        for hop in headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .rev()
+        {
+            let Ok(ip) = hop.trim().parse::<IpAddr>() else { break };
+            client = ip;
+            if !self.contains(ip) {
+                break;
+            }
+        }
+
+        client
+    }
+}
+
+/*
+|---------------------------------
+| IP ของผู้เรียก (เก็บลง refresh_tokens.ip)
+| - ต้อง serve ด้วย into_make_service_with_connect_info ---> ไม่มี ConnectInfo = None (ไม่เดา)
+|---------------------------------
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl FromRequestParts<Arc<AppState>> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(peer)| state.trusted_proxies.resolve(peer.ip(), &parts.headers));
+
+        Ok(Self(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proxies(list: &[&str]) -> TrustedProxies {
+        TrustedProxies(list.iter().map(|p| p.parse().unwrap()).collect())
+    }
+
+    fn forwarded(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_header() {
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+        let ip = proxies(&[]).resolve(peer, &forwarded("198.51.100.1"));
+        assert_eq!(ip, peer);
+    }
+
+    #[test]
+    fn trusted_peer_takes_rightmost_untrusted_hop() {
+        let trusted = proxies(&["10.0.0.0/8"]);
+        let ip = trusted.resolve("10.0.0.2".parse().unwrap(), &forwarded("1.2.3.4, 198.51.100.9, 10.0.0.5"));
+        assert_eq!(ip, "198.51.100.9".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn trusted_peer_without_header_is_peer() {
+        let peer: IpAddr = "10.0.0.2".parse().unwrap();
+        assert_eq!(proxies(&["10.0.0.0/8"]).resolve(peer, &HeaderMap::new()), peer);
+    }
+}
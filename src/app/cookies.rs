@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use cookie::{Cookie, SameSite, time::Duration as CookieDuration};
+
+use crate::app::{error::AppError, result::AppResult};
+use crate::utils::env::{env_bool, env_string};
+
+// ชื่อพื้นฐานของ cookie (ก่อนเติม prefix)
+const REFRESH_COOKIE: &str = "refresh_token";
+const CSRF_COOKIE: &str = "csrf_token";
+const MAGIC_LINK_COOKIE: &str = "magic_link_nonce";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CookiePrefix {
+    None,
+    Host,   // __Host-   ต้อง Secure + Path=/ + ห้ามมี Domain
+    Secure, // __Secure- ต้อง Secure
+}
+
+/*
+|---------------------------------
+| นโยบาย refresh cookie (ใช้ที่เดียวทั้ง set / rotate / clear)
+| - ตั้งค่าผ่าน ENV ตาม environment
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct CookiePolicy {
+    name: String,
+    csrf_name: String,
+    magic_link_name: String,
+    secure: bool,
+    same_site: SameSite,
+    domain: Option<String>,
+    path: String,
+    partitioned: bool,
+}
+
+impl CookiePolicy {
+    /*
+    | COOKIE_SECURE       true/false (ค่าเริ่มต้น: true เมื่อ production)
+    | COOKIE_SAME_SITE    strict/lax/none (ค่าเริ่มต้น lax)
+    | COOKIE_DOMAIN       ไม่ตั้ง = host-only
+    | COOKIE_PATH         ค่าเริ่มต้น /auth (prefix host ---> บังคับ /)
+    | COOKIE_PREFIX       none/host/secure
+    | COOKIE_PARTITIONED  true/false (CHIPS)
+    */
+    pub fn from_env(production: bool) -> AppResult<Self> {
+        let secure = env_bool("COOKIE_SECURE", production)?;
+
+        let same_site = match env_string("COOKIE_SAME_SITE", "lax").to_ascii_lowercase().as_str() {
+            "strict" => SameSite::Strict,
+            "lax" => SameSite::Lax,
+            "none" => SameSite::None,
+            other => return Err(AppError::BadRequest(format!("invalid COOKIE_SAME_SITE: {other}"))),
+        };
+
+        let prefix = match env_string("COOKIE_PREFIX", "none").to_ascii_lowercase().as_str() {
+            "none" | "" => CookiePrefix::None,
+            "host" => CookiePrefix::Host,
+            "secure" => CookiePrefix::Secure,
+            other => return Err(AppError::BadRequest(format!("invalid COOKIE_PREFIX: {other}"))),
+        };
+
+        let domain = std::env::var("COOKIE_DOMAIN").ok().filter(|d| !d.trim().is_empty());
+        let path = env_string("COOKIE_PATH", "/auth");
+        let partitioned = env_bool("COOKIE_PARTITIONED", false)?;
+
+        let policy = Self::new(prefix, secure, same_site, domain, path, partitioned);
+        policy.validate(production)?;
+
+        Ok(policy)
+    }
+
+    pub fn new(
+        prefix: CookiePrefix,
+        secure: bool,
+        same_site: SameSite,
+        domain: Option<String>,
+        path: String,
+        partitioned: bool,
+    ) -> Self {
+        let prefixed = |base: &str| match prefix {
+            CookiePrefix::None => base.to_string(),
+            CookiePrefix::Host => format!("__Host-{base}"),
+            CookiePrefix::Secure => format!("__Secure-{base}"),
+        };
+        let name = prefixed(REFRESH_COOKIE);
+        let csrf_name = prefixed(CSRF_COOKIE);
+        let magic_link_name = prefixed(MAGIC_LINK_COOKIE);
+
+        // __Host- ใช้ได้เฉพาะ Path=/ และห้ามมี Domain
+        let (domain, path) = match prefix {
+            CookiePrefix::Host => (None, "/".to_string()),
+            _ => (domain, path),
+        };
+
+        Self { name, csrf_name, magic_link_name, secure, same_site, domain, path, partitioned }
+    }
+
+    // กันค่าที่ browser จะปฏิเสธ หรือไม่ปลอดภัยใน production
+    fn validate(&self, production: bool) -> AppResult<()> {
+        if production && !self.secure {
+            return Err(AppError::BadRequest("COOKIE_SECURE must be true in production".into()));
+        }
+
+        if self.name.starts_with("__") && !self.secure {
+            return Err(AppError::BadRequest("COOKIE_PREFIX requires COOKIE_SECURE=true".into()));
+        }
+
+        if self.same_site == SameSite::None && !self.secure {
+            return Err(AppError::BadRequest("COOKIE_SAME_SITE=none requires COOKIE_SECURE=true".into()));
+        }
+
+        if self.partitioned && !self.secure {
+            return Err(AppError::BadRequest("COOKIE_PARTITIONED requires COOKIE_SECURE=true".into()));
+        }
+
+        Ok(())
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn csrf_name(&self) -> &str {
+        &self.csrf_name
+    }
+
+    pub fn magic_link_name(&self) -> &str {
+        &self.magic_link_name
+    }
+
+    fn refresh_base(&self, value: String) -> Cookie<'static> {
+        let mut c = self.common(self.name.clone(), value);
+        c.set_http_only(true);
+        c.set_path(self.path.clone());
+        c
+    }
+
+    fn common(&self, name: String, value: String) -> Cookie<'static> {
+        let mut c = Cookie::new(name, value);
+        c.set_secure(self.secure);
+        c.set_same_site(self.same_site);
+        if let Some(domain) = &self.domain {
+            c.set_domain(domain.clone());
+        }
+        if self.partitioned {
+            c.set_partitioned(true);
+        }
+        c
+    }
+
+    // expires_at = None ---> session cookie (หายเมื่อปิด browser)
+    pub fn refresh_cookie(&self, value: String, expires_at: Option<DateTime<Utc>>) -> Cookie<'static> {
+        let mut c = self.refresh_base(value);
+        if let Some(exp) = expires_at {
+            let secs = (exp - Utc::now()).num_seconds().max(0);
+            c.set_max_age(CookieDuration::seconds(secs));
+        }
+        c
+    }
+
+    // cookie สำหรับลบทิ้ง (attribute ต้องตรงกับตอน set ไม่งั้น browser ไม่ลบ)
+    pub fn removal_cookie(&self) -> Cookie<'static> {
+        let mut c = self.refresh_base(String::new());
+        c.make_removal();
+        c
+    }
+
+    // CSRF cookie: JS ต้องอ่านได้ (ไม่ HttpOnly) และต้องอ่านได้ทุก path ---> Path=/
+    // อายุเท่ากับ refresh cookie
+    pub fn csrf_cookie(&self, value: String, expires_at: Option<DateTime<Utc>>) -> Cookie<'static> {
+        let mut c = self.common(self.csrf_name.clone(), value);
+        c.set_path("/");
+        if let Some(exp) = expires_at {
+            let secs = (exp - Utc::now()).num_seconds().max(0);
+            c.set_max_age(CookieDuration::seconds(secs));
+        }
+        c
+    }
+
+    pub fn csrf_removal_cookie(&self) -> Cookie<'static> {
+        let mut c = self.common(self.csrf_name.clone(), String::new());
+        c.set_path("/");
+        c.make_removal();
+        c
+    }
+
+    // nonce ผูกลิงก์ login ทางอีเมลกับ browser ที่ขอ (อายุเท่าลิงก์)
+    pub fn magic_link_cookie(&self, value: String, max_age: chrono::Duration) -> Cookie<'static> {
+        let mut c = self.common(self.magic_link_name.clone(), value);
+        c.set_http_only(true);
+        c.set_path(self.path.clone());
+        c.set_max_age(CookieDuration::seconds(max_age.num_seconds().max(0)));
+        c
+    }
+
+    pub fn magic_link_removal_cookie(&self) -> Cookie<'static> {
+        let mut c = self.common(self.magic_link_name.clone(), String::new());
+        c.set_http_only(true);
+        c.set_path(self.path.clone());
+        c.make_removal();
+        c
+    }
+}
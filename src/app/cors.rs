@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::http::{HeaderName, HeaderValue, Method};
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+
+use crate::app::{error::AppError, result::AppResult};
+use crate::middleware::csrf::CSRF_HEADER;
+use crate::utils::env::{env_bool, env_i64, env_list};
+
+// รูปแบบ origin ที่อนุญาต
+// - "*"                       ทุก origin (ใช้กับ credentials ไม่ได้)
+// - "https://app.example.com" ตรงตัว
+// - "https://*.example.com"   ทุก subdomain (ไม่รวม example.com เอง)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OriginPattern {
+    Any,
+    Exact(String),
+    Subdomain { scheme: String, suffix: String },
+}
+
+impl OriginPattern {
+    pub fn parse(raw: &str) -> AppResult<Self> {
+        let raw = raw.trim().trim_end_matches('/');
+
+        if raw == "*" {
+            return Ok(Self::Any);
+        }
+
+        let (scheme, host) = raw
+            .split_once("://")
+            .ok_or_else(|| AppError::BadRequest(format!("invalid CORS origin: {raw}")))?;
+
+        match host.strip_prefix("*.") {
+            Some(rest) if !rest.is_empty() && !rest.contains('*') => Ok(Self::Subdomain {
+                scheme: format!("{scheme}://"),
+                suffix: format!(".{rest}").to_ascii_lowercase(),
+            }),
+            Some(_) => Err(AppError::BadRequest(format!("invalid CORS origin: {raw}"))),
+            None if host.contains('*') => Err(AppError::BadRequest(format!("invalid CORS origin: {raw}"))),
+            None => Ok(Self::Exact(raw.to_ascii_lowercase())),
+        }
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+
+        match self {
+            Self::Any => true,
+            Self::Exact(o) => *o == origin,
+            Self::Subdomain { scheme, suffix } => origin
+                .strip_prefix(scheme.as_str())
+                .and_then(|host| host.strip_suffix(suffix.as_str()))
+                .map(|sub| {
+                    !sub.is_empty()
+                        && sub.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                })
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CorsConfig {
+    pub origins: Vec<OriginPattern>,
+    pub methods: Vec<Method>,
+    pub headers: Vec<HeaderName>,
+    pub max_age: Duration,
+    pub credentials: bool,
+}
+
+impl CorsConfig {
+    /*
+    | {prefix}_ORIGINS            คั่นด้วย comma (ค่าเริ่มต้น http://localhost:3000)
+    | {prefix}_METHODS            ค่าเริ่มต้น GET,POST,PUT,DELETE,OPTIONS
+    | {prefix}_HEADERS            ค่าเริ่มต้น authorization,content-type,accept,x-csrf-token
+    | {prefix}_MAX_AGE_SECS       ค่าเริ่มต้น 600
+    | {prefix}_ALLOW_CREDENTIALS  ค่าเริ่มต้น true
+    |
+    | ตัวที่ไม่ได้ตั้ง ---> ใช้ค่าจาก base (ใช้ทำ override ราย route)
+    */
+    fn from_env(prefix: &str, base: &CorsConfig) -> AppResult<Self> {
+        let origins = match env_list(&format!("{prefix}_ORIGINS")) {
+            v if v.is_empty() => base.origins.clone(),
+            v => v.iter().map(|o| OriginPattern::parse(o)).collect::<AppResult<_>>()?,
+        };
+
+        let methods = match env_list(&format!("{prefix}_METHODS")) {
+            v if v.is_empty() => base.methods.clone(),
+            v => v
+                .iter()
+                .map(|m| {
+                    Method::from_bytes(m.to_ascii_uppercase().as_bytes())
+                        .map_err(|_| AppError::BadRequest(format!("invalid CORS method: {m}")))
+                })
+                .collect::<AppResult<_>>()?,
+        };
+
+        let headers = match env_list(&format!("{prefix}_HEADERS")) {
+            v if v.is_empty() => base.headers.clone(),
+            v => v
+                .iter()
+                .map(|h| {
+                    HeaderName::from_bytes(h.to_ascii_lowercase().as_bytes())
+                        .map_err(|_| AppError::BadRequest(format!("invalid CORS header: {h}")))
+                })
+                .collect::<AppResult<_>>()?,
+        };
+
+        let max_age = env_i64(&format!("{prefix}_MAX_AGE_SECS"), base.max_age.as_secs() as i64)?;
+        let credentials = env_bool(&format!("{prefix}_ALLOW_CREDENTIALS"), base.credentials)?;
+
+        let config = Self {
+            origins,
+            methods,
+            headers,
+            max_age: Duration::from_secs(max_age.max(0) as u64),
+            credentials,
+        };
+        config.validate(prefix)?;
+
+        Ok(config)
+    }
+
+    // wildcard origin + credentials = browser ส่ง cookie ให้ทุกเว็บ ---> ไม่ยอม start
+    fn validate(&self, prefix: &str) -> AppResult<()> {
+        if self.credentials && self.origins.contains(&OriginPattern::Any) {
+            return Err(AppError::BadRequest(format!(
+                "{prefix}_ORIGINS=* cannot be used with {prefix}_ALLOW_CREDENTIALS=true"
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = if self.origins.contains(&OriginPattern::Any) {
+            AllowOrigin::any()
+        } else {
+            let patterns = self.origins.clone();
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .map(|o| patterns.iter().any(|p| p.matches(o)))
+                    .unwrap_or(false)
+            })
+        };
+
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(AllowMethods::list(self.methods.clone()))
+            .allow_headers(AllowHeaders::list(self.headers.clone()))
+            .max_age(self.max_age)
+            .allow_credentials(self.credentials)
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: vec![OriginPattern::Exact("http://localhost:3000".into())],
+            methods: vec![Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS],
+            headers: vec![
+                axum::http::header::AUTHORIZATION,
+                axum::http::header::CONTENT_TYPE,
+                axum::http::header::ACCEPT,
+                HeaderName::from_static(CSRF_HEADER),
+            ],
+            max_age: Duration::from_secs(600),
+            credentials: true,
+        }
+    }
+}
+
+/*
+|---------------------------------
+| CORS ทั้งระบบ + override ราย route group
+| - CORS_*            ค่าหลัก
+| - CORS_{GROUP}_*    override เฉพาะ group (เช่น CORS_OAUTH_ORIGINS)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct CorsPolicies {
+    pub default: CorsConfig,
+    routes: HashMap<&'static str, CorsConfig>,
+}
+
+impl CorsPolicies {
+    pub fn from_env(groups: &[&'static str]) -> AppResult<Self> {
+        let default = CorsConfig::from_env("CORS", &CorsConfig::default())?;
+
+        let routes = groups
+            .iter()
+            .map(|g| {
+                let prefix = format!("CORS_{}", g.to_ascii_uppercase());
+                CorsConfig::from_env(&prefix, &default).map(|c| (*g, c))
+            })
+            .collect::<AppResult<_>>()?;
+
+        Ok(Self { default, routes })
+    }
+
+    pub fn layer(&self, group: &str) -> CorsLayer {
+        self.routes.get(group).unwrap_or(&self.default).layer()
+    }
+}
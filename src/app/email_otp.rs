@@ -0,0 +1,56 @@
+use chrono::Duration;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::utils::env::env_i64;
+
+pub const CODE_DIGITS: usize = 6;
+
+/*
+|---------------------------------
+| นโยบายรหัส 6 หลักทางอีเมล (login / step-up)
+| - EMAIL_OTP_TTL_SECS        อายุรหัส (ค่าเริ่มต้น 600)
+| - EMAIL_OTP_MAX_ATTEMPTS    กรอกผิดได้กี่ครั้งต่อรหัส (5) ครบแล้วต้องขอใหม่
+| - EMAIL_OTP_MAX_PER_EMAIL / EMAIL_OTP_WINDOW_SECS  จำนวนรหัสต่ออีเมลในช่วงเวลา (5 / 3600)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct EmailOtpPolicy {
+    pub ttl: Duration,
+    pub max_attempts: i32,
+    pub max_per_email: i64,
+    pub window: Duration,
+}
+
+impl EmailOtpPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        Ok(Self {
+            ttl: Duration::seconds(env_i64("EMAIL_OTP_TTL_SECS", 600)?.clamp(60, 3600)),
+            max_attempts: env_i64("EMAIL_OTP_MAX_ATTEMPTS", 5)?.clamp(1, 10) as i32,
+            max_per_email: env_i64("EMAIL_OTP_MAX_PER_EMAIL", 5)?.clamp(1, 100),
+            window: Duration::seconds(env_i64("EMAIL_OTP_WINDOW_SECS", 3600)?.clamp(60, 86_400)),
+        })
+    }
+}
+
+// รหัส 000000-999999 แบบ uniform (rejection sampling บน u32)
+pub fn generate_code() -> AppResult<String> {
+    const RANGE: u32 = 1_000_000;
+    let limit = u32::MAX - (u32::MAX % RANGE);
+
+    loop {
+        let mut buf = [0u8; 4];
+        getrandom::fill(&mut buf).map_err(|e| AppError::InternalError(format!("RNG failed: {:?}", e)))?;
+
+        let n = u32::from_le_bytes(buf);
+        if n < limit {
+            return Ok(format!("{:0width$}", n % RANGE, width = CODE_DIGITS));
+        }
+    }
+}
+
+// ตัดช่องว่างที่ user พิมพ์ / วางมา ---> None ถ้าไม่ใช่ตัวเลข 6 หลัก
+pub fn normalize_code(code: &str) -> Option<String> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    (code.len() == CODE_DIGITS && code.chars().all(|c| c.is_ascii_digit())).then_some(code)
+}
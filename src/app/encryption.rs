@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+
+use aes_gcm::{Aes256Gcm, aead::{Aead, KeyInit, Payload}};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use tracing::warn;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::utils::env::{env_bool, env_list, env_string};
+
+// คอลัมน์ที่เก็บแบบเข้ารหัส (table, column) ---> คำสั่ง reencrypt ไล่เข้ารหัสใหม่ตามรายการนี้
+// - ทุกตารางต้องมี primary key ชื่อ id (ใช้เป็นส่วนหนึ่งของ context)
+pub const ENCRYPTED_COLUMNS: &[(&str, &str)] = &[
+    ("users", "mfa_totp_secret"),
+];
+
+// รูปแบบ: MAGIC | kid_len (1) | kid | wrap nonce (12) | DEK ที่เข้ารหัสแล้ว (32+16) | data nonce (12) | ciphertext+tag
+const MAGIC: &[u8] = b"ev1";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const WRAPPED_KEY_LEN: usize = KEY_LEN + 16;
+
+// key ตัวอย่างใน .env.example ---> ห้ามใช้ตอน production
+const DEV_KEY_ID: &str = "dev";
+const DEV_KEY: &[u8; KEY_LEN] = b"dev-only-encryption-key-32-bytes";
+
+fn random<const N: usize>() -> AppResult<[u8; N]> {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes).map_err(|e| AppError::InternalError(format!("RNG failed: {:?}", e)))?;
+    Ok(bytes)
+}
+
+fn seal(key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> AppResult<Vec<u8>> {
+    Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::InternalError("invalid key length".into()))?
+        .encrypt(nonce.into(), Payload { msg, aad })
+        .map_err(|_| AppError::InternalError("encryption failed".into()))
+}
+
+fn open(key: &[u8], nonce: &[u8], msg: &[u8], aad: &[u8]) -> AppResult<Vec<u8>> {
+    Aes256Gcm::new_from_slice(key)
+        .map_err(|_| AppError::InternalError("invalid key length".into()))?
+        .decrypt(nonce.into(), Payload { msg, aad })
+        .map_err(|_| AppError::InternalError("decryption failed (wrong key or context)".into()))
+}
+
+/*
+|---------------------------------
+| Envelope encryption (AES-256-GCM) สำหรับคอลัมน์ที่เป็นความลับ
+| - แต่ละค่ามี DEK สุ่มของตัวเอง DEK ถูกเข้ารหัสด้วย KEK ตาม key id
+| - ENCRYPTION_KEYS="kid:base64,..." หรือ ENCRYPTION_KEYS_FILE (บรรทัดละ kid:base64, # = comment)
+| - ENCRYPTION_ACTIVE_KEY_ID ใช้เข้ารหัสค่าใหม่ (มี key เดียวไม่ต้องตั้ง)
+| - key id อื่นที่ยังอยู่ในรายการยัง decrypt ได้ (ช่วง rotate) ---> `authrs reencrypt` แล้วค่อยเอาออก
+| - context (table.column:id) ผูกเข้ากับ ciphertext ย้ายค่าไปแถวอื่นแล้ว decrypt ไม่ผ่าน
+| - ENCRYPTION_ALLOW_PLAINTEXT=false ---> ค่าที่ยังไม่เข้ารหัสถือเป็น error (ตั้งหลัง reencrypt ครบแล้ว)
+| - production ไม่ยอมใช้ key ตัวอย่าง (dev) จาก .env.example
+|---------------------------------
+*/
+#[derive(Clone)]
+pub struct Encryption {
+    keys: HashMap<String, [u8; KEY_LEN]>,
+    active: String,
+    allow_plaintext: bool,
+}
+
+impl Encryption {
+    pub fn new(keys: HashMap<String, [u8; KEY_LEN]>, active: String) -> AppResult<Self> {
+        if !keys.contains_key(&active) {
+            return Err(AppError::BadRequest(format!("ENCRYPTION_ACTIVE_KEY_ID {active:?} is not in ENCRYPTION_KEYS")));
+        }
+
+        Ok(Self { keys, active, allow_plaintext: true })
+    }
+
+    pub fn from_env(production: bool) -> AppResult<Self> {
+        let mut entries = env_list("ENCRYPTION_KEYS");
+
+        if let Ok(path) = std::env::var("ENCRYPTION_KEYS_FILE") {
+            let contents = std::fs::read_to_string(path.trim())?;
+            entries.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+
+        let mut keys = HashMap::new();
+
+        for entry in &entries {
+            let (kid, key) = entry
+                .split_once(':')
+                .ok_or_else(|| AppError::BadRequest("encryption key must be kid:base64".into()))?;
+            let kid = kid.trim();
+
+            if kid.is_empty() || kid.len() > 32 || !kid.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(AppError::BadRequest(format!("invalid encryption key id: {kid:?}")));
+            }
+
+            let key: [u8; KEY_LEN] = STANDARD
+                .decode(key.trim())?
+                .try_into()
+                .map_err(|_| AppError::BadRequest(format!("encryption key {kid} must be {KEY_LEN} bytes")))?;
+
+            if production && (kid == DEV_KEY_ID || &key == DEV_KEY) {
+                return Err(AppError::BadRequest("the example encryption key (dev) must not be used in production".into()));
+            }
+
+            if keys.insert(kid.to_string(), key).is_some() {
+                return Err(AppError::BadRequest(format!("duplicate encryption key id: {kid}")));
+            }
+        }
+
+        let active = match (env_string("ENCRYPTION_ACTIVE_KEY_ID", ""), keys.len()) {
+            (_, 0) => return Err(AppError::BadRequest("ENCRYPTION_KEYS / ENCRYPTION_KEYS_FILE is not set".into())),
+            (id, _) if !id.is_empty() => id,
+            (_, 1) => keys.keys().next().cloned().unwrap_or_default(),
+            _ => return Err(AppError::BadRequest("ENCRYPTION_ACTIVE_KEY_ID is required with more than one key".into())),
+        };
+
+        let mut encryption = Self::new(keys, active)?;
+        encryption.allow_plaintext = env_bool("ENCRYPTION_ALLOW_PLAINTEXT", true)?;
+
+        Ok(encryption)
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active
+    }
+
+    // key id ของค่าที่เข้ารหัสแล้ว (None = ไม่ใช่รูปแบบนี้ เช่นค่าเดิมที่ยังเป็น plain text)
+    pub fn key_id(data: &[u8]) -> Option<&str> {
+        let rest = data.strip_prefix(MAGIC)?;
+        let (&len, rest) = rest.split_first()?;
+        std::str::from_utf8(rest.get(..len as usize)?).ok()
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8], context: &str) -> AppResult<Vec<u8>> {
+        let kek = &self.keys[&self.active];
+        let kid = self.active.as_bytes();
+
+        let dek = random::<KEY_LEN>()?;
+        let wrap_nonce = random::<NONCE_LEN>()?;
+        let data_nonce = random::<NONCE_LEN>()?;
+
+        // DEK ผูกกับ key id + context / ข้อมูลผูกกับ context
+        let wrapped = seal(kek, &wrap_nonce, &dek, &[kid, context.as_bytes()].concat())?;
+        let ciphertext = seal(&dek, &data_nonce, plaintext, context.as_bytes())?;
+
+        let mut out = Vec::with_capacity(MAGIC.len() + 1 + kid.len() + 2 * NONCE_LEN + WRAPPED_KEY_LEN + ciphertext.len());
+        out.extend_from_slice(MAGIC);
+        out.push(kid.len() as u8);
+        out.extend_from_slice(kid);
+        out.extend_from_slice(&wrap_nonce);
+        out.extend_from_slice(&wrapped);
+        out.extend_from_slice(&data_nonce);
+        out.extend_from_slice(&ciphertext);
+
+        Ok(out)
+    }
+
+    /*
+    |---------------------------------
+    | decrypt ค่าที่อ่านจาก DB
+    | - ค่าที่ไม่ใช่รูปแบบ envelope = ข้อมูลก่อนเปิดการเข้ารหัส ---> คืนตามเดิม (reencrypt จะเข้ารหัสให้)
+    |   ยกเว้น ENCRYPTION_ALLOW_PLAINTEXT=false ---> error
+    | - ขึ้นต้นด้วย MAGIC แต่อ่าน key id ไม่ได้ = ถูกตัด ---> error (ไม่คืนเป็น plain text)
+    |---------------------------------
+    */
+    pub fn decrypt(&self, data: &[u8], context: &str) -> AppResult<Vec<u8>> {
+        if !data.starts_with(MAGIC) {
+            if !self.allow_plaintext {
+                return Err(AppError::InternalError(format!(
+                    "unencrypted value for {context} (ENCRYPTION_ALLOW_PLAINTEXT=false), run `authrs reencrypt`"
+                )));
+            }
+
+            warn!(context, "unencrypted value read, run `authrs reencrypt`");
+            return Ok(data.to_vec());
+        }
+
+        let kid = Self::key_id(data)
+            .ok_or_else(|| AppError::InternalError("encrypted value is truncated".into()))?;
+
+        let kek = self
+            .keys
+            .get(kid)
+            .ok_or_else(|| AppError::InternalError(format!("unknown encryption key id: {kid}")))?;
+
+        let rest = &data[MAGIC.len() + 1 + kid.len()..];
+        if rest.len() < 2 * NONCE_LEN + WRAPPED_KEY_LEN {
+            return Err(AppError::InternalError("encrypted value is truncated".into()));
+        }
+
+        let (wrap_nonce, rest) = rest.split_at(NONCE_LEN);
+        let (wrapped, rest) = rest.split_at(WRAPPED_KEY_LEN);
+        let (data_nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let dek = open(kek, wrap_nonce, wrapped, &[kid.as_bytes(), context.as_bytes()].concat())?;
+        open(&dek, data_nonce, ciphertext, context.as_bytes())
+    }
+
+    // เข้ารหัสใหม่ด้วย key ปัจจุบัน (ใช้ใน `authrs reencrypt`)
+    // - ใช้ key ปัจจุบันอยู่แล้ว ---> None (ไม่ต้องเขียนทับ)
+    // - plain text ---> เข้ารหัสให้เสมอ แม้ ENCRYPTION_ALLOW_PLAINTEXT=false
+    pub fn reencrypt(&self, data: &[u8], context: &str) -> AppResult<Option<Vec<u8>>> {
+        let plaintext = match Self::key_id(data) {
+            Some(kid) if kid == self.active => return Ok(None),
+            None if !data.starts_with(MAGIC) => data.to_vec(),
+            _ => self.decrypt(data, context)?,
+        };
+
+        self.encrypt(&plaintext, context).map(Some)
+    }
+
+    // ข้อความ (เก็บใน JSON) เช่น state ของ ceremony
+    pub fn encrypt_string(&self, plaintext: &str, context: &str) -> AppResult<String> {
+        Ok(STANDARD.encode(self.encrypt(plaintext.as_bytes(), context)?))
+    }
+
+    pub fn decrypt_string(&self, encoded: &str, context: &str) -> AppResult<String> {
+        let plain = self.decrypt(&STANDARD.decode(encoded)?, context)?;
+        String::from_utf8(plain).map_err(|_| AppError::InternalError("decrypted value is not UTF-8".into()))
+    }
+}
+
+// context ของคอลัมน์ที่เข้ารหัส (ต้องตรงกันทั้งตอนเขียนและอ่าน)
+pub fn column_context(table: &str, column: &str, id: impl std::fmt::Display) -> String {
+    format!("{table}.{column}:{id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTEXT: &str = "users.mfa_totp_secret:00000000-0000-0000-0000-000000000001";
+
+    fn encryption(keys: &[(&str, u8)], active: &str) -> Encryption {
+        let keys = keys.iter().map(|(kid, b)| (kid.to_string(), [*b; KEY_LEN])).collect();
+        Encryption::new(keys, active.into()).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let enc = encryption(&[("k1", 1)], "k1");
+
+        let data = enc.encrypt(b"JBSWY3DPEHPK3PXP", CONTEXT).unwrap();
+        assert_eq!(Encryption::key_id(&data), Some("k1"));
+        assert_eq!(enc.decrypt(&data, CONTEXT).unwrap(), b"JBSWY3DPEHPK3PXP");
+
+        let text = enc.encrypt_string("ceremony state", CONTEXT).unwrap();
+        assert_eq!(enc.decrypt_string(&text, CONTEXT).unwrap(), "ceremony state");
+    }
+
+    // ย้ายค่าไปแถว / คอลัมน์อื่น ---> decrypt ไม่ผ่าน
+    #[test]
+    fn wrong_context_fails() {
+        let enc = encryption(&[("k1", 1)], "k1");
+        let data = enc.encrypt(b"secret", CONTEXT).unwrap();
+
+        assert!(enc.decrypt(&data, "users.mfa_totp_secret:00000000-0000-0000-0000-000000000002").is_err());
+    }
+
+    #[test]
+    fn tampered_or_truncated_fails() {
+        let enc = encryption(&[("k1", 1)], "k1");
+        let data = enc.encrypt(b"secret", CONTEXT).unwrap();
+
+        // ทุก byte หลัง header (nonce, DEK ที่เข้ารหัส, ciphertext, tag)
+        for i in MAGIC.len() + 1 + 2..data.len() {
+            let mut tampered = data.clone();
+            tampered[i] ^= 0x01;
+            assert!(enc.decrypt(&tampered, CONTEXT).is_err(), "flipped byte {i} accepted");
+        }
+
+        // ตัดที่ความยาวไหนก็ไม่ panic และไม่ถูกคืนเป็น plain text
+        for len in MAGIC.len()..data.len() {
+            assert!(enc.decrypt(&data[..len], CONTEXT).is_err(), "truncated to {len} accepted");
+        }
+    }
+
+    #[test]
+    fn unknown_key_id_fails() {
+        let data = encryption(&[("k1", 1)], "k1").encrypt(b"secret", CONTEXT).unwrap();
+        assert!(encryption(&[("k2", 2)], "k2").decrypt(&data, CONTEXT).is_err());
+
+        // kid เดียวกันแต่ key ไม่ตรง
+        assert!(encryption(&[("k1", 9)], "k1").decrypt(&data, CONTEXT).is_err());
+    }
+
+    // rotate: key เก่ายังอยู่ในรายการ ---> ค่าเก่ายัง decrypt ได้ ค่าใหม่ใช้ key ใหม่
+    #[test]
+    fn old_key_decrypts_after_rotation() {
+        let old = encryption(&[("k1", 1)], "k1").encrypt(b"secret", CONTEXT).unwrap();
+        let rotated = encryption(&[("k1", 1), ("k2", 2)], "k2");
+
+        assert_eq!(rotated.decrypt(&old, CONTEXT).unwrap(), b"secret");
+        assert_eq!(Encryption::key_id(&rotated.encrypt(b"secret", CONTEXT).unwrap()), Some("k2"));
+    }
+
+    #[test]
+    fn reencrypt_skips_active_key() {
+        let rotated = encryption(&[("k1", 1), ("k2", 2)], "k2");
+
+        let current = rotated.encrypt(b"secret", CONTEXT).unwrap();
+        assert!(rotated.reencrypt(&current, CONTEXT).unwrap().is_none());
+
+        let old = encryption(&[("k1", 1)], "k1").encrypt(b"secret", CONTEXT).unwrap();
+        let new = rotated.reencrypt(&old, CONTEXT).unwrap().expect("old key is rewritten");
+        assert_eq!(Encryption::key_id(&new), Some("k2"));
+        assert_eq!(rotated.decrypt(&new, CONTEXT).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn plaintext_only_when_allowed() {
+        let mut enc = encryption(&[("k1", 1)], "k1");
+        assert_eq!(enc.decrypt(b"JBSWY3DPEHPK3PXP", CONTEXT).unwrap(), b"JBSWY3DPEHPK3PXP");
+
+        enc.allow_plaintext = false;
+        assert!(enc.decrypt(b"JBSWY3DPEHPK3PXP", CONTEXT).is_err());
+
+        // reencrypt ยังเข้ารหัสค่าเดิมให้ได้ (ทางเดียวที่จะเลิกใช้ plain text)
+        let data = enc.reencrypt(b"JBSWY3DPEHPK3PXP", CONTEXT).unwrap().unwrap();
+        assert_eq!(enc.decrypt(&data, CONTEXT).unwrap(), b"JBSWY3DPEHPK3PXP");
+    }
+}
@@ -1,12 +1,18 @@
 #![allow(dead_code)]
 
-use axum::http::StatusCode;
+use axum::http::{StatusCode, header};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
-use serde_json::json;
+use axum::extract::rejection::{FormRejection, JsonRejection};
+use jsonwebtoken::errors::ErrorKind;
+use serde::Serialize;
 use thiserror::Error;
 use sqlx::Error as SqlxError;
 use tracing::error;
+use validator::ValidationErrors;
+
+use crate::app::validation::{FieldError, field_errors};
+use crate::middleware::trace::current_trace_id;
 
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -31,6 +37,17 @@ pub enum AppError {
     #[error("JSON decode error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    // body ของ Json extractor ใช้ไม่ได้ (syntax / type / Content-Type / ขนาด)
+    #[error("JSON rejection: {0}")]
+    JsonRejection(#[from] JsonRejection),
+
+    // body ของ Form extractor ใช้ไม่ได้ (ขาด field / Content-Type / ขนาด)
+    #[error("Form rejection: {0}")]
+    FormRejection(#[from] FormRejection),
+
+    #[error("Validation failed: {0}")]
+    Validation(#[from] ValidationErrors),
+
     #[error("Bad request")]
     BadRequest(String),
 
@@ -46,6 +63,47 @@ pub enum AppError {
     #[error("Forbidden")]
     Forbidden,
 
+    // login ผิดทุกกรณี (ไม่มี user / รหัสผิด / ปิดบัญชี / ถูกล็อก) ---> ตอบเหมือนกันหมด
+    #[error("Invalid credentials")]
+    InvalidCredentials,
+
+    #[error("Token revoked")]
+    TokenRevoked,
+
+    #[error("Account disabled")]
+    AccountDisabled,
+
+    // restricted token (ต้องเปลี่ยนรหัสผ่านก่อน) เรียก endpoint อื่น
+    #[error("Password change required")]
+    PasswordChangeRequired,
+
+    #[error("Invalid refresh token")]
+    InvalidRefreshToken,
+
+    #[error("Session expired")]
+    SessionExpired,
+
+    // route สำคัญ: ยืนยันตัวตนนานเกิน max_age (วินาที) / ไม่ได้ใช้วิธีที่กำหนด (RFC 9470)
+    #[error("Insufficient user authentication")]
+    InsufficientUserAuthentication { max_age: i64 },
+
+    #[error("CSRF check failed")]
+    CsrfFailed,
+
+    // ขอรหัส / ลิงก์ทางอีเมลถี่เกินไป
+    #[error("Too many requests")]
+    TooManyRequests,
+
+    // OAuth 2.0 (RFC 6749 section 5.2)
+    #[error("Invalid client")]
+    InvalidClient,
+
+    #[error("Unsupported grant type")]
+    UnsupportedGrantType,
+
+    #[error("Invalid scope")]
+    InvalidScope,
+
     #[error(transparent)]
     JwtError(#[from] jsonwebtoken::errors::Error),
 
@@ -56,29 +114,101 @@ pub enum AppError {
     HmacKeyError(#[from] hmac::digest::InvalidLength),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        // กำหนด (status, code, message) ที่ "ปลอดภัยต่อการส่งกลับ client"
-        let (status, code, message) = match &self {
+/*
+|---------------------------------
+| error code ที่ client ใช้ตัดสินใจได้ (ห้ามเปลี่ยนชื่อ เพิ่มได้อย่างเดียว)
+|
+| code                    status  ความหมาย
+| bad_request             400     request ไม่ถูกต้อง (ดู detail)
+| invalid_json            400     body ไม่ใช่ JSON ที่ถูกต้อง
+| invalid_form            400     body ไม่ใช่ form (application/x-www-form-urlencoded) ที่ถูกต้อง
+| invalid_base64          400     ข้อมูล base64 ไม่ถูกต้อง
+| validation_failed       422     ข้อมูลไม่ผ่าน rule (ดู errors ราย field)
+| payload_too_large       413     body ใหญ่เกินที่กำหนด
+| unsupported_media_type  415     Content-Type ไม่ตรงกับที่ route รับ (application/json / form)
+| unsupported_grant_type  400     grant_type ไม่รองรับ
+| invalid_scope           400     ขอ scope เกินที่ได้รับอนุญาต
+| unauthorized            401     ไม่ได้ส่ง credential มา / ใช้ไม่ได้
+| invalid_credentials     401     login ไม่ผ่าน (ไม่บอกเหตุผล กัน user enumeration)
+| invalid_token           401     access token ผิดรูปแบบ / ลายเซ็นผิด
+| token_expired           401     access token หมดอายุ ---> เรียก /auth/refresh
+| token_revoked           401     token ถูกเพิกถอน (force logout / เปลี่ยนรหัสผ่าน) ---> login ใหม่
+| invalid_refresh_token   401     refresh cookie ไม่มี / ใช้ไม่ได้ ---> login ใหม่
+| session_expired         401     session เกินอายุสูงสุด ---> login ใหม่
+| insufficient_user_authentication 401  route สำคัญ: ต้องยืนยันตัวตนใหม่ (POST /auth/reauth / step-up) แล้วใช้ token ใหม่
+| invalid_client          401     client_id / client_secret ไม่ผ่าน
+| forbidden               403     ไม่มีสิทธิ์
+| account_disabled        403     บัญชีถูกปิดใช้งาน
+| password_change_required 403    ต้องเปลี่ยนรหัสผ่านก่อน (POST /auth/password)
+| csrf_failed             403     CSRF token / Origin ไม่ผ่าน
+| not_found               404     ไม่พบข้อมูล
+| too_many_requests       429     ขอรหัส / ลิงก์ทางอีเมลถี่เกินไป ---> รอแล้วลองใหม่
+| internal_error          500     ข้อผิดพลาดฝั่งเซิร์ฟเวอร์ (รายละเอียดอยู่ใน log)
+|---------------------------------
+*/
+impl AppError {
+    // (status, code, title) ตาม catalog ด้านบน
+    pub fn catalog(&self) -> (StatusCode, &'static str, &'static str) {
+        match self {
             // 4xx แก้ได้
-            AppError::BadRequest(msg) =>
-                (StatusCode::BAD_REQUEST, "bad_request", msg.clone()),
-            AppError::Unauthorized =>
-                (StatusCode::UNAUTHORIZED, "unauthorized", "Unauthorized".into()),
-            AppError::Forbidden =>
-                (StatusCode::FORBIDDEN, "forbidden", "Forbidden".into()),
-            AppError::NotFound =>
-                (StatusCode::NOT_FOUND, "not_found", "Not found".into()),
+            AppError::BadRequest(_) =>
+                (StatusCode::BAD_REQUEST, "bad_request", "Bad request"),
             AppError::JsonError(_) =>
-                (StatusCode::BAD_REQUEST, "invalid_json", "Invalid JSON".into()),
+                (StatusCode::BAD_REQUEST, "invalid_json", "Invalid JSON"),
+            AppError::JsonRejection(JsonRejection::MissingJsonContentType(_)) =>
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", "Unsupported media type"),
+            AppError::JsonRejection(r) if r.status() == StatusCode::PAYLOAD_TOO_LARGE =>
+                (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Payload too large"),
+            AppError::JsonRejection(_) =>
+                (StatusCode::BAD_REQUEST, "invalid_json", "Invalid JSON"),
+            AppError::FormRejection(FormRejection::InvalidFormContentType(_)) =>
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type", "Unsupported media type"),
+            AppError::FormRejection(r) if r.status() == StatusCode::PAYLOAD_TOO_LARGE =>
+                (StatusCode::PAYLOAD_TOO_LARGE, "payload_too_large", "Payload too large"),
+            AppError::FormRejection(_) =>
+                (StatusCode::BAD_REQUEST, "invalid_form", "Invalid form"),
+            AppError::Validation(_) =>
+                (StatusCode::UNPROCESSABLE_ENTITY, "validation_failed", "Validation failed"),
             AppError::Base64DecodeError(_) =>
-                (StatusCode::BAD_REQUEST, "invalid_base64", "Invalid base64".into()),
+                (StatusCode::BAD_REQUEST, "invalid_base64", "Invalid base64"),
+            AppError::UnsupportedGrantType =>
+                (StatusCode::BAD_REQUEST, "unsupported_grant_type", "Unsupported grant type"),
+            AppError::InvalidScope =>
+                (StatusCode::BAD_REQUEST, "invalid_scope", "Invalid scope"),
+            AppError::Unauthorized =>
+                (StatusCode::UNAUTHORIZED, "unauthorized", "Unauthorized"),
+            AppError::InvalidCredentials =>
+                (StatusCode::UNAUTHORIZED, "invalid_credentials", "Invalid credentials"),
+            AppError::JwtError(e) if matches!(e.kind(), ErrorKind::ExpiredSignature) =>
+                (StatusCode::UNAUTHORIZED, "token_expired", "Token expired"),
             AppError::JwtError(_) =>
-                (StatusCode::UNAUTHORIZED, "invalid_token", "Invalid or expired token".into()),
+                (StatusCode::UNAUTHORIZED, "invalid_token", "Invalid token"),
+            AppError::TokenRevoked =>
+                (StatusCode::UNAUTHORIZED, "token_revoked", "Token revoked"),
+            AppError::InvalidRefreshToken =>
+                (StatusCode::UNAUTHORIZED, "invalid_refresh_token", "Invalid refresh token"),
+            AppError::SessionExpired =>
+                (StatusCode::UNAUTHORIZED, "session_expired", "Session expired"),
+            AppError::InsufficientUserAuthentication { .. } =>
+                (StatusCode::UNAUTHORIZED, "insufficient_user_authentication", "Insufficient user authentication"),
+            AppError::InvalidClient =>
+                (StatusCode::UNAUTHORIZED, "invalid_client", "Invalid client"),
+            AppError::Forbidden =>
+                (StatusCode::FORBIDDEN, "forbidden", "Forbidden"),
+            AppError::AccountDisabled =>
+                (StatusCode::FORBIDDEN, "account_disabled", "Account disabled"),
+            AppError::PasswordChangeRequired =>
+                (StatusCode::FORBIDDEN, "password_change_required", "Password change required"),
+            AppError::CsrfFailed =>
+                (StatusCode::FORBIDDEN, "csrf_failed", "CSRF check failed"),
+            AppError::NotFound =>
+                (StatusCode::NOT_FOUND, "not_found", "Not found"),
+            AppError::TooManyRequests =>
+                (StatusCode::TOO_MANY_REQUESTS, "too_many_requests", "Too many requests"),
 
             // กรณี SQLx: แยก RowNotFound ---> 404
-            AppError::SqlxError(e) if matches!(e, SqlxError::RowNotFound) =>
-                (StatusCode::NOT_FOUND, "not_found", "Not found".into()),
+            AppError::SqlxError(SqlxError::RowNotFound) =>
+                (StatusCode::NOT_FOUND, "not_found", "Not found"),
 
             // ที่เหลือถือเป็น internal ทั้งหมด
             AppError::EnvVarError(_)
@@ -89,7 +219,57 @@ impl IntoResponse for AppError {
             | AppError::Argon2Error(_)
             | AppError::HmacKeyError(_)
             | AppError::InternalError(_) =>
-                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Internal server error".into()),
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal_error", "Internal server error"),
+        }
+    }
+
+    // token ใช้ไม่ได้ (ไม่ใช่ความผิดพลาดของระบบ) ---> introspection ตอบ active=false
+    pub fn is_token_rejection(&self) -> bool {
+        matches!(
+            self,
+            AppError::Unauthorized
+                | AppError::JwtError(_)
+                | AppError::TokenRevoked
+                | AppError::AccountDisabled
+        )
+    }
+}
+
+// RFC 7807 (application/problem+json)
+#[derive(Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: &'static str,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>,
+}
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, code, title) = self.catalog();
+
+        // detail ส่งกลับเฉพาะข้อความที่เราเขียนเอง (ไม่ส่ง error ภายใน)
+        let detail = match &self {
+            AppError::BadRequest(msg) => Some(msg.clone()),
+            AppError::InsufficientUserAuthentication { max_age } =>
+                Some(format!("re-authenticate within the last {max_age} seconds")),
+            AppError::JsonRejection(r) if status == StatusCode::BAD_REQUEST => Some(r.body_text()),
+            AppError::FormRejection(r) if status == StatusCode::BAD_REQUEST => Some(r.body_text()),
+            _ => None,
+        };
+
+        let errors = match &self {
+            AppError::Validation(e) => Some(field_errors(e)),
+            _ => None,
         };
 
         // log รายละเอียดจริง ๆ ฝั่งเซิร์ฟเวอร์
@@ -97,10 +277,30 @@ impl IntoResponse for AppError {
             error!(error = ?self, "internal error");
         }
 
-        let body = Json(json!({
-            "error": { "code": code, "message": message }
-        }));
+        let problem = Problem {
+            kind: format!("urn:problem-type:authrs:{code}"),
+            title,
+            status: status.as_u16(),
+            detail,
+            code,
+            trace_id: current_trace_id(),
+            errors,
+        };
+
+        let mut res = (
+            status,
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            Json(problem),
+        ).into_response();
 
-        (status, body).into_response()
+        // RFC 9470: บอก client ว่าต้องยืนยันตัวตนใหม่ภายในกี่วินาที
+        if let AppError::InsufficientUserAuthentication { max_age } = &self {
+            let value = format!(r#"Bearer error="insufficient_user_authentication", max_age={max_age}"#);
+            if let Ok(value) = value.parse() {
+                res.headers_mut().insert(header::WWW_AUTHENTICATE, value);
+            }
+        }
+
+        res
     }
-}
\ No newline at end of file
+}
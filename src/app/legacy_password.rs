@@ -0,0 +1,148 @@
+use argon2::password_hash::{PasswordHash, PasswordVerifier};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use base64ct::{Base64Unpadded, Encoding};
+use pbkdf2::Pbkdf2;
+use scrypt::Scrypt;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+
+/*
+|---------------------------------
+| hash จากระบบเก่าที่ยัง verify ได้ (import มาแล้วรอ rehash เป็น Argon2id ตอน login)
+| - bcrypt         $2a$ / $2b$ / $2x$ / $2y$
+| - scrypt (PHC)   $scrypt$ln=..,r=..,p=..$salt$hash
+| - PBKDF2-SHA256  $pbkdf2-sha256$i=..$salt$hash (PHC)
+|                  pbkdf2_sha256$<iterations>$<salt>$<base64 hash> (รูปแบบ Django)
+|---------------------------------
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegacyScheme {
+    Bcrypt,
+    Scrypt,
+    Pbkdf2Sha256,
+    DjangoPbkdf2Sha256,
+}
+
+impl LegacyScheme {
+    pub fn detect(hash: &str) -> Option<Self> {
+        if ["$2a$", "$2b$", "$2x$", "$2y$"].iter().any(|p| hash.starts_with(p)) {
+            Some(Self::Bcrypt)
+        } else if hash.starts_with("$scrypt$") {
+            Some(Self::Scrypt)
+        } else if hash.starts_with("$pbkdf2-sha256$") {
+            Some(Self::Pbkdf2Sha256)
+        } else if hash.starts_with("pbkdf2_sha256$") {
+            Some(Self::DjangoPbkdf2Sha256)
+        } else {
+            None
+        }
+    }
+
+    pub fn verify(self, password: &str, hash: &str) -> AppResult<bool> {
+        match self {
+            Self::Bcrypt => Ok(bcrypt::verify(password, hash).unwrap_or(false)),
+            Self::Scrypt => {
+                let parsed = PasswordHash::new(hash)?;
+                Ok(Scrypt.verify_password(password.as_bytes(), &parsed).is_ok())
+            }
+            Self::Pbkdf2Sha256 => {
+                let parsed = PasswordHash::new(hash)?;
+                Ok(Pbkdf2.verify_password(password.as_bytes(), &parsed).is_ok())
+            }
+            Self::DjangoPbkdf2Sha256 => {
+                let phc = django_to_phc(hash)?;
+                let parsed = PasswordHash::new(&phc)?;
+                Ok(Pbkdf2.verify_password(password.as_bytes(), &parsed).is_ok())
+            }
+        }
+    }
+}
+
+// hash ที่รองรับทั้งหมด (Argon2 + legacy) ใช้ตรวจตอน import
+pub fn is_supported_hash(hash: &str) -> bool {
+    if LegacyScheme::detect(hash).is_some() {
+        return true;
+    }
+
+    PasswordHash::new(hash).is_ok_and(|h| h.algorithm.as_str().starts_with("argon2"))
+}
+
+// Django: salt เป็น string ใช้ตรง ๆ, hash เป็น base64 มาตรฐาน ---> แปลงเป็น PHC ให้ Pbkdf2 ตรวจ
+// (เทียบผลแบบ constant-time อยู่ใน password-hash)
+fn django_to_phc(hash: &str) -> AppResult<String> {
+    let invalid = || AppError::BadRequest("invalid pbkdf2_sha256 hash".into());
+
+    let mut parts = hash.splitn(4, '$');
+    let (_, iterations, salt, digest) = (
+        parts.next().ok_or_else(invalid)?,
+        parts.next().ok_or_else(invalid)?,
+        parts.next().ok_or_else(invalid)?,
+        parts.next().ok_or_else(invalid)?,
+    );
+
+    let iterations: u32 = iterations.parse().map_err(|_| invalid())?;
+    let digest = STANDARD.decode(digest)?;
+
+    Ok(format!(
+        "$pbkdf2-sha256$i={iterations},l={}${}${}",
+        digest.len(),
+        Base64Unpadded::encode_string(salt.as_bytes()),
+        Base64Unpadded::encode_string(&digest),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // vector จาก test suite ของ OpenBSD / crypt_blowfish
+    const BCRYPT: &str = "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW";
+
+    // สร้างด้วย Python hashlib (รหัส "correct horse")
+    const SCRYPT: &str = "$scrypt$ln=10,r=8,p=1$c2FsdHNhbHRzYWx0c2FsdA$A9lBa6RTbfBovWqamVIqXKovIl4Vk6OZyVojLJmYmSI";
+    const PBKDF2: &str = "$pbkdf2-sha256$i=1000,l=32$c2FsdHNhbHRzYWx0c2FsdA$BBs+1+PaslLtBPULUr8/lQicvVuHiEPMz0i8MjLCbzM";
+    const DJANGO: &str = "pbkdf2_sha256$1000$seasalt42$DLjCn+VwiEMemQXgu8JpFEayHo19oxLeCyxZCF+tuek=";
+    const DJANGO_AS_PHC: &str = "$pbkdf2-sha256$i=1000,l=32$c2Vhc2FsdDQy$DLjCn+VwiEMemQXgu8JpFEayHo19oxLeCyxZCF+tuek";
+
+    fn check(hash: &str, scheme: LegacyScheme, password: &str) {
+        assert_eq!(LegacyScheme::detect(hash), Some(scheme));
+        assert!(scheme.verify(password, hash).unwrap(), "{scheme:?} rejects the right password");
+        assert!(!scheme.verify("wrong password", hash).unwrap(), "{scheme:?} accepts a wrong password");
+    }
+
+    #[test]
+    fn bcrypt_vector() {
+        check(BCRYPT, LegacyScheme::Bcrypt, "U*U");
+    }
+
+    #[test]
+    fn scrypt_vector() {
+        check(SCRYPT, LegacyScheme::Scrypt, "correct horse");
+    }
+
+    #[test]
+    fn pbkdf2_phc_vector() {
+        check(PBKDF2, LegacyScheme::Pbkdf2Sha256, "correct horse");
+    }
+
+    #[test]
+    fn django_pbkdf2_vector() {
+        check(DJANGO, LegacyScheme::DjangoPbkdf2Sha256, "correct horse");
+    }
+
+    #[test]
+    fn django_hash_converts_to_phc() {
+        assert_eq!(django_to_phc(DJANGO).unwrap(), DJANGO_AS_PHC);
+        assert!(django_to_phc("pbkdf2_sha256$abc$salt$AAAA").is_err());
+        assert!(django_to_phc("pbkdf2_sha256$1000$salt").is_err());
+    }
+
+    #[test]
+    fn argon2_is_not_legacy() {
+        let hash = "$argon2id$v=19$m=19456,t=2,p=1$c2FsdHNhbHRzYWx0c2FsdA$BBs+1+PaslLtBPULUr8/lQicvVuHiEPMz0i8MjLCbzM";
+        assert_eq!(LegacyScheme::detect(hash), None);
+        assert!(is_supported_hash(hash));
+        assert!(!is_supported_hash("md5$abc"));
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/*
+|---------------------------------
+| สถานะของ process (ใช้กับ /healthz, /readyz)
+| - shutting_down = true ตั้งแต่เริ่ม graceful shutdown ---> /readyz ตอบ 503
+|---------------------------------
+*/
+#[derive(Debug)]
+pub struct Lifecycle {
+    started_at: Instant,
+    shutting_down: AtomicBool,
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            shutting_down: AtomicBool::new(false),
+        }
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// (table, column) ที่ต้องมีใน DB ก่อนรับ traffic
+// - เพิ่มรายการที่นี่ทุกครั้งที่มีไฟล์ใน archive/database เพิ่ม
+pub const REQUIRED_SCHEMA: &[(&str, &str)] = &[
+    ("users", "token_version"),
+    ("users", "password_changed_at"),
+    ("users", "must_change_password"),
+    ("users", "mfa_totp_last_step"),
+    ("refresh_tokens", "token_hash"),
+    ("refresh_tokens", "session_started_at"),
+    ("refresh_tokens", "remember_me"),
+    ("refresh_tokens", "amr"),
+    ("oauth_clients", "client_secret_hash"),
+    ("oauth_clients", "token_version"),
+    ("password_history", "password_hash"),
+    ("webauthn_credentials", "passkey"),
+    ("auth_ceremonies", "state"),
+    ("auth_ceremonies", "amr"),
+    ("mfa_recovery_codes", "code_hash"),
+    ("audit_log", "action"),
+    ("magic_link_tokens", "nonce_hash"),
+    ("email_otp_codes", "code_hash"),
+];
@@ -0,0 +1,36 @@
+use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::app::{error::AppError, result::AppResult};
+use crate::utils::env::env_string;
+
+/*
+|---------------------------------
+| ติดตั้ง tracing subscriber
+| - LOG_FORMAT: pretty (ค่าเริ่มต้นตอน dev) / json (ค่าเริ่มต้นตอน production)
+| - RUST_LOG:   filter แบบ EnvFilter (ค่าเริ่มต้น "info,sqlx=warn")
+|---------------------------------
+*/
+pub fn init(production: bool) -> AppResult<()> {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new("info,sqlx=warn"));
+
+    let default_format = if production { "json" } else { "pretty" };
+    let format = env_string("LOG_FORMAT", default_format).to_ascii_lowercase();
+
+    let registry = tracing_subscriber::registry().with(filter);
+
+    // OTLP export (feature "otel")
+    #[cfg(feature = "otel")]
+    let registry = registry.with(crate::app::telemetry::layer()?);
+
+    let result = match format.as_str() {
+        "json" => registry
+            .with(fmt::layer().json().with_current_span(true).with_span_list(false))
+            .try_init(),
+        "pretty" => registry.with(fmt::layer().pretty()).try_init(),
+        "compact" => registry.with(fmt::layer().compact()).try_init(),
+        other => return Err(AppError::BadRequest(format!("invalid LOG_FORMAT: {other}"))),
+    };
+
+    result.map_err(|e| AppError::InternalError(format!("tracing init: {e}")))
+}
@@ -0,0 +1,43 @@
+use chrono::Duration;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::utils::env::{env_i64, env_string};
+
+/*
+|---------------------------------
+| นโยบายลิงก์ login ทางอีเมล
+| - MAGIC_LINK_URL          หน้า frontend ที่รับ ?token=... แล้ว POST /auth/magic-link/consume
+| - MAGIC_LINK_TTL_SECS     อายุลิงก์ (ค่าเริ่มต้น 900)
+| - MAGIC_LINK_MAX_PER_EMAIL / MAGIC_LINK_WINDOW_SECS  จำนวนลิงก์ต่ออีเมลในช่วงเวลา (5 / 3600)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct MagicLinkPolicy {
+    pub url: String,
+    pub ttl: Duration,
+    pub max_per_email: i64,
+    pub window: Duration,
+}
+
+impl MagicLinkPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        let url = env_string("MAGIC_LINK_URL", "http://localhost:3000/magic-link");
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(AppError::BadRequest(format!("invalid MAGIC_LINK_URL: {url}")));
+        }
+
+        Ok(Self {
+            url,
+            ttl: Duration::seconds(env_i64("MAGIC_LINK_TTL_SECS", 900)?.clamp(60, 3600)),
+            max_per_email: env_i64("MAGIC_LINK_MAX_PER_EMAIL", 5)?.clamp(1, 100),
+            window: Duration::seconds(env_i64("MAGIC_LINK_WINDOW_SECS", 3600)?.clamp(60, 86_400)),
+        })
+    }
+
+    // ลิงก์ที่ส่งในอีเมล (token เป็น base64url ไม่ต้อง encode เพิ่ม)
+    pub fn link(&self, token: &str) -> String {
+        let sep = if self.url.contains('?') { '&' } else { '?' };
+        format!("{}{sep}token={token}", self.url)
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::utils::env::env_string;
+
+// ไม่ derive Debug กันลิงก์ login หลุดลง log
+#[derive(Clone, Serialize)]
+pub struct Email {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> AppResult<()>;
+}
+
+// dev: เขียนอีเมลลง log แทนการส่งจริง
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> AppResult<()> {
+        info!(to = %email.to, subject = %email.subject, body = %email.text, "mail (log mailer)");
+        Ok(())
+    }
+}
+
+// POST JSON {from, to, subject, text} ไปที่ service ส่งเมล (relay ภายใน / provider ที่รับ webhook)
+pub struct WebhookMailer {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+#[async_trait]
+impl Mailer for WebhookMailer {
+    async fn send(&self, email: Email) -> AppResult<()> {
+        let mut req = self.client.post(&self.url).json(&email);
+        if let Some(token) = &self.token {
+            req = req.bearer_auth(token);
+        }
+
+        let res = req
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("mailer: {e}")))?;
+
+        if !res.status().is_success() {
+            return Err(AppError::InternalError(format!("mailer: webhook returned {}", res.status())));
+        }
+
+        Ok(())
+    }
+}
+
+/*
+|---------------------------------
+| เลือก Mailer จาก MAILER
+| - log (ค่าเริ่มต้นตอน development) / webhook / none (ค่าเริ่มต้นตอน production)
+| - webhook: MAILER_WEBHOOK_URL, MAILER_WEBHOOK_TOKEN (ไม่บังคับ)
+| - none ---> endpoint ที่ต้องส่งเมลถูกปิด
+| - production ห้ามใช้ log (ลิงก์ login จะอยู่ใน log)
+|---------------------------------
+*/
+pub fn from_env(production: bool) -> AppResult<Option<Arc<dyn Mailer>>> {
+    let default = if production { "none" } else { "log" };
+
+    match env_string("MAILER", default).to_ascii_lowercase().as_str() {
+        "none" => Ok(None),
+        "log" if production => Err(AppError::BadRequest("MAILER=log is not allowed in production".into())),
+        "log" => Ok(Some(Arc::new(LogMailer))),
+        "webhook" => {
+            let url = env_string("MAILER_WEBHOOK_URL", "");
+            if url.is_empty() {
+                return Err(AppError::BadRequest("MAILER_WEBHOOK_URL is not set".into()));
+            }
+
+            let token = Some(env_string("MAILER_WEBHOOK_TOKEN", "")).filter(|t| !t.is_empty());
+            let client = reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()
+                .map_err(|e| AppError::InternalError(format!("mailer: {e}")))?;
+
+            Ok(Some(Arc::new(WebhookMailer { client, url, token })))
+        }
+        other => Err(AppError::BadRequest(format!("invalid MAILER: {other}"))),
+    }
+}
+
+// ส่งใน background ---> เวลาตอบของ endpoint ไม่ขึ้นกับ mail server (ส่งไม่สำเร็จแค่ log)
+pub fn send_in_background(mailer: Arc<dyn Mailer>, email: Email, user_id: Uuid, kind: &'static str) {
+    tokio::spawn(async move {
+        if let Err(e) = mailer.send(email).await {
+            error!(%user_id, kind, error = %e, "failed to send email");
+        }
+    });
+}
+
+// ผู้ส่ง (From) ของอีเมลทุกฉบับ
+pub fn sender() -> String {
+    env_string("MAILER_FROM", "no-reply@localhost")
+}
@@ -0,0 +1,132 @@
+/*
+|---------------------------------
+| ตัวนับ / histogram ของระบบ auth (Prometheus)
+| - เปิดด้วย cargo feature "metrics"
+| - ปิด feature ---> ตัวนับใน handler เป็น no-op (จุดที่เรียกไม่ต้องใส่ cfg)
+|---------------------------------
+*/
+#[cfg(feature = "metrics")]
+use std::{sync::OnceLock, time::Duration};
+
+#[cfg(feature = "metrics")]
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use crate::app::result::AppResult;
+
+#[cfg(feature = "metrics")]
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+// ติดตั้ง recorder ครั้งเดียวตอน start
+pub fn install() -> AppResult<()> {
+    #[cfg(feature = "metrics")]
+    {
+        use crate::app::error::AppError;
+
+        let handle = PrometheusBuilder::new()
+            .set_buckets_for_metric(
+                Matcher::Suffix("duration_seconds".into()),
+                &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0],
+            )
+            .map_err(|e| AppError::InternalError(format!("metrics buckets: {e}")))?
+            .install_recorder()
+            .map_err(|e| AppError::InternalError(format!("metrics recorder: {e}")))?;
+
+        let _ = HANDLE.set(handle);
+    }
+
+    Ok(())
+}
+
+// ข้อความ Prometheus text format (None = ยังไม่ได้ install)
+#[cfg(feature = "metrics")]
+pub fn render() -> Option<String> {
+    HANDLE.get().map(|h| {
+        h.run_upkeep();
+        h.render()
+    })
+}
+
+// outcome: success / invalid_credentials / inactive / locked / not_found / mfa_required / mfa_failed
+pub fn login(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_login_total", "outcome" => outcome).increment(1);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+// บัญชีถูกล็อกจาก failed_login_attempts ครบเกณฑ์
+pub fn lockout() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_lockouts_total").increment(1);
+}
+
+// outcome: rotated / invalid / reuse_detected / inactive / session_expired / password_change_required
+pub fn refresh(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_refresh_total", "outcome" => outcome).increment(1);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+// outcome: sent / unknown_email / rate_limited / consumed / invalid
+pub fn magic_link(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_magic_link_total", "outcome" => outcome).increment(1);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+// outcome: sent / unknown_email / rate_limited / verified / invalid
+pub fn email_otp(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_email_otp_total", "outcome" => outcome).increment(1);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+// outcome: success / invalid_password / invalid_code / second_factor_required / locked
+pub fn reauth(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_reauth_total", "outcome" => outcome).increment(1);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+pub fn logout() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_logout_total").increment(1);
+}
+
+// ผลการตรวจ access token ใน AuthUser (accepted / rejected)
+pub fn access_check(outcome: &'static str) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("auth_access_checks_total", "outcome" => outcome).increment(1);
+
+    #[cfg(not(feature = "metrics"))]
+    let _ = outcome;
+}
+
+// latency ราย route (เรียกจาก middleware)
+#[cfg(feature = "metrics")]
+pub fn http_request(method: &str, path: &str, status: u16, elapsed: Duration) {
+    let labels = [
+        ("method", method.to_string()),
+        ("path", path.to_string()),
+        ("status", status.to_string()),
+    ];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+}
+
+// gauge ของ connection pool (อัปเดตตอนถูก scrape)
+#[cfg(feature = "metrics")]
+pub fn db_pool(size: u32, idle: usize, max: u32) {
+    metrics::gauge!("db_pool_connections").set(size as f64);
+    metrics::gauge!("db_pool_idle_connections").set(idle as f64);
+    metrics::gauge!("db_pool_max_connections").set(max as f64);
+}
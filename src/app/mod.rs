@@ -1,3 +1,25 @@
+pub mod audit;
+pub mod ceremony;
+pub mod client_ip;
+pub mod cookies;
+pub mod cors;
+pub mod email_otp;
+pub mod encryption;
 pub mod error;
+pub mod legacy_password;
+pub mod lifecycle;
+pub mod logging;
+pub mod magic_link;
+pub mod mailer;
+pub mod metrics;
+pub mod password;
+pub mod password_policy;
+pub mod recovery_codes;
 pub mod result;
-pub mod state;
\ No newline at end of file
+pub mod session;
+pub mod state;
+pub mod telemetry;
+pub mod totp;
+pub mod user_cache;
+pub mod validation;
+pub mod webauthn;
\ No newline at end of file
@@ -0,0 +1,282 @@
+use argon2::{
+    Algorithm, Argon2, KeyId, Params, ParamsBuilder, PasswordHash, PasswordHasher as _, PasswordVerifier, Version,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{DateTime, NaiveDate, Utc};
+use tracing::warn;
+
+use crate::app::error::AppError;
+use crate::app::legacy_password::LegacyScheme;
+use crate::app::result::AppResult;
+use crate::utils::env::{env_i64, env_string};
+
+// ค่าเริ่มต้นตาม OWASP (Argon2id, m=19 MiB, t=2, p=1)
+pub const DEFAULT_M_COST: u32 = 19 * 1024;
+pub const DEFAULT_T_COST: u32 = 2;
+pub const DEFAULT_P_COST: u32 = 1;
+
+// ผล verify: ok + hash นี้ควร rehash ด้วย params ปัจจุบันไหม
+#[derive(Debug, Clone, Copy)]
+pub struct Verified {
+    pub ok: bool,
+    pub needs_rehash: bool,
+}
+
+// pepper + id ที่บันทึกไว้ใน hash (PHC keyid) ---> รู้ว่า hash ไหนใช้ pepper ตัวไหน
+#[derive(Clone)]
+pub struct Pepper {
+    pub id: KeyId,
+    pub secret: Vec<u8>,
+    // ช่วงย้ายข้อมูล: hash ที่ใช้ pepper แต่สร้างก่อนมี keyid ---> ลอง pepper ด้วยจนถึงวันนี้
+    pub untagged_until: Option<DateTime<Utc>>,
+}
+
+/*
+|---------------------------------
+| Argon2id สำหรับรหัสผ่านของ user
+| - ARGON2_M_COST_KIB / ARGON2_T_COST / ARGON2_P_COST
+| - PASSWORD_PEPPER (base64, ไม่บังคับ) ใช้เป็น secret ของ Argon2 (ไม่ได้เก็บใน DB)
+|   PASSWORD_PEPPER_ID (ไม่เกิน 8 ตัวอักษร, ค่าเริ่มต้น "1") เก็บเป็น keyid ใน hash
+|   PASSWORD_PEPPER_UNTAGGED_UNTIL (YYYY-MM-DD, ไม่บังคับ) ช่วงย้าย hash ที่ใช้ pepper แต่ยังไม่มี keyid
+| - hash เก่าที่ params ไม่ตรง / ไม่มี pepper / legacy (bcrypt ฯลฯ) ---> verify ได้ แล้วบอกให้ rehash
+|---------------------------------
+*/
+#[derive(Clone)]
+pub struct PasswordHasher {
+    params: Params,
+    pepper: Option<Pepper>,
+    // hash ของรหัสสุ่ม ใช้ verify แทนตอนไม่พบ user (เวลาเท่ากับ user จริง)
+    dummy_hash: String,
+}
+
+impl PasswordHasher {
+    pub fn new(m_cost: u32, t_cost: u32, p_cost: u32, pepper: Option<Pepper>) -> AppResult<Self> {
+        let mut builder = ParamsBuilder::new();
+        builder.m_cost(m_cost).t_cost(t_cost).p_cost(p_cost);
+
+        // hash ที่ใช้ pepper มี keyid ติดไปด้วย
+        if let Some(pepper) = &pepper {
+            builder.keyid(pepper.id);
+        }
+
+        let params = builder
+            .build()
+            .map_err(|e| AppError::BadRequest(format!("invalid Argon2 params: {e}")))?;
+
+        let mut hasher = Self { params, pepper, dummy_hash: String::new() };
+
+        let mut random = [0u8; 32];
+        getrandom::fill(&mut random)
+            .map_err(|e| AppError::InternalError(format!("RNG failed: {:?}", e)))?;
+        hasher.dummy_hash = hasher.hash(&STANDARD.encode(random))?;
+
+        Ok(hasher)
+    }
+
+    pub fn from_env() -> AppResult<Self> {
+        let m_cost = env_i64("ARGON2_M_COST_KIB", DEFAULT_M_COST as i64)?;
+        let t_cost = env_i64("ARGON2_T_COST", DEFAULT_T_COST as i64)?;
+        let p_cost = env_i64("ARGON2_P_COST", DEFAULT_P_COST as i64)?;
+
+        let pepper = match std::env::var("PASSWORD_PEPPER") {
+            Ok(s) if !s.trim().is_empty() => {
+                let id = env_string("PASSWORD_PEPPER_ID", "1");
+                let id = KeyId::new(id.as_bytes())
+                    .map_err(|e| AppError::BadRequest(format!("invalid PASSWORD_PEPPER_ID: {e}")))?;
+
+                let untagged_until = match std::env::var("PASSWORD_PEPPER_UNTAGGED_UNTIL") {
+                    Ok(d) if !d.trim().is_empty() => Some(
+                        NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")
+                            .map_err(|_| AppError::BadRequest(format!("invalid PASSWORD_PEPPER_UNTAGGED_UNTIL: {d}")))?
+                            .and_time(chrono::NaiveTime::MIN)
+                            .and_utc(),
+                    ),
+                    _ => None,
+                };
+
+                Some(Pepper { id, secret: STANDARD.decode(s.trim())?, untagged_until })
+            }
+            _ => None,
+        };
+
+        let to_u32 = |name: &str, v: i64| {
+            u32::try_from(v).map_err(|_| AppError::BadRequest(format!("invalid {name}: {v}")))
+        };
+
+        Self::new(
+            to_u32("ARGON2_M_COST_KIB", m_cost)?,
+            to_u32("ARGON2_T_COST", t_cost)?,
+            to_u32("ARGON2_P_COST", p_cost)?,
+            pepper,
+        )
+    }
+
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+
+    fn argon2(&self, with_pepper: bool) -> AppResult<Argon2<'_>> {
+        match (&self.pepper, with_pepper) {
+            (Some(pepper), true) => Argon2::new_with_secret(
+                &pepper.secret,
+                Algorithm::Argon2id,
+                Version::V0x13,
+                self.params.clone(),
+            )
+            .map_err(|e| AppError::BadRequest(format!("invalid PASSWORD_PEPPER: {e}"))),
+            _ => Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params.clone())),
+        }
+    }
+
+    pub fn hash(&self, password: &str) -> AppResult<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        Ok(self.argon2(true)?.hash_password(password.as_bytes(), &salt)?.to_string())
+    }
+
+    /*
+    |---------------------------------
+    | verify กับ hash ใน DB (Argon2 หนึ่งรอบเสมอ ไม่ลองหลายแบบ)
+    | - ไม่มี keyid = hash ที่ไม่ใช้ pepper ---> ผ่านแล้ว rehash ถ้าตอนนี้ตั้ง pepper ไว้
+    |   (ยกเว้นก่อน PASSWORD_PEPPER_UNTAGGED_UNTIL: ลองแบบมี pepper ก่อน ---> สองรอบ)
+    | - keyid ตรงกับ PASSWORD_PEPPER_ID ---> verify ด้วย pepper
+    | - keyid อื่น (pepper ที่ไม่ได้ตั้งไว้แล้ว) ---> ไม่ผ่าน (เผา dummy ให้เวลาเท่ากัน)
+    |---------------------------------
+    */
+    pub fn verify(&self, password: &str, hash: &str) -> AppResult<Verified> {
+        // hash ที่ import มาจากระบบเก่า (ไม่ใช้ pepper)
+        if let Some(scheme) = LegacyScheme::detect(hash) {
+            let ok = scheme.verify(password, hash)?;
+            return Ok(Verified { ok, needs_rehash: ok });
+        }
+
+        let parsed = PasswordHash::new(hash)?;
+        let keyid = Params::try_from(&parsed)?.keyid().to_vec();
+
+        let with_pepper = match &self.pepper {
+            _ if keyid.is_empty() => false,
+            Some(pepper) if pepper.id.as_bytes() == keyid.as_slice() => true,
+            _ => {
+                warn!("password hash uses a pepper id that is not configured");
+                self.verify_dummy(password)?;
+                return Ok(Verified { ok: false, needs_rehash: false });
+            }
+        };
+
+        // ช่วงย้ายข้อมูล: hash ที่ไม่มี keyid อาจใช้ pepper ---> ผ่าน = rehash ให้มี keyid
+        let migrating = self
+            .pepper
+            .as_ref()
+            .is_some_and(|p| p.untagged_until.is_some_and(|t| Utc::now() < t));
+
+        if !with_pepper
+            && migrating
+            && self.argon2(true)?.verify_password(password.as_bytes(), &parsed).is_ok()
+        {
+            return Ok(Verified { ok: true, needs_rehash: true });
+        }
+
+        if self.argon2(with_pepper)?.verify_password(password.as_bytes(), &parsed).is_err() {
+            return Ok(Verified { ok: false, needs_rehash: false });
+        }
+
+        let needs_rehash = self.is_outdated(&parsed) || (self.pepper.is_some() && !with_pepper);
+
+        Ok(Verified { ok: true, needs_rehash })
+    }
+
+    // เผา Argon2 ให้เท่ากับ verify จริงที่ไม่ผ่าน (ใช้ตอนไม่พบ user / บัญชีถูกล็อก)
+    pub fn verify_dummy(&self, password: &str) -> AppResult<()> {
+        self.verify(password, &self.dummy_hash)?;
+        Ok(())
+    }
+
+    // algorithm / version / m / t / p ไม่ตรงกับ config ปัจจุบัน
+    fn is_outdated(&self, parsed: &PasswordHash<'_>) -> bool {
+        if parsed.algorithm != Algorithm::Argon2id.ident() || parsed.version != Some(Version::V0x13.into()) {
+            return true;
+        }
+
+        match Params::try_from(parsed) {
+            Ok(p) => {
+                p.m_cost() != self.params.m_cost()
+                    || p.t_cost() != self.params.t_cost()
+                    || p.p_cost() != self.params.p_cost()
+            }
+            Err(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // params ต่ำสุดให้เทสต์เร็ว
+    fn hasher(pepper: Option<(&str, &[u8])>) -> PasswordHasher {
+        migrating(pepper, None)
+    }
+
+    fn migrating(pepper: Option<(&str, &[u8])>, untagged_until: Option<DateTime<Utc>>) -> PasswordHasher {
+        let pepper = pepper.map(|(id, secret)| Pepper {
+            id: KeyId::new(id.as_bytes()).unwrap(),
+            secret: secret.to_vec(),
+            untagged_until,
+        });
+        PasswordHasher::new(64, 1, 1, pepper).unwrap()
+    }
+
+    // hash ที่ใช้ pepper แต่ไม่มี keyid (สร้างก่อนมี keyid)
+    fn untagged_peppered_hash(password: &str, secret: &[u8]) -> String {
+        let argon2 = Argon2::new_with_secret(secret, Algorithm::Argon2id, Version::V0x13, Params::new(64, 1, 1, None).unwrap()).unwrap();
+        argon2.hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng)).unwrap().to_string()
+    }
+
+    #[test]
+    fn peppered_hash_records_keyid() {
+        let h = hasher(Some(("k1", b"pepper-one")));
+        let hash = h.hash("correct horse").unwrap();
+
+        // "k1" ใน B64 ของ PHC
+        assert!(hash.contains("keyid=azE"), "{hash}");
+
+        let v = h.verify("correct horse", &hash).unwrap();
+        assert!(v.ok && !v.needs_rehash);
+        assert!(!h.verify("wrong horse", &hash).unwrap().ok);
+    }
+
+    #[test]
+    fn unpeppered_hash_verifies_and_needs_rehash() {
+        let hash = hasher(None).hash("correct horse").unwrap();
+        assert!(!hash.contains("keyid"));
+
+        let v = hasher(Some(("k1", b"pepper-one"))).verify("correct horse", &hash).unwrap();
+        assert!(v.ok && v.needs_rehash);
+
+        let v = hasher(None).verify("correct horse", &hash).unwrap();
+        assert!(v.ok && !v.needs_rehash);
+    }
+
+    // hash ที่มี keyid ไม่ถูกลองแบบไม่มี pepper (และกลับกัน)
+    #[test]
+    fn only_matching_variant_is_tried() {
+        let hash = hasher(Some(("k1", b"pepper-one"))).hash("correct horse").unwrap();
+
+        assert!(!hasher(None).verify("correct horse", &hash).unwrap().ok);
+        assert!(!hasher(Some(("k2", b"pepper-one"))).verify("correct horse", &hash).unwrap().ok);
+        assert!(!hasher(Some(("k1", b"pepper-two"))).verify("correct horse", &hash).unwrap().ok);
+    }
+
+    // ไม่มี keyid แต่ใช้ pepper ---> ผ่านเฉพาะก่อน PASSWORD_PEPPER_UNTAGGED_UNTIL (แล้ว rehash)
+    #[test]
+    fn untagged_peppered_hash_only_during_migration() {
+        let hash = untagged_peppered_hash("correct horse", b"pepper-one");
+        let pepper = Some(("k1", &b"pepper-one"[..]));
+
+        let v = migrating(pepper, Some(Utc::now() + chrono::Duration::days(1))).verify("correct horse", &hash).unwrap();
+        assert!(v.ok && v.needs_rehash);
+
+        assert!(!migrating(pepper, Some(Utc::now() - chrono::Duration::days(1))).verify("correct horse", &hash).unwrap().ok);
+        assert!(!hasher(pepper).verify("correct horse", &hash).unwrap().ok);
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use sha1::{Digest, Sha1};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::utils::env::{env_bool, env_i64, env_list, env_string};
+
+/*
+|---------------------------------
+| รายการรหัสผ่านที่หลุด (รูปแบบ k-anonymity range ของ HIBP)
+| - BREACHED_PASSWORDS_DIR มีไฟล์ชื่อ prefix 5 ตัวแรกของ SHA-1 (เช่น "21BD1" หรือ "21BD1.txt")
+| - แต่ละบรรทัด "<SUFFIX 35 ตัว>:<COUNT>"
+| - อ่านเฉพาะไฟล์ของ prefix นั้นตอนตรวจ (ไม่โหลดทั้งชุดเข้า memory)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct BreachedList {
+    dir: PathBuf,
+    min_count: u64,
+}
+
+impl BreachedList {
+    pub fn open(dir: PathBuf, min_count: u64) -> AppResult<Self> {
+        if !dir.is_dir() {
+            return Err(AppError::BadRequest(format!(
+                "BREACHED_PASSWORDS_DIR is not a directory: {}",
+                dir.display()
+            )));
+        }
+
+        Ok(Self { dir, min_count })
+    }
+
+    pub fn contains(&self, password: &str) -> AppResult<bool> {
+        let digest = Sha1::digest(password.as_bytes());
+        let hex: String = digest.iter().map(|b| format!("{b:02X}")).collect();
+        let (prefix, suffix) = hex.split_at(5);
+
+        let file = [self.dir.join(prefix), self.dir.join(format!("{prefix}.txt"))]
+            .into_iter()
+            .find_map(|p| File::open(p).ok());
+
+        // ไม่มีไฟล์ของ prefix นี้ = ไม่เคยหลุด
+        let Some(file) = file else {
+            return Ok(false);
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            let (hash, count) = line.trim().split_once(':').unwrap_or((line.trim(), "1"));
+
+            if hash.eq_ignore_ascii_case(suffix) {
+                let count: u64 = count.trim().parse().unwrap_or(1);
+                return Ok(count >= self.min_count);
+            }
+        }
+
+        Ok(false)
+    }
+}
+
+/*
+|---------------------------------
+| นโยบายรหัสผ่าน (ใช้ตอนตั้ง / เปลี่ยนรหัส ไม่ใช้ตอน login)
+| - PASSWORD_MIN_LENGTH (12) / PASSWORD_MAX_LENGTH (128, กัน input ยาวเข้า Argon2)
+| - PASSWORD_REQUIRED_CLASSES: lower,upper,digit,symbol (ค่าเริ่มต้นไม่บังคับ)
+| - PASSWORD_MIN_SCORE: คะแนน zxcvbn 0-4 (ค่าเริ่มต้น 3, 0 = ปิด)
+| - PASSWORD_REJECT_PERSONAL: ห้ามมี username / ส่วนหน้า @ ของ email (ค่าเริ่มต้น true)
+| - BREACHED_PASSWORDS_DIR / BREACHED_PASSWORDS_MIN_COUNT
+| - PASSWORD_HISTORY_DEPTH: ห้ามซ้ำกับรหัสกี่อันล่าสุด นับรวมรหัสปัจจุบัน (ค่าเริ่มต้น 5, 0 = ปิด)
+| - PASSWORD_MAX_AGE_DAYS: อายุสูงสุดราย role เช่น "admin:90,user:365" (ไม่ระบุ = ไม่หมดอายุ)
+| - ผิดกี่ข้อก็ส่งกลับครบทุกข้อ (422 ราย rule)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub require_lower: bool,
+    pub require_upper: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub min_score: u8,
+    pub reject_personal: bool,
+    pub breached: Option<BreachedList>,
+    pub history_depth: i64,
+    pub max_age: HashMap<String, Duration>,
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> AppResult<Self> {
+        let min_length = env_i64("PASSWORD_MIN_LENGTH", 12)?.clamp(1, 1024) as usize;
+        let max_length = env_i64("PASSWORD_MAX_LENGTH", 128)?.clamp(min_length as i64, 1024) as usize;
+
+        let classes = env_list("PASSWORD_REQUIRED_CLASSES");
+        for c in &classes {
+            if !matches!(c.as_str(), "lower" | "upper" | "digit" | "symbol") {
+                return Err(AppError::BadRequest(format!("invalid PASSWORD_REQUIRED_CLASSES: {c}")));
+            }
+        }
+        let has = |name: &str| classes.iter().any(|c| c == name);
+
+        let min_score = env_i64("PASSWORD_MIN_SCORE", 3)?.clamp(0, 4) as u8;
+
+        let breached = match env_string("BREACHED_PASSWORDS_DIR", "") {
+            dir if dir.is_empty() => None,
+            dir => Some(BreachedList::open(
+                PathBuf::from(dir),
+                env_i64("BREACHED_PASSWORDS_MIN_COUNT", 1)?.max(1) as u64,
+            )?),
+        };
+
+        let mut max_age = HashMap::new();
+        for entry in env_list("PASSWORD_MAX_AGE_DAYS") {
+            let invalid = || AppError::BadRequest(format!("invalid PASSWORD_MAX_AGE_DAYS: {entry}"));
+            let (role, days) = entry.split_once(':').ok_or_else(invalid)?;
+            let days: i64 = days.trim().parse().map_err(|_| invalid())?;
+
+            // 0 = ไม่หมดอายุ
+            if days > 0 {
+                max_age.insert(role.trim().to_string(), Duration::days(days));
+            }
+        }
+
+        Ok(Self {
+            min_length,
+            max_length,
+            require_lower: has("lower"),
+            require_upper: has("upper"),
+            require_digit: has("digit"),
+            require_symbol: has("symbol"),
+            min_score,
+            reject_personal: env_bool("PASSWORD_REJECT_PERSONAL", true)?,
+            breached,
+            history_depth: env_i64("PASSWORD_HISTORY_DEPTH", 5)?.clamp(0, 50),
+            max_age,
+        })
+    }
+
+    // รหัสผ่านเกินอายุสูงสุดของ role นี้แล้วหรือยัง (set_at = เปลี่ยนล่าสุด หรือวันสมัคร)
+    pub fn is_expired(&self, role: &str, set_at: DateTime<Utc>) -> bool {
+        self.max_age
+            .get(role)
+            .is_some_and(|age| set_at + *age <= Utc::now())
+    }
+
+    /*
+    |---------------------------------
+    | ตรวจรหัสผ่านใหม่
+    | - field: ชื่อ field ใน request ที่จะรายงาน error (เช่น "new_password")
+    | - username / email: ใช้ตรวจข้อมูลส่วนตัว + ป้อนให้ zxcvbn
+    |---------------------------------
+    */
+    pub fn check(
+        &self,
+        field: &'static str,
+        password: &str,
+        username: &str,
+        email: Option<&str>,
+    ) -> AppResult<()> {
+        let mut errors = ValidationErrors::new();
+        let length = password.chars().count();
+
+        if length < self.min_length {
+            let mut e = ValidationError::new("too_short")
+                .with_message(format!("must be at least {} characters", self.min_length).into());
+            e.add_param("min".into(), &self.min_length);
+            errors.add(field, e);
+        }
+
+        // ยาวเกินไม่ต้องตรวจต่อ (ไม่ส่ง input ยาว ๆ เข้า zxcvbn / SHA-1)
+        if length > self.max_length {
+            let mut e = ValidationError::new("too_long")
+                .with_message(format!("must be at most {} characters", self.max_length).into());
+            e.add_param("max".into(), &self.max_length);
+            errors.add(field, e);
+            return Err(errors.into());
+        }
+
+        let classes = [
+            (self.require_lower, "missing_lowercase", "must contain a lowercase letter", password.chars().any(char::is_lowercase)),
+            (self.require_upper, "missing_uppercase", "must contain an uppercase letter", password.chars().any(char::is_uppercase)),
+            (self.require_digit, "missing_digit", "must contain a digit", password.chars().any(|c| c.is_ascii_digit())),
+            (self.require_symbol, "missing_symbol", "must contain a symbol", password.chars().any(|c| !c.is_alphanumeric())),
+        ];
+
+        for (required, code, message, present) in classes {
+            if required && !present {
+                errors.add(field, ValidationError::new(code).with_message(message.into()));
+            }
+        }
+
+        // username / ส่วนหน้า @ ของ email (ตั้งแต่ 3 ตัวขึ้นไป, ไม่สนตัวพิมพ์)
+        let email_local = email.and_then(|e| e.split('@').next()).unwrap_or("");
+        let lower = password.to_lowercase();
+
+        if self.reject_personal {
+            let personal = [
+                ("contains_username", "must not contain your username", username),
+                ("contains_email", "must not contain your email address", email_local),
+            ];
+
+            for (code, message, value) in personal {
+                if value.chars().count() >= 3 && lower.contains(&value.to_lowercase()) {
+                    errors.add(field, ValidationError::new(code).with_message(message.into()));
+                }
+            }
+        }
+
+        if self.min_score > 0 && !password.is_empty() {
+            let estimate = zxcvbn::zxcvbn(password, &[username, email_local]);
+            let score = estimate.score() as u8;
+
+            if score < self.min_score {
+                let message = match estimate.feedback().and_then(|f| f.warning()) {
+                    Some(w) => format!("is too easy to guess: {w}"),
+                    None => "is too easy to guess".to_string(),
+                };
+
+                let mut e = ValidationError::new("too_weak").with_message(message.into());
+                e.add_param("score".into(), &score);
+                e.add_param("min_score".into(), &self.min_score);
+                errors.add(field, e);
+            }
+        }
+
+        if let Some(list) = &self.breached
+            && list.contains(password)?
+        {
+            errors.add(
+                field,
+                ValidationError::new("breached")
+                    .with_message("has appeared in a data breach; choose a different password".into()),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.into())
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use sqlx::{PgConnection, PgPool};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::app::telemetry::db_span;
+use crate::controllers::auth::utils::hash_refresh_token;
+use crate::utils::env::env_i64;
+
+// ตัด 0/o, 1/i/l ที่อ่านสับสนออก
+const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+// 10 ตัวอักษร (~49 bit) แสดงเป็น "xxxxx-xxxxx"
+const CODE_LEN: usize = 10;
+
+fn generate_code() -> AppResult<String> {
+    let mut code = String::with_capacity(CODE_LEN + 1);
+    let mut buf = [0u8; 32];
+
+    // rejection sampling กัน bias (ใช้เฉพาะ byte ที่ < ALPHABET.len() * 8)
+    let limit = (ALPHABET.len() * (256 / ALPHABET.len())) as u8;
+
+    while code.len() < CODE_LEN + 1 {
+        getrandom::fill(&mut buf).map_err(|e| AppError::InternalError(format!("RNG failed: {:?}", e)))?;
+
+        for b in buf.iter().filter(|b| **b < limit) {
+            if code.len() == CODE_LEN / 2 {
+                code.push('-');
+            }
+            if code.len() == CODE_LEN + 1 {
+                break;
+            }
+            code.push(ALPHABET[*b as usize % ALPHABET.len()] as char);
+        }
+    }
+
+    Ok(code)
+}
+
+// ไม่สนขีด / ช่องว่าง / ตัวพิมพ์ที่ user พิมพ์มา
+fn hash(code: &str, secret: &[u8]) -> AppResult<String> {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    hash_refresh_token(&format!("recovery:{normalized}"), secret)
+}
+
+/*
+|---------------------------------
+| สร้างชุดใหม่แทนชุดเดิมทั้งหมด (รหัสเก่าที่ยังไม่ใช้ใช้ไม่ได้อีก)
+| - MFA_RECOVERY_CODE_COUNT (ค่าเริ่มต้น 10)
+| - คืนรหัสจริงให้แสดงครั้งเดียว DB เก็บแค่ hash
+|---------------------------------
+*/
+pub async fn replace(conn: &mut PgConnection, user_id: Uuid, secret: &[u8]) -> AppResult<Vec<String>> {
+    let count = env_i64("MFA_RECOVERY_CODE_COUNT", 10)?.clamp(4, 20) as usize;
+
+    sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = $1", user_id)
+        .execute(&mut *conn)
+        .instrument(db_span("DELETE mfa_recovery_codes"))
+        .await?;
+
+    let mut codes = Vec::with_capacity(count);
+    let mut hashes = Vec::with_capacity(count);
+
+    while codes.len() < count {
+        let code = generate_code()?;
+        let h = hash(&code, secret)?;
+
+        if !hashes.contains(&h) {
+            codes.push(code);
+            hashes.push(h);
+        }
+    }
+
+    sqlx::query!(
+        "INSERT INTO mfa_recovery_codes (user_id, code_hash) SELECT $1, unnest($2::text[])",
+        user_id,
+        &hashes
+    )
+    .execute(&mut *conn)
+    .instrument(db_span("INSERT mfa_recovery_codes"))
+    .await?;
+
+    Ok(codes)
+}
+
+// ใช้รหัส (ครั้งเดียว) ---> true ถ้ารหัสถูกและยังไม่เคยใช้
+pub async fn consume(db: &PgPool, user_id: Uuid, code: &str, secret: &[u8]) -> AppResult<bool> {
+    let used = sqlx::query!(
+        "UPDATE mfa_recovery_codes SET used_at = now()
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL",
+        user_id,
+        hash(code, secret)?
+    )
+    .execute(db)
+    .instrument(db_span("UPDATE mfa_recovery_codes"))
+    .await?
+    .rows_affected();
+
+    Ok(used == 1)
+}
+
+pub async fn remaining(db: &PgPool, user_id: Uuid) -> AppResult<i64> {
+    let n = sqlx::query_scalar!(
+        r#"SELECT count(*) as "n!" FROM mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL"#,
+        user_id
+    )
+    .fetch_one(db)
+    .instrument(db_span("SELECT mfa_recovery_codes"))
+    .await?;
+
+    Ok(n)
+}
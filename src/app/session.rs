@@ -0,0 +1,59 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::app::result::AppResult;
+use crate::utils::env::env_i64;
+
+/*
+|---------------------------------
+| อายุของ refresh token / session
+| - idle: อายุต่อ 1 refresh token (ต่ออายุทุกครั้งที่ rotate = sliding)
+| - idle_remember: เหมือน idle แต่สำหรับ login แบบ remember me
+| - max_age: อายุสูงสุดของ session นับจาก login ครั้งแรก (rotate แล้วไม่รีเซ็ต)
+| - reauth_max_age: route สำคัญต้องยืนยันตัวตน (login / POST /auth/reauth) ภายในเวลานี้
+| - reuse_grace: token ที่เพิ่งถูก rotate ยังใช้ได้ภายในเวลานี้ (หลาย tab refresh พร้อมกัน ไม่นับเป็น reuse)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct SessionPolicy {
+    pub idle: Duration,
+    pub idle_remember: Duration,
+    pub max_age: Duration,
+    pub reauth_max_age: Duration,
+    pub reuse_grace: Duration,
+}
+
+impl SessionPolicy {
+    // REFRESH_IDLE_HOURS (12), REFRESH_IDLE_HOURS_REMEMBER (720 = 30 วัน), SESSION_MAX_AGE_HOURS (2160 = 90 วัน)
+    // REAUTH_MAX_AGE_SECS (300, 30-86400), REFRESH_REUSE_GRACE_SECS (5, 0-60)
+    pub fn from_env() -> AppResult<Self> {
+        let idle = env_i64("REFRESH_IDLE_HOURS", 12)?.max(1);
+        let idle_remember = env_i64("REFRESH_IDLE_HOURS_REMEMBER", 720)?.max(1);
+        let max_age = env_i64("SESSION_MAX_AGE_HOURS", 2160)?.max(1);
+        let reauth_max_age = env_i64("REAUTH_MAX_AGE_SECS", 300)?.clamp(30, 86_400);
+        let reuse_grace = env_i64("REFRESH_REUSE_GRACE_SECS", 5)?.clamp(0, 60);
+
+        Ok(Self {
+            idle: Duration::hours(idle),
+            idle_remember: Duration::hours(idle_remember),
+            max_age: Duration::hours(max_age),
+            reauth_max_age: Duration::seconds(reauth_max_age),
+            reuse_grace: Duration::seconds(reuse_grace),
+        })
+    }
+
+    pub fn session_deadline(&self, started_at: DateTime<Utc>) -> DateTime<Utc> {
+        started_at + self.max_age
+    }
+
+    // วันหมดอายุของ refresh token ตัวใหม่ = min(now + idle, started_at + max_age)
+    pub fn expires_at(
+        &self,
+        started_at: DateTime<Utc>,
+        remember_me: bool,
+        now: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let idle = if remember_me { self.idle_remember } else { self.idle };
+
+        (now + idle).min(self.session_deadline(started_at))
+    }
+}
@@ -1,8 +1,24 @@
 #![allow(dead_code)]
 
+use std::{sync::Arc, time::Duration};
+
 use sqlx::{PgPool, postgres::PgPoolOptions};
+use webauthn_rs::Webauthn;
 
+use crate::app::ceremony::CeremonyStore;
+use crate::app::client_ip::TrustedProxies;
+use crate::app::cookies::CookiePolicy;
+use crate::app::cors::{CorsPolicies, OriginPattern};
+use crate::app::email_otp::EmailOtpPolicy;
+use crate::app::encryption::Encryption;
+use crate::app::lifecycle::Lifecycle;
+use crate::app::magic_link::MagicLinkPolicy;
+use crate::app::mailer::Mailer;
+use crate::app::password::PasswordHasher;
+use crate::app::password_policy::PasswordPolicy;
 use crate::app::result::AppResult;
+use crate::app::session::SessionPolicy;
+use crate::app::user_cache::UserSecurityCache;
 
 #[derive(Clone)]
 pub struct AppState {
@@ -12,6 +28,26 @@ pub struct AppState {
     pub jwt_audience: String,
     pub access_token_ttl: i64,
     pub refresh_secret: Vec<u8>,
+    pub user_cache: UserSecurityCache,
+    pub introspection_api_keys: Vec<String>,
+    pub session_policy: SessionPolicy,
+    pub cookie_policy: CookiePolicy,
+    pub cors: CorsPolicies,
+    pub csrf_trusted_origins: Vec<OriginPattern>,
+    pub trusted_proxies: TrustedProxies,
+    pub lifecycle: Arc<Lifecycle>,
+    pub metrics_token: Option<String>,
+    pub auth_body_limit: usize,
+    pub login_min_response: Duration,
+    pub password_hasher: PasswordHasher,
+    pub password_policy: PasswordPolicy,
+    pub webauthn: Arc<Webauthn>,
+    pub ceremonies: CeremonyStore,
+    pub encryption: Encryption,
+    // None = MAILER=none (ปิด endpoint ที่ต้องส่งเมล)
+    pub mailer: Option<Arc<dyn Mailer>>,
+    pub magic_link: MagicLinkPolicy,
+    pub email_otp: EmailOtpPolicy,
 }
 
 impl AppState {
@@ -0,0 +1,262 @@
+/*
+|---------------------------------
+| OpenTelemetry (OTLP trace export)
+| - เปิดด้วย cargo feature "otel"
+| - ตั้งค่าด้วย OTEL_* มาตรฐาน (SDK/exporter อ่านเอง)
+|   OTEL_EXPORTER_OTLP_ENDPOINT / OTEL_EXPORTER_OTLP_TRACES_ENDPOINT / OTEL_EXPORTER_OTLP_HEADERS
+|   OTEL_SERVICE_NAME / OTEL_RESOURCE_ATTRIBUTES / OTEL_TRACES_SAMPLER(_ARG)
+|   OTEL_SDK_DISABLED=true หรือ OTEL_TRACES_EXPORTER=none ---> ไม่ export
+| - ปิด feature ---> span ยังมีใน log ตามปกติ (จุดที่เรียกไม่ต้องใส่ cfg)
+|---------------------------------
+*/
+#[cfg(feature = "otel")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "otel")]
+use axum::http::HeaderMap;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+#[cfg(feature = "otel")]
+use tracing::Subscriber;
+#[cfg(feature = "otel")]
+use tracing_opentelemetry::OpenTelemetryLayer;
+#[cfg(feature = "otel")]
+use tracing_subscriber::registry::LookupSpan;
+
+#[cfg(feature = "otel")]
+use crate::app::result::AppResult;
+use tracing::Span;
+
+#[cfg(feature = "otel")]
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+// layer สำหรับ tracing subscriber (None = ปิด export ด้วย env)
+#[cfg(feature = "otel")]
+pub fn layer<S>() -> AppResult<Option<OpenTelemetryLayer<S, SdkTracer>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+
+    use crate::app::error::AppError;
+    use crate::utils::env::{env_bool, env_string};
+
+    let exporter_kind = env_string("OTEL_TRACES_EXPORTER", "otlp").to_ascii_lowercase();
+
+    if env_bool("OTEL_SDK_DISABLED", false)? || exporter_kind == "none" {
+        return Ok(None);
+    }
+
+    if exporter_kind != "otlp" {
+        return Err(AppError::BadRequest(format!("unsupported OTEL_TRACES_EXPORTER: {exporter_kind}")));
+    }
+
+    // build นี้มีแค่ OTLP/HTTP (protobuf)
+    let protocol = env_string("OTEL_EXPORTER_OTLP_PROTOCOL", "http/protobuf");
+    if protocol != "http/protobuf" {
+        return Err(AppError::BadRequest(format!("unsupported OTEL_EXPORTER_OTLP_PROTOCOL: {protocol}")));
+    }
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+        .build()
+        .map_err(|e| AppError::InternalError(format!("otlp exporter: {e}")))?;
+
+    Ok(Some(install(exporter)))
+}
+
+// ติดตั้ง provider (global + ไว้ flush ตอน shutdown) แล้วคืน layer ที่ส่ง span ไปที่ exporter
+#[cfg(feature = "otel")]
+fn install<S>(exporter: opentelemetry_otlp::SpanExporter) -> OpenTelemetryLayer<S, SdkTracer>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    use opentelemetry::{global, trace::TracerProvider};
+    use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator};
+
+    // ไม่ได้ตั้ง OTEL_SERVICE_NAME ---> ใช้ชื่อ crate แทน "unknown_service"
+    let mut resource = Resource::builder();
+    if std::env::var("OTEL_SERVICE_NAME").is_err() {
+        resource = resource.with_service_name(env!("CARGO_PKG_NAME"));
+    }
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build();
+
+    let tracer = provider.tracer(env!("CARGO_PKG_NAME"));
+
+    // W3C traceparent / tracestate
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    let _ = PROVIDER.set(provider);
+
+    tracing_opentelemetry::layer().with_tracer(tracer)
+}
+
+// ต่อ span ของ request เข้ากับ trace ของผู้เรียก (header traceparent)
+#[cfg(feature = "otel")]
+pub fn set_remote_parent(span: &Span, headers: &HeaderMap) {
+    use opentelemetry::propagation::{Extractor, TextMapPropagator};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    struct HeaderExtractor<'a>(&'a HeaderMap);
+
+    impl Extractor for HeaderExtractor<'_> {
+        fn get(&self, key: &str) -> Option<&str> {
+            self.0.get(key).and_then(|v| v.to_str().ok())
+        }
+
+        fn keys(&self) -> Vec<&str> {
+            self.0.keys().map(|k| k.as_str()).collect()
+        }
+    }
+
+    // W3C traceparent / tracestate (ตัวเดียวกับที่ตั้งเป็น global ใน install)
+    let cx = TraceContextPropagator::new().extract(&HeaderExtractor(headers));
+    let _ = span.set_parent(cx);
+}
+
+// trace id ของ span ปัจจุบัน (None = ไม่ได้ export / ไม่ถูก sample)
+#[cfg(feature = "otel")]
+pub fn current_trace_id() -> Option<String> {
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let cx = Span::current().context();
+    let span = cx.span();
+    let sc = span.span_context();
+
+    sc.is_valid().then(|| sc.trace_id().to_string())
+}
+
+// flush span ที่ค้างก่อนปิด process (blocking ---> เรียกผ่าน spawn_blocking)
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        tracing::warn!(error = %e, "otel shutdown failed");
+    }
+}
+
+/*
+|---------------------------------
+| span ต่อ 1 query
+| - summary สั้น ๆ แบบ "SELECT users" (ไม่ใส่ค่า parameter)
+| - ใช้คู่กับ tracing::Instrument: query.fetch_one(&db).instrument(db_span("...")).await
+|---------------------------------
+*/
+pub fn db_span(summary: &'static str) -> Span {
+    tracing::info_span!(
+        "db.query",
+        otel.name = summary,
+        otel.kind = "client",
+        db.system.name = "postgresql",
+        db.query.summary = summary,
+    )
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use super::*;
+
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+
+    fn traceparent() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("traceparent", TRACEPARENT.parse().unwrap());
+        headers
+    }
+
+    // collector จำลอง: รับ request แรก ตอบ 200 แล้วส่ง request line กลับมา
+    async fn fake_collector() -> (String, tokio::sync::oneshot::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 64 * 1024];
+            let n = sock.read(&mut buf).await.unwrap();
+            let head = String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or("").to_string();
+
+            sock.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+            let _ = tx.send(head);
+        });
+
+        (format!("http://{addr}/v1/traces"), rx)
+    }
+
+    // span ของ request ต่อ trace จาก traceparent ---> trace_id ใน problem+json / log เป็นของผู้เรียก
+    #[test]
+    fn request_span_continues_remote_trace() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            set_remote_parent(&span, &traceparent());
+
+            let _guard = span.enter();
+            assert_eq!(current_trace_id().as_deref(), Some(TRACE_ID));
+        });
+    }
+
+    #[test]
+    fn span_without_traceparent_starts_new_trace() {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            set_remote_parent(&span, &HeaderMap::new());
+
+            let _guard = span.enter();
+            let id = current_trace_id().expect("sampled span has a trace id");
+            assert_ne!(id, TRACE_ID);
+        });
+    }
+
+    // install + shutdown ของ crate ส่ง span ไปที่ collector จริง (OTLP/HTTP)
+    #[tokio::test]
+    async fn installed_layer_exports_on_shutdown() {
+        let (endpoint, rx) = fake_collector().await;
+
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()
+            .unwrap();
+
+        let subscriber = tracing_subscriber::registry().with(install(exporter));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request");
+            set_remote_parent(&span, &traceparent());
+            span.in_scope(|| db_span("SELECT users").in_scope(|| {}));
+        });
+
+        // flush เป็น blocking call (reqwest blocking client)
+        tokio::task::spawn_blocking(shutdown).await.unwrap();
+
+        let head = tokio::time::timeout(std::time::Duration::from_secs(5), rx)
+            .await
+            .expect("collector got nothing")
+            .unwrap();
+
+        assert!(head.starts_with("POST /v1/traces"), "{head}");
+    }
+}
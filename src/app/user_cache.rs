@@ -0,0 +1,342 @@
+use std::future::Future;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use moka::sync::Cache;
+use sqlx::{PgPool, postgres::PgListener};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::app::{result::AppResult, telemetry::db_span};
+use crate::utils::env::env_i64;
+use tracing::Instrument;
+
+// channel ที่ใช้กระจาย invalidation ระหว่างหลาย instance (payload = user id)
+pub const INVALIDATE_CHANNEL: &str = "user_security_changed";
+
+// จำนวนตัวนับ generation (แบ่งตาม hash ของ user id, ชนกันแค่ทำให้ไม่ cache รอบนั้น)
+const GENERATION_SHARDS: usize = 64;
+
+/*
+|---------------------------------
+| สถานะด้าน security ของ user ที่ต้องตรวจทุก request
+| (is_active / token_version / password_changed_at / ต้องเปลี่ยนรหัสผ่านไหม / เปิด MFA ไหม)
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct UserSecurity {
+    pub username: String,
+    pub role: String,
+    pub is_active: bool,
+    pub token_version: i32,
+    pub password_changed_at: Option<DateTime<Utc>>,
+    pub must_change_password: bool,
+    // เวลาที่ตั้งรหัสผ่านปัจจุบัน (ยังไม่เคยเปลี่ยน = วันสมัคร) ใช้คิดอายุรหัสผ่าน
+    pub password_set_at: DateTime<Utc>,
+    // เปิด MFA ---> route สำคัญต้องผ่านสอง factor (ดู require_recent_auth)
+    pub mfa_enabled: bool,
+}
+
+/*
+|---------------------------------
+| cache แบบจำกัดขนาด + TTL (key = user id)
+| - ลบทิ้งทันทีเมื่อมีการเขียนฝั่งเรา
+| - instance อื่นรับรู้ผ่าน LISTEN/NOTIFY
+| - evict ทุกครั้งเพิ่ม generation ของ id นั้น: โหลดที่เริ่มก่อน evict (อ่านค่าเก่าจาก DB)
+|   จะไม่ถูกเก็บลง cache ทับค่าที่เพิ่งลบ
+|---------------------------------
+*/
+#[derive(Clone)]
+pub struct UserSecurityCache {
+    inner: Cache<Uuid, UserSecurity>,
+    generations: Arc<[AtomicU64]>,
+    hasher: RandomState,
+}
+
+impl UserSecurityCache {
+    pub fn new(capacity: u64, ttl: Duration) -> Self {
+        let inner = Cache::builder()
+            .max_capacity(capacity)
+            .time_to_live(ttl)
+            .build();
+
+        let generations = (0..GENERATION_SHARDS).map(|_| AtomicU64::new(0)).collect();
+
+        Self { inner, generations, hasher: RandomState::new() }
+    }
+
+    fn generation(&self, id: &Uuid) -> &AtomicU64 {
+        &self.generations[self.hasher.hash_one(id) as usize % GENERATION_SHARDS]
+    }
+
+    // USER_CACHE_CAPACITY (ค่าเริ่มต้น 10000), USER_CACHE_TTL_SECS (ค่าเริ่มต้น 30)
+    pub fn from_env() -> AppResult<Self> {
+        let capacity = env_i64("USER_CACHE_CAPACITY", 10_000)?.max(0) as u64;
+        let ttl_secs = env_i64("USER_CACHE_TTL_SECS", 30)?.clamp(0, 3600) as u64;
+
+        Ok(Self::new(capacity, Duration::from_secs(ttl_secs)))
+    }
+
+    // อ่านจาก cache ก่อน ไม่มีค่อยโหลดจาก DB (ไม่ cache กรณีไม่พบ user)
+    pub async fn get(&self, db: &PgPool, id: Uuid) -> AppResult<Option<UserSecurity>> {
+        self.get_or_load(id, async {
+            let row = sqlx::query_as!(
+                UserSecurity,
+                r#"
+                SELECT
+                username, role, is_active, token_version,
+                password_changed_at as "password_changed_at: chrono::DateTime<chrono::Utc>",
+                must_change_password,
+                COALESCE(password_changed_at, created_at) as "password_set_at!: chrono::DateTime<chrono::Utc>",
+                mfa_enabled
+                FROM users
+                WHERE id = $1
+                "#,
+                id
+            )
+            .fetch_optional(db)
+            .instrument(db_span("SELECT users"))
+            .await?;
+
+            Ok(row)
+        })
+        .await
+    }
+
+    /*
+    |---------------------------------
+    | อ่าน generation ก่อนโหลด ---> เก็บลง cache ---> ตรวจ generation ซ้ำ
+    | - มี evict ระหว่างโหลด ---> ลบที่เพิ่งเก็บออก (ค่าอาจเป็นของก่อนเขียน)
+    | - ตรวจหลังเก็บ (ไม่ใช่ก่อน) กัน evict ที่แทรกระหว่างตรวจกับเก็บ
+    |---------------------------------
+    */
+    async fn get_or_load<F>(&self, id: Uuid, load: F) -> AppResult<Option<UserSecurity>>
+    where
+        F: Future<Output = AppResult<Option<UserSecurity>>>,
+    {
+        if let Some(hit) = self.inner.get(&id) {
+            return Ok(Some(hit));
+        }
+
+        let generation = self.generation(&id);
+        let before = generation.load(Ordering::Acquire);
+
+        let row = load.await?;
+
+        if let Some(sec) = &row {
+            self.inner.insert(id, sec.clone());
+
+            if generation.load(Ordering::Acquire) != before {
+                self.inner.invalidate(&id);
+            }
+        }
+
+        Ok(row)
+    }
+
+    // ลบเฉพาะใน instance นี้
+    // เพิ่ม generation ก่อนลบ (ดู get_or_load)
+    pub fn evict(&self, id: &Uuid) {
+        self.generation(id).fetch_add(1, Ordering::AcqRel);
+        self.inner.invalidate(id);
+    }
+
+    pub fn clear(&self) {
+        for generation in self.generations.iter() {
+            generation.fetch_add(1, Ordering::AcqRel);
+        }
+        self.inner.invalidate_all();
+    }
+
+    // ใช้หลังเขียน users (deactivate / เปลี่ยนรหัสผ่าน / force logout)
+    // ลบใน instance นี้ทันที แล้วแจ้ง instance อื่นผ่าน NOTIFY
+    pub async fn invalidate(&self, db: &PgPool, id: Uuid) -> AppResult<()> {
+        self.evict(&id);
+
+        sqlx::query!(
+            "SELECT pg_notify($1, $2)",
+            INVALIDATE_CHANNEL,
+            id.to_string()
+        )
+        .execute(db)
+        .instrument(db_span("SELECT pg_notify"))
+        .await?;
+
+        Ok(())
+    }
+}
+
+// สิ่งที่ listener ได้รับในแต่ละรอบ
+enum ListenerEvent<'a> {
+    Notification(&'a str),
+    // connection หลุดแล้วต่อใหม่ (PgListener reconnect เองรอบถัดไป)
+    Reconnected,
+    Error,
+}
+
+// notification ระหว่างหลุดการเชื่อมต่อหายไปแล้ว ---> ล้าง cache ทั้งหมด
+fn handle_event(cache: &UserSecurityCache, event: ListenerEvent<'_>) {
+    match event {
+        ListenerEvent::Notification(payload) => match payload.parse::<Uuid>() {
+            Ok(id) => cache.evict(&id),
+            Err(_) => warn!(payload, "invalid invalidation payload"),
+        },
+        ListenerEvent::Reconnected | ListenerEvent::Error => cache.clear(),
+    }
+}
+
+/*
+|---------------------------------
+| background task: LISTEN แล้ว evict ตาม payload
+| - ถ้าหลุดการเชื่อมต่อ อาจพลาด notification ไป ---> ล้าง cache ทั้งหมด
+|---------------------------------
+*/
+pub async fn spawn_listener(db: PgPool, cache: UserSecurityCache) -> AppResult<()> {
+    let mut listener = PgListener::connect_with(&db).await?;
+    listener.listen(INVALIDATE_CHANNEL).await?;
+
+    tokio::spawn(async move {
+        loop {
+            match listener.try_recv().await {
+                Ok(Some(n)) => handle_event(&cache, ListenerEvent::Notification(n.payload())),
+                Ok(None) => handle_event(&cache, ListenerEvent::Reconnected),
+                Err(e) => {
+                    error!(error = ?e, "user cache listener error");
+                    handle_event(&cache, ListenerEvent::Error);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(is_active: bool) -> UserSecurity {
+        UserSecurity {
+            username: "alice".into(),
+            role: "user".into(),
+            is_active,
+            token_version: 1,
+            password_changed_at: None,
+            must_change_password: false,
+            password_set_at: Utc::now(),
+            mfa_enabled: false,
+        }
+    }
+
+    fn cache() -> UserSecurityCache {
+        UserSecurityCache::new(100, Duration::from_secs(60))
+    }
+
+    #[tokio::test]
+    async fn loaded_value_is_cached() {
+        let cache = cache();
+        let id = Uuid::new_v4();
+
+        cache.get_or_load(id, async { Ok(Some(sample(true))) }).await.unwrap();
+
+        assert!(cache.inner.get(&id).is_some_and(|s| s.is_active));
+    }
+
+    #[tokio::test]
+    async fn evict_during_load_discards_stale_value() {
+        let cache = cache();
+        let id = Uuid::new_v4();
+        let (loaded_tx, loaded_rx) = tokio::sync::oneshot::channel::<()>();
+        let (evicted_tx, evicted_rx) = tokio::sync::oneshot::channel::<()>();
+
+        // โหลดค่าเก่า (is_active = true) ค้างไว้ ระหว่างนั้น deactivate + evict
+        let loader = {
+            let cache = cache.clone();
+            tokio::spawn(async move {
+                cache
+                    .get_or_load(id, async {
+                        loaded_tx.send(()).ok();
+                        evicted_rx.await.ok();
+                        Ok(Some(sample(true)))
+                    })
+                    .await
+            })
+        };
+
+        loaded_rx.await.unwrap();
+        cache.evict(&id);
+        evicted_tx.send(()).unwrap();
+
+        let row = loader.await.unwrap().unwrap();
+        assert!(row.is_some());
+        assert!(cache.inner.get(&id).is_none());
+    }
+
+    #[tokio::test]
+    async fn evict_of_other_user_keeps_entry() {
+        let cache = cache();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+
+        cache.get_or_load(a, async { Ok(Some(sample(true))) }).await.unwrap();
+        cache.get_or_load(b, async { Ok(Some(sample(true))) }).await.unwrap();
+        cache.evict(&a);
+
+        assert!(cache.inner.get(&a).is_none());
+        assert!(cache.inner.get(&b).is_some());
+    }
+
+    #[tokio::test]
+    async fn listener_notification_evicts_one_user() {
+        let cache = cache();
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        cache.get_or_load(a, async { Ok(Some(sample(true))) }).await.unwrap();
+        cache.get_or_load(b, async { Ok(Some(sample(true))) }).await.unwrap();
+
+        handle_event(&cache, ListenerEvent::Notification(&a.to_string()));
+        handle_event(&cache, ListenerEvent::Notification("not-a-uuid"));
+
+        assert!(cache.inner.get(&a).is_none());
+        assert!(cache.inner.get(&b).is_some());
+    }
+
+    #[tokio::test]
+    async fn listener_reconnect_clears_everything() {
+        let cache = cache();
+        let id = Uuid::new_v4();
+        cache.get_or_load(id, async { Ok(Some(sample(true))) }).await.unwrap();
+
+        handle_event(&cache, ListenerEvent::Reconnected);
+        assert!(cache.inner.get(&id).is_none());
+
+        cache.get_or_load(id, async { Ok(Some(sample(true))) }).await.unwrap();
+        handle_event(&cache, ListenerEvent::Error);
+        assert!(cache.inner.get(&id).is_none());
+    }
+
+    // ต้องมี DATABASE_URL: invalidate ของ instance หนึ่งไปถึง listener ของอีก instance
+    #[tokio::test]
+    #[ignore]
+    async fn invalidate_reaches_other_instance() {
+        dotenv::dotenv().ok();
+        let db = PgPool::connect(&std::env::var("DATABASE_URL").unwrap()).await.unwrap();
+        let (local, remote) = (cache(), cache());
+        let id = Uuid::new_v4();
+
+        spawn_listener(db.clone(), remote.clone()).await.unwrap();
+        remote.get_or_load(id, async { Ok(Some(sample(true))) }).await.unwrap();
+
+        local.invalidate(&db, id).await.unwrap();
+
+        for _ in 0..50 {
+            if remote.inner.get(&id).is_none() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("remote cache was not invalidated");
+    }
+}
@@ -0,0 +1,102 @@
+use axum::{
+    Json,
+    extract::{FromRequest, Request},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use validator::{Validate, ValidationErrors, ValidationErrorsKind};
+
+use crate::app::error::AppError;
+
+/*
+|---------------------------------
+| JSON extractor ที่ตรวจ rule ของ DTO ก่อนเข้า handler
+| - JSON เสีย / ไม่มี Content-Type / body ใหญ่เกิน ---> AppError::JsonRejection
+| - ผิด rule (#[validate(...)]) ---> 422 พร้อม error ราย field
+|---------------------------------
+*/
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+
+        value.validate()?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+// 1 รายการต่อ 1 rule ที่ไม่ผ่าน (field ซ้อนใช้ path แบบ "a.b" / "a[0].b")
+#[derive(Debug, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut out = Vec::new();
+    collect(errors, "", &mut out);
+    out.sort_by(|a, b| a.field.cmp(&b.field).then_with(|| a.code.cmp(&b.code)));
+    out
+}
+
+fn collect(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (name, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}.{name}")
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(list) => {
+                for e in list {
+                    let message = match &e.message {
+                        Some(m) => m.to_string(),
+                        None => default_message(&e.code, &e.params),
+                    };
+
+                    out.push(FieldError {
+                        field: path.clone(),
+                        code: e.code.to_string(),
+                        message,
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(inner) => collect(inner, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (i, inner) in items {
+                    collect(inner, &format!("{path}[{i}]"), out);
+                }
+            }
+        }
+    }
+}
+
+// ข้อความมาตรฐานของ rule ที่ใช้บ่อย (rule ที่ตั้ง message เองจะไม่มาถึงตรงนี้)
+fn default_message(
+    code: &str,
+    params: &std::collections::HashMap<std::borrow::Cow<'static, str>, serde_json::Value>,
+) -> String {
+    let min = params.get("min");
+    let max = params.get("max");
+
+    match (code, min, max) {
+        ("length", Some(min), Some(max)) => format!("must be between {min} and {max} characters"),
+        ("length", Some(min), None) => format!("must be at least {min} characters"),
+        ("length", None, Some(max)) => format!("must be at most {max} characters"),
+        ("range", Some(min), Some(max)) => format!("must be between {min} and {max}"),
+        ("email", _, _) => "must be a valid email address".into(),
+        ("url", _, _) => "must be a valid URL".into(),
+        ("required", _, _) => "is required".into(),
+        _ => "is invalid".into(),
+    }
+}
+
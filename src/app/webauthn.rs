@@ -0,0 +1,97 @@
+use webauthn_rs::prelude::{Url, Webauthn, WebauthnBuilder};
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::utils::env::{env_list, env_string};
+
+/*
+|---------------------------------
+| Relying Party ของ WebAuthn / passkey
+| - WEBAUTHN_RP_ID: domain ที่ passkey ผูกอยู่ (ค่าเริ่มต้น localhost) เปลี่ยนทีหลัง = passkey เดิมใช้ไม่ได้
+| - WEBAUTHN_RP_ORIGIN: origin ของหน้าเว็บที่เรียก navigator.credentials (ค่าเริ่มต้น http://localhost:3000)
+| - WEBAUTHN_EXTRA_ORIGINS: origin อื่นที่อนุญาต (คั่นด้วย comma)
+| - WEBAUTHN_RP_NAME: ชื่อที่แสดงใน prompt ของ authenticator
+|---------------------------------
+*/
+pub fn from_env() -> AppResult<Webauthn> {
+    let rp_id = env_string("WEBAUTHN_RP_ID", "localhost");
+    let rp_name = env_string("WEBAUTHN_RP_NAME", env!("CARGO_PKG_NAME"));
+
+    let parse = |name: &str, value: &str| {
+        Url::parse(value).map_err(|e| AppError::BadRequest(format!("invalid {name}: {e}")))
+    };
+
+    let origin = parse("WEBAUTHN_RP_ORIGIN", &env_string("WEBAUTHN_RP_ORIGIN", "http://localhost:3000"))?;
+
+    let invalid = |e| AppError::BadRequest(format!("invalid WebAuthn config: {e:?}"));
+
+    let mut builder = WebauthnBuilder::new(&rp_id, &origin)
+        .map_err(invalid)?
+        .rp_name(&rp_name);
+
+    for extra in env_list("WEBAUTHN_EXTRA_ORIGINS") {
+        builder = builder.append_allowed_origin(&parse("WEBAUTHN_EXTRA_ORIGINS", &extra)?);
+    }
+
+    builder.build().map_err(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use webauthn_authenticator_rs::{WebauthnAuthenticator, softpasskey::SoftPasskey};
+    use webauthn_rs::prelude::{PasskeyAuthentication, PasskeyRegistration, Uuid};
+
+    // state ของ ceremony ถูกเก็บใน auth_ceremonies เป็น JSON ---> ผ่าน serde เหมือนของจริง
+    fn through_db<T: serde::Serialize + serde::de::DeserializeOwned>(state: T) -> T {
+        serde_json::from_value(serde_json::to_value(state).unwrap()).unwrap()
+    }
+
+    fn origin() -> Url {
+        Url::parse(&env_string("WEBAUTHN_RP_ORIGIN", "http://localhost:3000")).unwrap()
+    }
+
+    #[test]
+    fn soft_passkey_registers_and_authenticates() {
+        let webauthn = from_env().unwrap();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+        let user_id = Uuid::new_v4();
+
+        let (options, registration) = webauthn
+            .start_passkey_registration(user_id, "alice", "alice", None)
+            .unwrap();
+        let credential = authenticator.do_registration(origin(), options).unwrap();
+        let passkey = webauthn
+            .finish_passkey_registration(&credential, &through_db::<PasskeyRegistration>(registration))
+            .unwrap();
+
+        let (options, authentication) = webauthn.start_passkey_authentication(std::slice::from_ref(&passkey)).unwrap();
+        let assertion = authenticator.do_authentication(origin(), options).unwrap();
+        let result = webauthn
+            .finish_passkey_authentication(&assertion, &through_db::<PasskeyAuthentication>(authentication))
+            .unwrap();
+
+        assert_eq!(result.cred_id(), passkey.cred_id());
+        assert!(result.user_verified());
+    }
+
+    // assertion ของ ceremony หนึ่งเอาไปตอบอีก ceremony ไม่ได้ (challenge ไม่ตรง)
+    #[test]
+    fn assertion_for_other_challenge_is_rejected() {
+        let webauthn = from_env().unwrap();
+        let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+        let (options, registration) = webauthn
+            .start_passkey_registration(Uuid::new_v4(), "alice", "alice", None)
+            .unwrap();
+        let credential = authenticator.do_registration(origin(), options).unwrap();
+        let passkey = webauthn.finish_passkey_registration(&credential, &registration).unwrap();
+
+        let (options, _) = webauthn.start_passkey_authentication(std::slice::from_ref(&passkey)).unwrap();
+        let (_, other) = webauthn.start_passkey_authentication(&[passkey]).unwrap();
+
+        let assertion = authenticator.do_authentication(origin(), options).unwrap();
+        assert!(webauthn.finish_passkey_authentication(&assertion, &other).is_err());
+    }
+}
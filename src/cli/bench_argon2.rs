@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+use crate::app::password::{DEFAULT_P_COST, PasswordHasher};
+use crate::app::result::AppResult;
+use crate::cli::flag_u32;
+
+// m ที่ลอง (KiB): 19 MiB ... 1 GiB
+const M_COSTS: &[u32] = &[19 * 1024, 32 * 1024, 64 * 1024, 128 * 1024, 256 * 1024, 512 * 1024, 1024 * 1024];
+const T_COSTS: &[u32] = &[1, 2, 3, 4, 6, 8];
+const ROUNDS: usize = 3;
+
+/*
+|---------------------------------
+| authrs bench-argon2 [--target-ms 500] [--parallelism 1]
+| - วัดเวลา hash ของแต่ละ (m, t) บนเครื่องนี้ (median ของ 3 รอบ)
+| - แนะนำชุดที่แรงที่สุด (m ก่อน แล้วค่อย t) ที่ยังไม่เกิน target
+| - ต้องรันบนเครื่องสเปกเดียวกับ production ถึงจะมีความหมาย
+|---------------------------------
+*/
+pub fn run(args: &[String]) -> AppResult<()> {
+    let target = Duration::from_millis(flag_u32(args, "--target-ms", 500)? as u64);
+    let p_cost = flag_u32(args, "--parallelism", DEFAULT_P_COST)?;
+
+    // config ปัจจุบันจาก env (รวม pepper) ---> ดูว่ายังอยู่ใน target ไหม
+    let current = PasswordHasher::from_env()?;
+    let params = current.params();
+    println!(
+        "current: m={} KiB t={} p={} ---> {:?}",
+        params.m_cost(),
+        params.t_cost(),
+        params.p_cost(),
+        measure(&current)?,
+    );
+
+    println!("target: {:?} (p={p_cost})", target);
+    println!("{:>10} {:>4} {:>10}", "m (KiB)", "t", "time");
+
+    let mut best: Option<(u32, u32, Duration)> = None;
+
+    'outer: for &m in M_COSTS {
+        for (i, &t) in T_COSTS.iter().enumerate() {
+            let hasher = PasswordHasher::new(m, t, p_cost, None)?;
+            let took = measure(&hasher)?;
+            println!("{m:>10} {t:>4} {took:>10.1?}");
+
+            if took > target {
+                // t มากขึ้นมีแต่ช้าลง ---> ข้ามไป m ถัดไป (t ต่ำสุดยังเกิน = m ที่ใหญ่กว่าก็เกิน)
+                if i == 0 {
+                    break 'outer;
+                }
+                break;
+            }
+
+            best = Some((m, t, took));
+        }
+    }
+
+    match best {
+        Some((m, t, took)) => {
+            println!();
+            println!("recommended ({took:.1?}):");
+            println!("ARGON2_M_COST_KIB={m}");
+            println!("ARGON2_T_COST={t}");
+            println!("ARGON2_P_COST={p_cost}");
+        }
+        None => println!("no parameters fit within {:?}; keep the defaults", target),
+    }
+
+    Ok(())
+}
+
+fn measure(hasher: &PasswordHasher) -> AppResult<Duration> {
+    let mut times = Vec::with_capacity(ROUNDS);
+
+    for _ in 0..ROUNDS {
+        let start = Instant::now();
+        hasher.hash("benchmark-password")?;
+        times.push(start.elapsed());
+    }
+
+    times.sort();
+    Ok(times[ROUNDS / 2])
+}
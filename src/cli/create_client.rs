@@ -0,0 +1,60 @@
+use crate::app::error::AppError;
+use crate::app::password::PasswordHasher;
+use crate::app::result::AppResult;
+use crate::app::state::AppState;
+use crate::cli::flag;
+use crate::controllers::auth::utils::generate_refresh_token;
+
+/*
+|---------------------------------
+| authrs create-client <client_id> [--name <ชื่อบริการ>] [--scopes "introspect ..."]
+| - สร้าง client_secret แบบสุ่ม แสดงครั้งเดียว (เก็บแค่ hash)
+| - hash ด้วย PasswordHasher (params / pepper เดียวกับ server) ---> ต้องตั้ง env ชุดเดียวกัน
+| - client_id ซ้ำ ---> error (ไม่ทับของเดิม)
+|---------------------------------
+*/
+pub async fn run(args: &[String]) -> AppResult<()> {
+    let client_id = args
+        .first()
+        .filter(|a| !a.starts_with("--"))
+        .ok_or_else(|| AppError::BadRequest("usage: create-client <client_id> [--name <name>] [--scopes \"a b\"]".into()))?;
+
+    let name = flag(args, "--name").unwrap_or(client_id);
+    let scopes: Vec<String> = flag(args, "--scopes")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    let secret = generate_refresh_token()?;
+    let hash = PasswordHasher::from_env()?.hash(&secret)?;
+
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| AppError::BadRequest("DATABASE_URL is not set".into()))?;
+    let db = AppState::connect(&database_url).await?;
+
+    let inserted = sqlx::query!(
+        r#"
+            INSERT INTO oauth_clients (client_id, client_secret_hash, name, scopes)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (client_id) DO NOTHING
+        "#,
+        client_id,
+        hash,
+        name,
+        &scopes
+    )
+    .execute(&db)
+    .await?
+    .rows_affected();
+
+    if inserted == 0 {
+        return Err(AppError::BadRequest(format!("client already exists: {client_id}")));
+    }
+
+    println!("client_id:     {client_id}");
+    println!("client_secret: {secret}");
+    println!("scopes:        {}", scopes.join(" "));
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::app::error::AppError;
+use crate::app::legacy_password::is_supported_hash;
+use crate::app::result::AppResult;
+use crate::app::state::AppState;
+use crate::app::validation::field_errors;
+use crate::cli::flag;
+
+fn default_role() -> String {
+    "user".into()
+}
+
+fn default_active() -> bool {
+    true
+}
+
+// 1 แถวของไฟล์ import (CSV header / key ของ JSON ใช้ชื่อเดียวกัน)
+#[derive(Deserialize, Validate)]
+pub struct ImportRow {
+    #[validate(length(min = 1, max = 64))]
+    pub username: String,
+    #[validate(email)]
+    pub email: String,
+    // hash จากระบบเดิม (argon2 / bcrypt / scrypt / pbkdf2-sha256) ห้ามเป็น plain text
+    pub password_hash: String,
+    #[serde(default = "default_role")]
+    #[validate(length(min = 1, max = 20))]
+    pub role: String,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+/*
+|---------------------------------
+| authrs import-users <file> [--format csv|json] [--dry-run]
+| - csv: มี header username,email,password_hash[,role,is_active]
+| - json: array ของ object ที่มี key เดียวกัน
+| - ตรวจทุกแถวก่อน ผิดแม้แถวเดียว ---> ไม่ import อะไรเลย
+| - username / email ซ้ำกับที่มีอยู่ ---> ข้าม (ไม่ทับ)
+| - hash เก่าจะถูก rehash เป็น Argon2id ตอน user login สำเร็จครั้งถัดไป
+|---------------------------------
+*/
+pub async fn run(args: &[String]) -> AppResult<()> {
+    let path = args
+        .first()
+        .filter(|a| !a.starts_with("--"))
+        .ok_or_else(|| AppError::BadRequest("usage: import-users <file> [--format csv|json] [--dry-run]".into()))?;
+
+    let format = match flag(args, "--format") {
+        Some(f) => f.to_ascii_lowercase(),
+        None => Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("csv")
+            .to_ascii_lowercase(),
+    };
+
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+
+    let rows = match format.as_str() {
+        "csv" => read_csv(path)?,
+        "json" => serde_json::from_str::<Vec<ImportRow>>(&std::fs::read_to_string(path)?)?,
+        other => return Err(AppError::BadRequest(format!("unsupported format: {other}"))),
+    };
+
+    // ตรวจทั้งไฟล์ก่อน (เลขแถวนับจาก 1 ไม่รวม header)
+    let mut problems = Vec::new();
+
+    for (i, row) in rows.iter().enumerate() {
+        if let Err(e) = row.validate() {
+            for f in field_errors(&e) {
+                problems.push(format!("row {}: {} {}", i + 1, f.field, f.message));
+            }
+        }
+
+        if !is_supported_hash(&row.password_hash) {
+            problems.push(format!("row {}: password_hash has an unsupported format", i + 1));
+        }
+    }
+
+    if !problems.is_empty() {
+        for p in &problems {
+            eprintln!("{p}");
+        }
+        return Err(AppError::BadRequest(format!("{} invalid row(s), nothing imported", problems.len())));
+    }
+
+    if dry_run {
+        println!("{} row(s) valid (dry run, nothing imported)", rows.len());
+        return Ok(());
+    }
+
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| AppError::BadRequest("DATABASE_URL is not set".into()))?;
+    let db = AppState::connect(&database_url).await?;
+
+    let mut tx = db.begin().await?;
+    let mut inserted = 0u64;
+
+    for row in &rows {
+        let result = sqlx::query!(
+            r#"
+                INSERT INTO users (username, email, password_hash, role, is_active)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT DO NOTHING
+            "#,
+            row.username,
+            row.email,
+            row.password_hash,
+            row.role,
+            row.is_active
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        inserted += result.rows_affected();
+    }
+
+    tx.commit().await?;
+
+    println!(
+        "imported {inserted} user(s), skipped {} existing",
+        rows.len() as u64 - inserted
+    );
+
+    Ok(())
+}
+
+fn read_csv(path: &str) -> AppResult<Vec<ImportRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .map_err(|e| AppError::BadRequest(format!("csv: {e}")))?;
+
+    reader
+        .deserialize()
+        .enumerate()
+        .map(|(i, r)| r.map_err(|e| AppError::BadRequest(format!("csv row {}: {e}", i + 1))))
+        .collect()
+}
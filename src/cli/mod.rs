@@ -0,0 +1,47 @@
+pub mod bench_argon2;
+pub mod create_client;
+pub mod import_users;
+pub mod reencrypt;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+
+/*
+|---------------------------------
+| คำสั่งจาก command line (ไม่มี argument = start server)
+| - authrs bench-argon2 [--target-ms 500] [--parallelism 1]
+| - authrs import-users <file> [--format csv|json] [--dry-run]
+| - authrs create-client <client_id> [--name <name>] [--scopes "a b"]
+| - authrs reencrypt [--dry-run] [--batch-size 500]
+|---------------------------------
+*/
+pub async fn run(args: &[String]) -> AppResult<()> {
+    // โหลด .env ตอน dev เท่านั้น (เหมือน server)
+    if cfg!(debug_assertions) {
+        dotenv::dotenv().ok();
+    }
+
+    match args.first().map(String::as_str) {
+        Some("bench-argon2") => bench_argon2::run(&args[1..]),
+        Some("import-users") => import_users::run(&args[1..]).await,
+        Some("create-client") => create_client::run(&args[1..]).await,
+        Some("reencrypt") => reencrypt::run(&args[1..]).await,
+        Some(other) => Err(AppError::BadRequest(format!("unknown command: {other}"))),
+        None => Ok(()),
+    }
+}
+
+// อ่านค่า --name <value> (ไม่มี ---> None)
+pub fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+pub fn flag_u32(args: &[String], name: &str, default: u32) -> AppResult<u32> {
+    match flag(args, name) {
+        Some(v) => v.parse().map_err(|_| AppError::BadRequest(format!("invalid {name}: {v}"))),
+        None => Ok(default),
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::app::encryption::{ENCRYPTED_COLUMNS, Encryption, column_context};
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::app::state::AppState;
+use crate::cli::flag_u32;
+use crate::utils::env::is_production;
+
+/*
+|---------------------------------
+| authrs reencrypt [--dry-run] [--batch-size 500]
+| - เข้ารหัสทุกคอลัมน์ใน ENCRYPTED_COLUMNS ใหม่ด้วย ENCRYPTION_ACTIVE_KEY_ID
+| - ค่าที่ใช้ key เก่า (ต้องยังอยู่ใน ENCRYPTION_KEYS) และค่าที่ยังเป็น plain text
+| - แถวที่ถูกแก้ระหว่างรัน ---> ข้าม (รันซ้ำได้ ค่าที่เป็น key ปัจจุบันแล้วไม่ถูกแตะ)
+| - รันจบแล้วไม่มีค่าที่ใช้ key เก่า ---> เอา key เก่าออกจาก ENCRYPTION_KEYS ได้
+|---------------------------------
+*/
+pub async fn run(args: &[String]) -> AppResult<()> {
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let batch_size = flag_u32(args, "--batch-size", 500)?.clamp(1, 10_000) as i64;
+
+    let encryption = Encryption::from_env(is_production())?;
+    let active = encryption.active_key_id().to_string();
+
+    let database_url = std::env::var("DATABASE_URL")
+        .map_err(|_| AppError::BadRequest("DATABASE_URL is not set".into()))?;
+    let db = AppState::connect(&database_url).await?;
+
+    for (table, column) in ENCRYPTED_COLUMNS {
+        // ชื่อ table / column มาจากค่าคงที่ในโค้ด ไม่ใช่ input
+        let select = format!(
+            "SELECT id, {column} AS value FROM {table} WHERE {column} IS NOT NULL AND id > $1 ORDER BY id LIMIT $2"
+        );
+        let update = format!("UPDATE {table} SET {column} = $2 WHERE id = $1 AND {column} = $3");
+
+        // key id ---> จำนวนแถวที่พบ ("plaintext" = ยังไม่เข้ารหัส)
+        let mut seen: BTreeMap<String, u64> = BTreeMap::new();
+        let (mut rewritten, mut skipped) = (0u64, 0u64);
+        let mut after = Uuid::nil();
+
+        loop {
+            let rows = sqlx::query(&select)
+                .bind(after)
+                .bind(batch_size)
+                .fetch_all(&db)
+                .await?;
+
+            let Some(last) = rows.last() else {
+                break;
+            };
+            after = last.try_get("id")?;
+
+            for row in &rows {
+                let id: Uuid = row.try_get("id")?;
+                let value: Vec<u8> = row.try_get("value")?;
+
+                let kid = Encryption::key_id(&value).unwrap_or("plaintext").to_string();
+                *seen.entry(kid.clone()).or_default() += 1;
+
+                if dry_run {
+                    continue;
+                }
+
+                let context = column_context(table, column, id);
+                let Some(encrypted) = encryption.reencrypt(&value, &context)? else {
+                    continue;
+                };
+
+                let updated = sqlx::query(&update)
+                    .bind(id)
+                    .bind(&encrypted)
+                    .bind(&value)
+                    .execute(&db)
+                    .await?
+                    .rows_affected();
+
+                if updated == 1 {
+                    rewritten += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+
+        let summary = seen
+            .iter()
+            .map(|(kid, n)| format!("{kid}={n}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        if dry_run {
+            println!("{table}.{column}: [{summary}] (dry run, active key {active})");
+        } else {
+            println!("{table}.{column}: [{summary}] ---> re-encrypted {rewritten} with {active}, skipped {skipped} changed during run");
+        }
+    }
+
+    Ok(())
+}
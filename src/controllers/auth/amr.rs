@@ -0,0 +1,27 @@
+// วิธียืนยันตัวตน (claim amr) ใช้ชื่อเดียวกับ methods ของ MFA
+pub const PASSWORD: &str = "password";
+pub const WEBAUTHN: &str = "webauthn";
+pub const TOTP: &str = "totp";
+pub const RECOVERY_CODE: &str = "recovery_code";
+pub const MAGIC_LINK: &str = "magic_link";
+pub const EMAIL_OTP: &str = "email_otp";
+
+// ผ่านตั้งแต่สอง factor ขึ้นไปใน session เดียวกัน
+pub const MFA: &str = "mfa";
+
+// รวมวิธีที่ผ่าน (ตัดซ้ำ, คงลำดับ) + เติม "mfa" เมื่อมีมากกว่าหนึ่งวิธี
+pub fn normalize<'a>(methods: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+
+    for m in methods {
+        if m != MFA && !out.iter().any(|o| o == m) {
+            out.push(m.to_string());
+        }
+    }
+
+    if out.len() > 1 {
+        out.push(MFA.to_string());
+    }
+
+    out
+}
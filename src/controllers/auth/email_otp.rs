@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State, http::{HeaderMap, StatusCode}, response::Response};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, warn};
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::app::client_ip::ClientIp;
+use crate::app::{email_otp::{generate_code, normalize_code}, error::AppError, mailer::{Email, Mailer, send_in_background, sender}, metrics, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson};
+use crate::controllers::auth::amr;
+use crate::controllers::auth::issue::{issue_session, load_session_user};
+use crate::controllers::auth::me::AuthUser;
+use crate::controllers::auth::mfa::mfa_challenge;
+use crate::controllers::auth::reauth::{ReauthResponse, complete_reauth};
+use crate::controllers::auth::utils::hash_refresh_token;
+
+// รหัสใช้ได้เฉพาะขั้นตอนที่ขอ (รหัส login ใช้ step-up ไม่ได้ และกลับกัน)
+const PURPOSE_LOGIN: &str = "login";
+const PURPOSE_STEP_UP: &str = "step_up";
+
+#[derive(Deserialize, Validate)]
+pub struct EmailOtpRequest {
+    #[validate(email, length(max = 254))]
+    pub email: String,
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+#[derive(Serialize)]
+pub struct EmailOtpResponse {
+    // ส่งกลับมาพร้อมรหัสตอน verify
+    pub otp_token: Uuid,
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct EmailOtpVerifyRequest {
+    pub otp_token: Uuid,
+    #[validate(length(min = 1, max = 16))]
+    pub code: String,
+}
+
+fn code_hash(state: &AppState, otp_token: Uuid, code: &str) -> AppResult<String> {
+    hash_refresh_token(&format!("email-otp:{otp_token}:{code}"), &state.refresh_secret)
+}
+
+/*
+|---------------------------------
+| สร้างรหัสใหม่ + ส่งเมล
+| - None = ไม่มี user / เกินจำนวนต่ออีเมลในช่วงเวลา (ไม่ส่ง)
+| - ไม่มี user ก็รันคำสั่งชุดเดียวกัน (id = nil ไม่ตรงกับแถวไหน) ให้เวลาตอบไม่บอกว่ามี user หรือไม่
+| - ล็อกแถว user ก่อนนับ ---> คำขอพร้อมกันไม่หลุดเกิน max_per_email
+|---------------------------------
+*/
+async fn send_code(
+    state: &AppState,
+    mailer: Arc<dyn Mailer>,
+    user: Option<(Uuid, String)>,
+    purpose: &'static str,
+    remember_me: bool,
+) -> AppResult<Option<Uuid>> {
+    let policy = &state.email_otp;
+    let otp_token = Uuid::new_v4();
+    let code = generate_code()?;
+    let window_start = Utc::now() - policy.window;
+    let user_id = user.as_ref().map_or(Uuid::nil(), |(id, _)| *id);
+
+    sqlx::query!(
+        "DELETE FROM email_otp_codes WHERE created_at < $1 AND expires_at < now()",
+        window_start
+    )
+    .execute(&state.db)
+    .instrument(db_span("DELETE email_otp_codes"))
+    .await?;
+
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query!("SELECT id FROM users WHERE id = $1 FOR NO KEY UPDATE", user_id)
+        .fetch_optional(&mut *tx)
+        .instrument(db_span("SELECT users"))
+        .await?;
+
+    let inserted = sqlx::query!(
+        "INSERT INTO email_otp_codes (id, user_id, purpose, code_hash, remember_me, expires_at)
+         SELECT $1, u.id, $3, $4, $5, $6 FROM users u
+         WHERE u.id = $2
+           AND (SELECT count(*) FROM email_otp_codes WHERE user_id = $2 AND created_at > $7) < $8",
+        otp_token,
+        user_id,
+        purpose,
+        code_hash(state, otp_token, &code)?,
+        remember_me,
+        Utc::now() + policy.ttl,
+        window_start,
+        policy.max_per_email
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("INSERT email_otp_codes"))
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    let Some((user_id, to)) = user else {
+        return Ok(None);
+    };
+
+    if inserted == 0 {
+        warn!(%user_id, purpose, "email otp rate limit reached");
+        metrics::email_otp("rate_limited");
+        return Ok(None);
+    }
+
+    let (subject, reason) = match purpose {
+        PURPOSE_STEP_UP => ("Your verification code", "to confirm it's you"),
+        _ => ("Your sign-in code", "to sign in"),
+    };
+
+    let email = Email {
+        from: sender(),
+        to,
+        subject: subject.into(),
+        text: format!(
+            "Your code is {code}. Enter it {reason}. It expires in {} minutes.\n\nIf you did not request it, you can ignore this email.",
+            policy.ttl.num_minutes()
+        ),
+    };
+
+    send_in_background(mailer, email, user_id, "email_otp");
+    metrics::email_otp("sent");
+
+    Ok(Some(otp_token))
+}
+
+/*
+|---------------------------------
+| ตรวจรหัส ---> (user_id, remember_me)
+| - ทุกครั้งที่ตรวจนับ attempts ก่อน ครบ EMAIL_OTP_MAX_ATTEMPTS แล้วรหัสนั้นใช้ไม่ได้อีก
+| - ผ่าน ---> used_at (ใช้ได้ครั้งเดียว แม้ส่งพร้อมกันสอง request)
+|---------------------------------
+*/
+async fn check_code(
+    state: &AppState,
+    otp_token: Uuid,
+    purpose: &'static str,
+    code: &str,
+) -> AppResult<Option<(Uuid, bool)>> {
+    let Some(code) = normalize_code(code) else {
+        metrics::email_otp("invalid");
+        return Ok(None);
+    };
+
+    let row = sqlx::query!(
+        "UPDATE email_otp_codes SET attempts = attempts + 1
+         WHERE id = $1 AND purpose = $2 AND used_at IS NULL AND expires_at > now() AND attempts < $3
+         RETURNING user_id, code_hash, remember_me",
+        otp_token,
+        purpose,
+        state.email_otp.max_attempts
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("UPDATE email_otp_codes"))
+    .await?;
+
+    let Some(row) = row.filter(|r| code_hash(state, otp_token, &code).is_ok_and(|h| h == r.code_hash)) else {
+        metrics::email_otp("invalid");
+        return Ok(None);
+    };
+
+    let used = sqlx::query!(
+        "UPDATE email_otp_codes SET used_at = now() WHERE id = $1 AND used_at IS NULL",
+        otp_token
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE email_otp_codes"))
+    .await?
+    .rows_affected();
+
+    if used == 0 {
+        metrics::email_otp("invalid");
+        return Ok(None);
+    }
+
+    metrics::email_otp("verified");
+
+    Ok(Some((row.user_id, row.remember_me)))
+}
+
+/*
+|---------------------------------
+| POST /auth/email-otp
+| - ส่งรหัส 6 หลักไปที่อีเมล คืน otp_token ไว้ใช้คู่กับรหัส
+| - อีเมลที่ไม่มี / บัญชีปิด / เกิน rate limit ---> otp_token สุ่มที่ไม่มีวันผ่าน (ตอบเหมือนกัน งาน DB ชุดเดียวกัน)
+|---------------------------------
+*/
+pub async fn request(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(req): ValidatedJson<EmailOtpRequest>,
+) -> AppResult<(StatusCode, Json<EmailOtpResponse>)> {
+    let mailer = state.mailer.clone().ok_or(AppError::NotFound)?;
+
+    let user = sqlx::query!(
+        r#"SELECT id, email::text as "email!" FROM users WHERE email = $1::text::citext AND is_active"#,
+        req.email
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT users"))
+    .await?;
+
+    if user.is_none() {
+        metrics::email_otp("unknown_email");
+    }
+
+    let user = user.map(|u| (u.id, u.email));
+    let otp_token = send_code(&state, mailer, user, PURPOSE_LOGIN, req.remember_me).await?;
+
+    let res = EmailOtpResponse {
+        otp_token: otp_token.unwrap_or_else(Uuid::new_v4),
+        expires_in: state.email_otp.ttl.num_seconds(),
+    };
+
+    Ok((StatusCode::ACCEPTED, Json(res)))
+}
+
+/*
+|---------------------------------
+| POST /auth/email-otp/verify
+| - ผ่าน ---> ออก session เหมือน /auth/login (เปิด MFA ไว้ ---> mfa_token แทน)
+| - ได้รหัสจากอีเมล = เป็นเจ้าของอีเมล ---> ตั้ง email_verified_at ถ้ายังไม่มี
+|---------------------------------
+*/
+pub async fn verify(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<EmailOtpVerifyRequest>,
+) -> AppResult<Response> {
+    if state.mailer.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let (user_id, remember_me) = check_code(&state, req.otp_token, PURPOSE_LOGIN, &req.code)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    let mfa_enabled = sqlx::query_scalar!(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, now())
+         WHERE id = $1 AND is_active
+         RETURNING mfa_enabled",
+        user_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .ok_or(AppError::InvalidCredentials)?;
+
+    if mfa_enabled {
+        return mfa_challenge(&state, user_id, remember_me, &[amr::EMAIL_OTP]).await;
+    }
+
+    let user = load_session_user(&state, user_id)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    metrics::login("success");
+
+    issue_session(&state, &headers, ip, jar, user, remember_me, &[amr::EMAIL_OTP]).await
+}
+
+/*
+|---------------------------------
+| POST /auth/step-up/email-otp (ต้องมี access token)
+| - ส่งรหัสไปที่อีเมลของ user ก่อนทำรายการสำคัญ (เปลี่ยนอีเมล, สร้าง API key ฯลฯ)
+| - เกิน rate limit ---> 429 (user รู้ตัวอยู่แล้ว ไม่ต้องซ่อน)
+| - เปิด MFA ---> 400 รหัสทางอีเมลเป็น factor เดียว ไม่ผ่าน route สำคัญ (ใช้ POST /auth/reauth + code)
+|---------------------------------
+*/
+pub async fn step_up_request(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> AppResult<(StatusCode, Json<EmailOtpResponse>)> {
+    let mailer = state.mailer.clone().ok_or(AppError::NotFound)?;
+
+    if user.mfa_enabled {
+        return Err(AppError::BadRequest("MFA is enabled: re-authenticate with a second factor".into()));
+    }
+
+    let email = sqlx::query_scalar!(r#"SELECT email::text as "email!" FROM users WHERE id = $1"#, user.id)
+        .fetch_optional(&state.db)
+        .instrument(db_span("SELECT users"))
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    let otp_token = send_code(&state, mailer, Some((user.id, email)), PURPOSE_STEP_UP, false)
+        .await?
+        .ok_or(AppError::TooManyRequests)?;
+
+    let res = EmailOtpResponse { otp_token, expires_in: state.email_otp.ttl.num_seconds() };
+
+    Ok((StatusCode::ACCEPTED, Json(res)))
+}
+
+/*
+|---------------------------------
+| POST /auth/step-up/email-otp/verify (ต้องมี access token)
+| - ผ่าน ---> access token ใหม่ที่ auth_time = ตอนนี้, amr = ["email_otp"] (ดู complete_reauth)
+| - รหัสผิด ---> 422 (ไม่ใช่ 401 ให้ client ไม่เข้าใจว่า token หมดอายุ)
+|---------------------------------
+*/
+pub async fn step_up_verify(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<EmailOtpVerifyRequest>,
+) -> AppResult<Json<ReauthResponse>> {
+    let verified = check_code(&state, req.otp_token, PURPOSE_STEP_UP, &req.code)
+        .await?
+        .filter(|(user_id, _)| *user_id == user.id);
+
+    if verified.is_none() {
+        let mut errors = ValidationErrors::new();
+        errors.add("code", ValidationError::new("invalid").with_message("code is incorrect or expired".into()));
+        return Err(errors.into());
+    }
+
+    complete_reauth(&state, &user, &jar, &[amr::EMAIL_OTP]).await
+}
@@ -0,0 +1,205 @@
+use std::net::IpAddr;
+
+use axum::{Json, http::{HeaderMap, StatusCode, header}, response::{IntoResponse, Response}};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{encode, Header, EncodingKey};
+use sqlx::types::ipnet::IpNet;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::app::{result::AppResult, state::AppState, telemetry::db_span};
+use crate::controllers::auth::amr;
+use crate::controllers::auth::login::{Claims, LoginResponse};
+use crate::controllers::auth::utils::{generate_csrf_token, generate_refresh_token, hash_refresh_token};
+use crate::utils::env::env_i64;
+
+// ข้อมูลของ user ที่ใช้ออก token (ยืนยันตัวตนเสร็จแล้ว)
+#[derive(Debug)]
+pub struct SessionUser {
+    pub id: Uuid,
+    pub username: String,
+    pub role: String,
+    pub token_version: i32,
+    pub is_active: bool,
+    pub must_change_password: bool,
+    // password_changed_at หรือวันสมัคร (คิดอายุรหัสผ่าน)
+    pub password_set_at: DateTime<Utc>,
+}
+
+pub async fn load_session_user(state: &AppState, id: Uuid) -> AppResult<Option<SessionUser>> {
+    let row = sqlx::query_as!(
+        SessionUser,
+        r#"
+            SELECT id, username::text as "username!", role, token_version, is_active,
+                   must_change_password,
+                   COALESCE(password_changed_at, created_at) as "password_set_at!"
+            FROM users WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT users"))
+    .await?;
+
+    Ok(row)
+}
+
+// เวลา / วิธียืนยันตัวตนล่าสุดของ session (claim auth_time / amr)
+#[derive(Clone, Debug)]
+pub struct AuthContext {
+    pub auth_time: DateTime<Utc>,
+    pub amr: Vec<String>,
+}
+
+/*
+|---------------------------------
+| เซ็น access token ของ user ---> (token, expires_in วินาที)
+| - ACCESS_TTL_MIN (ค่าเริ่มต้น 15, 1-120)
+|---------------------------------
+*/
+pub fn sign_access_token(
+    state: &AppState,
+    user: &SessionUser,
+    password_change_required: bool,
+    auth: &AuthContext,
+) -> AppResult<(String, i64)> {
+    let now = Utc::now();
+
+    // อ่านจาก ENV, ไม่มีก็ 15 นาที
+    let ttl_min = env_i64("ACCESS_TTL_MIN", 15)?;
+    
+    // กันค่าพิสดารเล็กน้อย (เช่น ไม่ให้ติดลบ/ยาวเกิน)
+    let ttl_min = ttl_min.clamp(1, 120);
+
+    let exp = now + chrono::Duration::minutes(ttl_min);
+
+    let claims = Claims {
+        sub: user.id,
+        username: user.username.clone(),
+        role: user.role.clone(),
+        iat: now.timestamp() as usize,
+        exp: exp.timestamp() as usize,
+        iss: state.jwt_issuer.clone(),
+        aud: state.jwt_audience.clone(),
+        jti: Uuid::new_v4().to_string(),
+        token_version: user.token_version,
+        scope: None,
+        client_id: None,
+        password_change_required,
+        auth_time: Some(auth.auth_time.timestamp() as usize),
+        amr: auth.amr.clone(),
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&state.jwt_secret),
+    )?;
+
+    Ok((token, (exp - now).num_seconds()))
+}
+
+/*
+|---------------------------------
+| ออก access token + refresh cookie + CSRF token (ปลายทางของทุกวิธี login)
+| - password / passkey / ลิงก์ทางอีเมล / MFA ที่ผ่านแล้วมาจบที่นี่
+| - methods = วิธีที่ผ่านใน login ครั้งนี้ ---> amr + auth_time ของ session
+| - ต้องเปลี่ยนรหัสผ่าน ---> restricted token อย่างเดียว ไม่มี refresh
+|---------------------------------
+*/
+pub async fn issue_session(
+    state: &AppState,
+    headers: &HeaderMap,
+    ip: Option<IpAddr>,
+    jar: CookieJar,
+    user: SessionUser,
+    remember_me: bool,
+    methods: &[&str],
+) -> AppResult<Response> {
+    // admin สั่ง / รหัสผ่านหมดอายุตาม role ---> ออก restricted token (เปลี่ยนรหัสผ่านได้อย่างเดียว)
+    let password_change_required = user.must_change_password
+        || state.password_policy.is_expired(&user.role, user.password_set_at);
+
+    let now = Utc::now();
+    let auth = AuthContext { auth_time: now, amr: amr::normalize(methods.iter().copied()) };
+
+    let (token, expires_in) = sign_access_token(state, &user, password_change_required, &auth)?;
+
+    // restricted token ไม่มี refresh token / cookie (ต่ออายุไม่ได้ เปลี่ยนรหัสเสร็จแล้ว login ใหม่)
+    if password_change_required {
+        let res = LoginResponse {
+            access_token: token,
+            token_type: "Bearer".into(),
+            expires_in,
+            csrf_token: None,
+            password_change_required,
+        };
+
+        return Ok((StatusCode::OK, Json(res)).into_response());
+    }
+
+    // ออก refresh token
+    let refresh_plain = generate_refresh_token()?;
+    let refresh_hash = hash_refresh_token(&refresh_plain, &state.refresh_secret)?;
+    
+    // เริ่ม session ใหม่: อายุตาม SessionPolicy (idle / remember me / absolute)
+    let session_started_at = now;
+    let refresh_exp = state
+        .session_policy
+        .expires_at(session_started_at, remember_me, now);
+
+    // browser / app / library
+    let user_agent: Option<String> = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    // IP ของ client (ClientIp: peer address / X-Forwarded-For จาก proxy ที่เชื่อ) ---> IpNet
+    let ip: Option<IpNet> = ip.map(IpNet::from);
+
+    sqlx::query!(
+        r#"
+            INSERT INTO refresh_tokens
+                (user_id, token_hash, user_agent, ip, expires_at, session_started_at, remember_me, auth_time, amr)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#,
+        user.id,
+        refresh_hash,
+        user_agent,
+        ip,
+        refresh_exp,
+        session_started_at,
+        remember_me,
+        auth.auth_time,
+        &auth.amr
+    )
+    .execute(&state.db)
+    .instrument(db_span("INSERT refresh_tokens"))
+    .await?;
+
+    // สร้าง refresh cookie ตาม CookiePolicy
+    // ไม่ได้เลือก remember me ---> session cookie (หายเมื่อปิด browser)
+    let refresh_cookie = state
+        .cookie_policy
+        .refresh_cookie(refresh_plain, remember_me.then_some(refresh_exp));
+
+    // CSRF token (signed double-submit ผูกกับ refresh token ตัวนี้) อายุเท่ากับ refresh cookie
+    let csrf_token = generate_csrf_token(&refresh_hash, &state.refresh_secret)?;
+    let csrf_cookie = state
+        .cookie_policy
+        .csrf_cookie(csrf_token.clone(), remember_me.then_some(refresh_exp));
+
+    // เตรียม response
+    let res = LoginResponse { 
+        access_token: token, 
+        token_type: "Bearer".into(), 
+        expires_in,
+        csrf_token: Some(csrf_token),
+        password_change_required,
+    };
+
+    let jar = jar.add(refresh_cookie).add(csrf_cookie);
+
+    Ok((jar, (StatusCode::OK, Json(res))).into_response())
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+use chrono::Utc;
+use tracing::{Instrument, warn};
+use uuid::Uuid;
+
+use crate::app::{metrics, result::AppResult, state::AppState, telemetry::db_span};
+
+/*
+|---------------------------------
+| ตัวนับรหัสผิด (failed_login_attempts / locked_until) ใช้ร่วมกันทุกจุดที่ตรวจรหัสผ่าน
+| - login, reauth, เปลี่ยนรหัสผ่าน นับรวมกัน ---> เดารหัสผ่านทางไหนก็โดนล็อกเหมือนกัน
+| - factor ที่สอง (TOTP / passkey / recovery code) ผิดก็นับ ล้างตัวนับเมื่อผ่านครบทุก factor เท่านั้น
+| - ครบ 5 ครั้งล็อก 15 นาที
+|---------------------------------
+*/
+
+// ถูกล็อกอยู่หรือไม่ (ไม่พบ user = ไม่ล็อก)
+pub async fn is_locked(state: &AppState, user_id: Uuid) -> AppResult<bool> {
+    let locked_until = sqlx::query_scalar!("SELECT locked_until FROM users WHERE id = $1", user_id)
+        .fetch_optional(&state.db)
+        .instrument(db_span("SELECT users"))
+        .await?
+        .flatten();
+
+    Ok(locked_until.is_some_and(|t| t > Utc::now()))
+}
+
+/*
+|---------------------------------
+| เพิ่ม failed_login_attempts (ครบ 5 ครั้งล็อก 15 นาที)
+| - None ---> UPDATE เดียวกันกับ id ที่ไม่มีจริง (ไม่นับ แต่เสียเวลา DB เท่ากัน)
+| - ไม่ critical ถ้าอัปเดตพลาดก็ไม่ต้อง fail ทั้งคำขอ
+|---------------------------------
+*/
+pub async fn record_failure(state: &AppState, user_id: Option<Uuid>) {
+    let attempts = sqlx::query_scalar!(
+        "UPDATE users
+         SET failed_login_attempts = failed_login_attempts + 1,
+             locked_until = CASE WHEN failed_login_attempts + 1 >= 5
+                                 THEN now() + interval '15 minutes'
+                                 ELSE locked_until END
+         WHERE id = $1
+         RETURNING failed_login_attempts",
+        user_id.unwrap_or(Uuid::nil())
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await
+    .inspect_err(|e| warn!(error = ?e, "failed login count update failed"))
+    .ok()
+    .flatten();
+
+    if attempts.is_some_and(|n| n >= 5) {
+        metrics::lockout();
+    }
+}
+
+// login ผ่านครบทุก factor ---> ล้างตัวนับ + last_login_at (ไม่ critical)
+pub async fn record_login(state: &AppState, user_id: Uuid) {
+    let _ = sqlx::query!(
+        "UPDATE users
+         SET failed_login_attempts = 0,
+             locked_until = NULL,
+             last_login_at = now()
+         WHERE id = $1",
+        user_id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await
+    .inspect_err(|e| warn!(error = ?e, "login bookkeeping update failed"));
+}
+
+// ผ่านครบทุก factor แล้ว ---> ล้างตัวนับ
+pub async fn reset(state: &AppState, user_id: Uuid) -> AppResult<()> {
+    sqlx::query!(
+        "UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = $1",
+        user_id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?;
+
+    Ok(())
+}
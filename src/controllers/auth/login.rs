@@ -1,25 +1,31 @@
-use std::{net::IpAddr, sync::Arc};
+use std::sync::Arc;
 
-use argon2::{Argon2, PasswordHash, PasswordVerifier};
-use axum::{Json, extract::State, http::{HeaderMap, StatusCode, header}, response::{IntoResponse, Response}};
-use axum_extra::extract::cookie::{Cookie, SameSite};
-use chrono::{Duration, Utc};
+use axum::{extract::State, http::HeaderMap, response::Response};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use jsonwebtoken::{encode, Header, EncodingKey};
-use sqlx::types::ipnet::IpNet;
+use tracing::{Instrument, warn};
 use uuid::Uuid;
-use cookie::time::OffsetDateTime;
-use crate::{app::{error::AppError, result::AppResult, state::AppState}, controllers::auth::utils::{generate_refresh_token, hash_refresh_token}, utils::env::env_i64};
+use validator::Validate;
+use crate::app::client_ip::ClientIp;
+use crate::{app::{error::AppError, metrics, password::Verified, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson}, controllers::auth::{amr, lockout, issue::{SessionUser, issue_session}, mfa::mfa_challenge}};
 
-#[derive(Debug, Deserialize)]
+// ไม่ derive Debug กัน password หลุดลง log
+// ไม่ตรวจ policy ของรหัสผ่านตอน login (แค่จำกัดความยาวกันงาน Argon2 หนักเกิน)
+#[derive(Deserialize, Validate)]
 pub struct LoginRequest {
+    #[validate(length(min = 1, max = 64))]
     pub username: String,
+    #[validate(length(min = 1, max = 1024))]
     pub password: String,
+    // ไม่ส่งมา = false ---> cookie แบบ session + อายุสั้นกว่า
+    #[serde(default)]
+    pub remember_me: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
-    pub sub: uuid::Uuid,
+    pub sub: Uuid,
     pub username: String,
     pub role: String,
     pub iat: usize,
@@ -28,19 +34,41 @@ pub struct Claims {
     pub aud: String,
     pub jti: String,
     pub token_version: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    // มีค่าเฉพาะ token ของ service account (client_credentials)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    // restricted token: ใช้ได้แค่ POST /auth/password
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_change_required: bool,
+    // เวลายืนยันตัวตนล่าสุดของ session (unix) + วิธีที่ใช้ ตาม OIDC (ไม่มีใน token ของ service account)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_time: Option<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub amr: Vec<String>,
 }
 
-#[derive(Debug, Serialize)]
+// ไม่ derive Debug กัน token หลุดลง log
+#[derive(Serialize)]
 pub struct LoginResponse {
     pub access_token: String,
     pub token_type: String, // "Bearer"
     pub expires_in: i64, // วินาที
+    // ส่งกลับมาใน X-CSRF-Token ตอนเรียก /auth/refresh, /auth/logout (ไม่มีเมื่อเป็น restricted token)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub csrf_token: Option<String>,
+    // true = access_token ใช้ได้แค่ POST /auth/password
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub password_change_required: bool,
 }
 
 pub async fn login(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(payload): Json<LoginRequest>,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(payload): ValidatedJson<LoginRequest>,
 ) -> AppResult<Response> {
     let user = sqlx::query!(
         r#"
@@ -50,138 +78,101 @@ pub async fn login(
                 password_hash, 
                 role,
                 is_active,
-                token_version 
+                token_version,
+                locked_until,
+                mfa_enabled,
+                must_change_password,
+                COALESCE(password_changed_at, created_at) as "password_set_at!"
             FROM users WHERE username = $1
         "#,
         payload.username
     )
     .fetch_optional(&state.db)
-    .await?
-    .ok_or_else(|| AppError::NotFound)?;
+    .instrument(db_span("SELECT users"))
+    .await?;
+
+    let locked = user
+        .as_ref()
+        .is_some_and(|u| u.locked_until.is_some_and(|t| t > Utc::now()));
+
+    // ไม่พบ user / ถูกล็อก ---> verify กับ dummy hash แทน ให้ทุกกรณีเสีย Argon2 เท่ากัน
+    let verified = match &user {
+        Some(u) if !locked => state.password_hasher.verify(&payload.password, &u.password_hash)?,
+        _ => {
+            state.password_hasher.verify_dummy(&payload.password)?;
+            Verified { ok: false, needs_rehash: false }
+        }
+    };
 
-    if !user.is_active { 
-        return Err(AppError::Unauthorized);
+    // ทุกกรณีที่ login ไม่ผ่านตอบ InvalidCredentials เหมือนกัน (เหตุผลจริงดูจาก metrics/log)
+    // และรัน UPDATE ตัวนับเหมือนกันทุกกรณี ให้เวลา DB ไม่บอกว่ามี user นี้หรือไม่
+    let Some(user) = user else {
+        lockout::record_failure(&state, None).await;
+        metrics::login("not_found");
+        return Err(AppError::InvalidCredentials);
+    };
+
+    // ถูกล็อกจากการใส่รหัสผิดซ้ำ ---> ไม่นับว่าผ่านจนกว่าจะหมดเวลา
+    if locked {
+        lockout::record_failure(&state, None).await;
+        metrics::login("locked");
+        return Err(AppError::InvalidCredentials);
     }
-   
-    let parsed_hash = PasswordHash::new(&user.password_hash)?;
-
-    Argon2::default()
-        .verify_password(payload.password.as_bytes(), &parsed_hash)
-        .map_err(|_| {
-            // เพิ่ม failed_attempts เมื่อพลาด
-            // (ไม่ critical ถ้าอัปเดตพลาดก็ไม่ต้อง fail ทั้งคำขอ)
-            let _ = sqlx::query!(
-                "UPDATE users
-                 SET failed_login_attempts = failed_login_attempts + 1,
-                     locked_until = CASE WHEN failed_login_attempts + 1 >= 5
-                                         THEN now() + interval '15 minutes'
-                                         ELSE locked_until END
-                 WHERE id = $1",
-                user.id
-            )
-            .execute(&state.db);
-            AppError::Unauthorized
-        })?;
 
-    // ผ่านแล้ว รีเซ็ตตัวนับ + อัปเดต last_login_at
-    let _ = sqlx::query!(
-        "UPDATE users
-         SET failed_login_attempts = 0,
-             locked_until = NULL,
-             last_login_at = now()
-         WHERE id = $1",
-        user.id
-    )
-    .execute(&state.db)
-    .await;
+    if !verified.ok {
+        lockout::record_failure(&state, Some(user.id)).await;
+        metrics::login("invalid_credentials");
+        return Err(AppError::InvalidCredentials);
+    }
 
-    let now = Utc::now();
+    if !user.is_active {
+        lockout::record_failure(&state, None).await;
+        metrics::login("inactive");
+        return Err(AppError::InvalidCredentials);
+    }
 
-    // อ่านจาก ENV, ไม่มีก็ 15 นาที
-    let ttl_min = env_i64("ACCESS_TTL_MIN", 15)?;
-    
-    // กันค่าพิสดารเล็กน้อย (เช่น ไม่ให้ติดลบ/ยาวเกิน)
-    let ttl_min = ttl_min.clamp(1, 120);
+    // ผ่านแล้ว รีเซ็ตตัวนับ + อัปเดต last_login_at
+    // (เปิด MFA ---> รอจนผ่าน factor ที่สอง ไม่งั้นรู้รหัสผ่านแล้วเดา TOTP ได้ไม่จำกัด)
+    if !user.mfa_enabled {
+        lockout::record_login(&state, user.id).await;
+    }
 
-    let exp = now + chrono::Duration::minutes(ttl_min);
+    // hash ด้วย params เก่า / ก่อนเปิด pepper ---> hash ใหม่ตอนที่มีรหัสจริงอยู่ในมือ
+    // (เทียบ hash เดิมใน WHERE กันทับรหัสที่เพิ่งถูกเปลี่ยนไประหว่างนี้)
+    if verified.needs_rehash {
+        match state.password_hasher.hash(&payload.password) {
+            Ok(new_hash) => {
+                let _ = sqlx::query!(
+                    "UPDATE users SET password_hash = $1 WHERE id = $2 AND password_hash = $3",
+                    new_hash,
+                    user.id,
+                    user.password_hash
+                )
+                .execute(&state.db)
+                .instrument(db_span("UPDATE users"))
+                .await
+                .inspect_err(|e| warn!(error = ?e, "password rehash failed"));
+            }
+            Err(e) => warn!(error = ?e, "password rehash failed"),
+        }
+    }
 
-    let claims = Claims {
-        sub: user.id,
+    let session_user = SessionUser {
+        id: user.id,
         username: user.username,
         role: user.role,
-        iat: now.timestamp() as usize,
-        exp: exp.timestamp() as usize,
-        iss: state.jwt_issuer.clone(),
-        aud: state.jwt_audience.clone(),
-        jti: Uuid::new_v4().to_string(),
         token_version: user.token_version,
+        is_active: user.is_active,
+        must_change_password: user.must_change_password,
+        password_set_at: user.password_set_at,
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(&state.jwt_secret),
-    )?;
-
-    // ออก refresh token
-    let refresh_plain = generate_refresh_token()?;
-    let refresh_hash = hash_refresh_token(&refresh_plain, &state.refresh_secret)?;
-    
-    // หมดอายุ 30 วัน
-    let refresh_exp = Utc::now() + Duration::days(30);
-
-    // refresh_exp = chrono::DateTime<Utc> ---> แปลงเป็น OffsetDateTime
-    let expires = OffsetDateTime::from_unix_timestamp(refresh_exp.timestamp())
-        .map_err(|e| AppError::InternalError(format!("valid timestamp: {:?}", e).into()))?;
-
-    // browser / app / library
-    let user_agent: Option<String> = headers
-        .get(header::USER_AGENT)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
-
-    // IP
-    let ip: Option<IpAddr> = Some("192.168.1.10".parse().unwrap());
-
-    // แปลงเป็น IpNet
-    let ip: Option<IpNet> = ip.map(IpNet::from);
-
-    sqlx::query!(
-        r#"
-            INSERT INTO refresh_tokens (user_id, token_hash, user_agent, ip, expires_at)
-            VALUES ($1, $2, $3, $4, $5)
-        "#,
-        user.id,
-        refresh_hash,
-        user_agent,
-        ip,
-        refresh_exp
-    )
-    .execute(&state.db)
-    .await?;
-
-    // สร้าง refresh cookie
-    // ตั้งค่าแบบ universal (ไม่พึ่ง builder API)
-    let mut refresh_cookie = Cookie::new("refresh_token", refresh_plain);
-    refresh_cookie.set_http_only(true);
-    refresh_cookie.set_secure(false); // dev = false
-    // refresh_cookie.set_secure(true);
-    refresh_cookie.set_same_site(SameSite::Lax); // หรือ Strict
-    refresh_cookie.set_path("/auth");
-    refresh_cookie.set_expires(expires);
-
-    // เตรียม response
-    let res = LoginResponse { 
-        access_token: token.clone(), 
-        token_type: "Bearer".into(), 
-        expires_in: (exp - now).num_seconds() 
-    };
+    // เปิด MFA ---> ยังไม่ออก token ให้ยืนยัน factor ที่สองก่อน
+    if user.mfa_enabled {
+        return mfa_challenge(&state, session_user.id, payload.remember_me, &[amr::PASSWORD]).await;
+    }
 
-    let response = (
-        StatusCode::OK,
-        [(header::SET_COOKIE, refresh_cookie.to_string())],
-        Json(res),
-    ).into_response();
+    metrics::login("success");
 
-    Ok(response)
-}
\ No newline at end of file
+    issue_session(&state, &headers, ip, jar, session_user, payload.remember_me, &[amr::PASSWORD]).await
+}
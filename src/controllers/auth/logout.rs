@@ -1,15 +1,15 @@
 use std::sync::Arc;
 use axum::{extract::State, http::{StatusCode}};
-use cookie::Cookie;
-use crate::{app::{result::AppResult, state::AppState}, controllers::auth::utils::hash_refresh_token};
+use crate::{app::{metrics, result::AppResult, state::AppState, telemetry::db_span}, controllers::auth::utils::hash_refresh_token};
 use axum_extra::extract::cookie::CookieJar;
+use tracing::Instrument;
 
 pub async fn logout(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
 ) -> AppResult<impl axum::response::IntoResponse> {
     // revoke ใน DB ถ้ามี cookie
-    if let Some(c) = jar.get("refresh_token") {
+    if let Some(c) = jar.get(state.cookie_policy.name()) {
         let hash = hash_refresh_token(c.value(), &state.refresh_secret)?;
         
         sqlx::query!(
@@ -18,15 +18,16 @@ pub async fn logout(
             hash
         )
         .execute(&state.db)
+        .instrument(db_span("UPDATE refresh_tokens"))
         .await?;
     }
 
-    // ลบคุกกี้ด้วย CookieJar (ต้องตั้ง path ให้ตรงกับตอน set)
-    let jar = jar.remove(
-        Cookie::build("refresh_token")
-            .path("/auth")
-            .build(),
-    );
+    // ลบคุกกี้ด้วย CookieJar (attribute ต้องตรงกับตอน set ---> ใช้ CookiePolicy)
+    let jar = jar
+        .add(state.cookie_policy.removal_cookie())
+        .add(state.cookie_policy.csrf_removal_cookie());
+
+    metrics::logout();
 
     // คืน NO_CONTENT + Set-Cookie (ลบทิ้ง) โดยไม่ต้อง .into_response()
     Ok((jar, StatusCode::NO_CONTENT))
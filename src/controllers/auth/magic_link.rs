@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State, http::{HeaderMap, StatusCode}, response::{IntoResponse, Response}};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::app::client_ip::ClientIp;
+use crate::app::{error::AppError, mailer::{Email, send_in_background, sender}, metrics, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson};
+use crate::controllers::auth::amr;
+use crate::controllers::auth::issue::{issue_session, load_session_user};
+use crate::controllers::auth::mfa::mfa_challenge;
+use crate::controllers::auth::utils::{generate_refresh_token, hash_refresh_token};
+
+#[derive(Deserialize, Validate)]
+pub struct MagicLinkRequest {
+    #[validate(email, length(max = 254))]
+    pub email: String,
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+// ตอบเหมือนกันทุกกรณี (ไม่บอกว่ามีอีเมลนี้ในระบบหรือไม่)
+#[derive(Serialize)]
+pub struct MagicLinkResponse {
+    pub expires_in: i64,
+}
+
+// ไม่ derive Debug กัน token หลุดลง log
+#[derive(Deserialize, Validate)]
+pub struct MagicLinkConsumeRequest {
+    #[validate(length(min = 1, max = 128))]
+    pub token: String,
+}
+
+/*
+|---------------------------------
+| POST /auth/magic-link
+| - ตั้ง nonce cookie ทุกครั้ง ลิงก์ใช้ได้เฉพาะ browser ที่มี nonce นี้ (ขอใหม่ = ลิงก์เก่าจาก browser นี้ใช้ไม่ได้)
+| - อีเมลที่ไม่มี / บัญชีปิด / เกิน rate limit ---> ไม่ส่ง แต่ตอบ 202 เหมือนกัน (งาน DB ชุดเดียวกัน)
+| - ส่งเมลใน background
+|---------------------------------
+*/
+pub async fn request(
+    State(state): State<Arc<AppState>>,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<MagicLinkRequest>,
+) -> AppResult<Response> {
+    let mailer = state.mailer.clone().ok_or(AppError::NotFound)?;
+    let policy = &state.magic_link;
+
+    let nonce = generate_refresh_token()?;
+    let jar = jar.add(state.cookie_policy.magic_link_cookie(nonce.clone(), policy.ttl));
+    let accepted = (
+        StatusCode::ACCEPTED,
+        jar,
+        Json(MagicLinkResponse { expires_in: policy.ttl.num_seconds() }),
+    );
+
+    let token = generate_refresh_token()?;
+    let window_start = Utc::now() - policy.window;
+
+    // อีเมลที่ไม่มีในระบบก็รันคำสั่งชุดเดียวกัน (id = nil ไม่ตรงกับแถวไหน) ให้เวลาตอบไม่บอกว่ามี user หรือไม่
+    sqlx::query!(
+        "DELETE FROM magic_link_tokens WHERE created_at < $1 AND expires_at < now()",
+        window_start
+    )
+    .execute(&state.db)
+    .instrument(db_span("DELETE magic_link_tokens"))
+    .await?;
+
+    let mut tx = state.db.begin().await?;
+
+    // ล็อกแถว user ---> คำขอพร้อมกันของอีเมลเดียวกันนับทีละคำขอ (ไม่หลุดเกิน max_per_email)
+    let user = sqlx::query!(
+        r#"SELECT id, email::text as "email!" FROM users WHERE email = $1::text::citext AND is_active FOR NO KEY UPDATE"#,
+        req.email
+    )
+    .fetch_optional(&mut *tx)
+    .instrument(db_span("SELECT users"))
+    .await?;
+
+    // นับรวมลิงก์ที่ใช้ไปแล้ว / หมดอายุแล้วในช่วงเวลาเดียวกัน
+    let inserted = sqlx::query!(
+        "INSERT INTO magic_link_tokens (user_id, token_hash, nonce_hash, remember_me, expires_at)
+         SELECT u.id, $2, $3, $4, $5 FROM users u
+         WHERE u.id = $1
+           AND (SELECT count(*) FROM magic_link_tokens WHERE user_id = $1 AND created_at > $6) < $7",
+        user.as_ref().map_or(Uuid::nil(), |u| u.id),
+        hash_refresh_token(&format!("magic:{token}"), &state.refresh_secret)?,
+        hash_refresh_token(&format!("magic-nonce:{nonce}"), &state.refresh_secret)?,
+        req.remember_me,
+        Utc::now() + policy.ttl,
+        window_start,
+        policy.max_per_email
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("INSERT magic_link_tokens"))
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+
+    let Some(user) = user else {
+        metrics::magic_link("unknown_email");
+        return Ok(accepted.into_response());
+    };
+
+    if inserted == 0 {
+        warn!(user_id = %user.id, "magic link rate limit reached");
+        metrics::magic_link("rate_limited");
+        return Ok(accepted.into_response());
+    }
+
+    let email = Email {
+        from: sender(),
+        to: user.email,
+        subject: "Your sign-in link".into(),
+        text: format!(
+            "Use this link to sign in. It expires in {} minutes and works only once, in the browser where you requested it.\n\n{}\n\nIf you did not request it, you can ignore this email.",
+            policy.ttl.num_minutes(),
+            policy.link(&token)
+        ),
+    };
+
+    send_in_background(mailer, email, user.id, "magic_link");
+
+    metrics::magic_link("sent");
+
+    Ok(accepted.into_response())
+}
+
+/*
+|---------------------------------
+| POST /auth/magic-link/consume
+| - ต้องมี nonce cookie จาก browser เดียวกับที่ขอลิงก์
+| - ใช้ token ครั้งเดียว (UPDATE ... used_at IS NULL) แล้วออก session เหมือน /auth/login
+| - เปิด MFA ไว้ ---> ได้ mfa_token แทน (ลิงก์นับเป็น factor แรกแทนรหัสผ่าน)
+| - คลิกลิงก์ได้ = เป็นเจ้าของอีเมล ---> ตั้ง email_verified_at ถ้ายังไม่มี
+|---------------------------------
+*/
+pub async fn consume(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<MagicLinkConsumeRequest>,
+) -> AppResult<Response> {
+    if state.mailer.is_none() {
+        return Err(AppError::NotFound);
+    }
+
+    let Some(nonce) = jar.get(state.cookie_policy.magic_link_name()).map(|c| c.value().to_string()) else {
+        metrics::magic_link("invalid");
+        return Err(AppError::InvalidCredentials);
+    };
+
+    let row = sqlx::query!(
+        "UPDATE magic_link_tokens SET used_at = now()
+         WHERE token_hash = $1 AND nonce_hash = $2 AND used_at IS NULL AND expires_at > now()
+         RETURNING user_id, remember_me",
+        hash_refresh_token(&format!("magic:{}", req.token), &state.refresh_secret)?,
+        hash_refresh_token(&format!("magic-nonce:{nonce}"), &state.refresh_secret)?
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("UPDATE magic_link_tokens"))
+    .await?;
+
+    let Some(row) = row else {
+        metrics::magic_link("invalid");
+        return Err(AppError::InvalidCredentials);
+    };
+
+    let jar = jar.add(state.cookie_policy.magic_link_removal_cookie());
+
+    let mfa_enabled = sqlx::query_scalar!(
+        "UPDATE users SET email_verified_at = COALESCE(email_verified_at, now())
+         WHERE id = $1 AND is_active
+         RETURNING mfa_enabled",
+        row.user_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .ok_or(AppError::InvalidCredentials)?;
+
+    metrics::magic_link("consumed");
+
+    if mfa_enabled {
+        let challenge = mfa_challenge(&state, row.user_id, row.remember_me, &[amr::MAGIC_LINK]).await?;
+        return Ok((jar, challenge).into_response());
+    }
+
+    let user = load_session_user(&state, row.user_id)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    metrics::login("success");
+
+    issue_session(&state, &headers, ip, jar, user, row.remember_me, &[amr::MAGIC_LINK]).await
+}
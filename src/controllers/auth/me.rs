@@ -3,91 +3,145 @@ use axum::extract::FromRequestParts;
 use axum::http::request::Parts;
 use axum_extra::TypedHeader;
 use axum_extra::headers::{Authorization, authorization::Bearer};
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::app::metrics;
 use crate::app::state::AppState;
 use crate::app::error::AppError;
-use crate::controllers::auth::login::Claims;
+use crate::controllers::auth::verify::{password_change_required, verify_access_token};
 
+/*
+|---------------------------------
+| เจ้าของ access token ที่เป็นคน (users)
+| - token ของ service account (client_credentials) ถูกปฏิเสธเสมอ (403)
+|   handler ไม่ต้องเช็คเอง ---> route ของ service ใช้ ServicePrincipal แทน
+|---------------------------------
+*/
 #[derive(Clone, Debug)]
 pub struct AuthUser {
     pub id: Uuid,
     pub username: String,
     pub role: String,
+    pub scopes: Vec<String>,
+    // true = ใช้ได้แค่เปลี่ยนรหัสผ่าน (ดู PasswordChangeUser)
+    pub password_change_required: bool,
+    // เวลา / วิธียืนยันตัวตนล่าสุด (login หรือ step-up)
+    pub auth_time: Option<DateTime<Utc>>,
+    pub amr: Vec<String>,
+    pub mfa_enabled: bool,
 }
 
-impl FromRequestParts<Arc<AppState>> for AuthUser {
-    type Rejection = AppError;
+// เจ้าของ access token ที่เป็น service account (client_credentials) ---> id = oauth_clients.id
+#[derive(Clone, Debug)]
+pub struct ServicePrincipal {
+    pub id: Uuid,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+}
 
-    async fn from_request_parts(
-        parts: &mut Parts,
-        state: &Arc<AppState>,
-    ) -> Result<Self, Self::Rejection> {
+impl AuthUser {
+    // ตรวจ token + สถานะเจ้าของ ---> (AuthUser, client_id ถ้าเป็น service account)
+    // (ยังไม่ตัดสินเรื่อง restricted token / ชนิดของเจ้าของ token)
+    async fn authenticate(parts: &mut Parts, state: &Arc<AppState>) -> Result<(Self, Option<String>), AppError> {
         // 1) ดึง Bearer token
         let TypedHeader(Authorization(bearer)) =
             TypedHeader::<Authorization<Bearer>>::from_request_parts(parts, state)
                 .await
-                .map_err(|_| AppError::Unauthorized)?;
-
-        // 2) ตั้งค่า Validation ให้ตรวจ exp/iss/aud/leeway
-        let mut v = Validation::new(Algorithm::HS256);
-        v.validate_exp = true;
-        v.leeway = 30;
-        v.set_issuer(&[&state.jwt_issuer]);
-        v.set_audience(&[state.jwt_audience.clone()]);
-
-        // 3) decode + verify
-        let data = decode::<Claims>(
-            bearer.token(),
-            &DecodingKey::from_secret(&state.jwt_secret), // Vec<u8> ---> &[u8]
-            &v,
-        )?;
-
-        let claims = data.claims;
-
-        // 4) (ออปชัน) เช็ค jti blocklist ถ้ามีระบบเก็บ
-        // if state.is_blocklisted(&claims.jti).await? {
-        //     return Err(AppError::Unauthorized);
-        // }
-
-        // 5) โหลดผู้ใช้จาก DB เพื่อตรวจ token_version / is_active / password_changed_at
-        let user = sqlx::query!(
-            r#"
-            SELECT
-            id, username, role, is_active, token_version,
-            password_changed_at as "password_changed_at: chrono::DateTime<chrono::Utc>"
-            FROM users
-            WHERE id = $1
-            "#,
-            claims.sub
-        )
-        .fetch_optional(&state.db)
-        .await?
-        .ok_or(AppError::Unauthorized)?;
-
-        if !user.is_active {
-            return Err(AppError::Unauthorized);
-        }
+                .map_err(|_| {
+                    metrics::access_check("rejected");
+                    AppError::Unauthorized
+                })?;
 
-        // ตรวจ token_version ให้ตรงกับ DB
-        if claims.token_version != user.token_version {
-            return Err(AppError::Unauthorized);
-        }
+        // 2) ตรวจ token + สถานะผู้ใช้
+        let (claims, user) = verify_access_token(state, bearer.token())
+            .await
+            .inspect_err(|_| metrics::access_check("rejected"))?;
 
-        // ตรวจ iat กับ password_changed_at (ถ้ามี)
-        if let Some(changed_at) = user.password_changed_at {
-            if (claims.iat as i64) < changed_at.timestamp() {
-                return Err(AppError::Unauthorized);
-            }
-        }
+        metrics::access_check("accepted");
+
+        let password_change_required = password_change_required(state, &claims, &user);
 
-        Ok(AuthUser {
+        let scopes = claims
+            .scope
+            .as_deref()
+            .map(|s| s.split_whitespace().map(String::from).collect())
+            .unwrap_or_default();
+
+        let auth_user = AuthUser {
             id: claims.sub,
             username: user.username,
             role: user.role,
-        })
+            scopes,
+            password_change_required,
+            auth_time: claims.auth_time.and_then(|t| DateTime::from_timestamp(t as i64, 0)),
+            amr: claims.amr,
+            mfa_enabled: user.mfa_enabled,
+        };
+
+        Ok((auth_user, claims.client_id))
+    }
+
+    // เฉพาะคน (token ของ service account ---> 403)
+    async fn authenticate_user(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, AppError> {
+        match Self::authenticate(parts, state).await? {
+            (user, None) => Ok(user),
+            (_, Some(_)) => Err(AppError::Forbidden),
+        }
+    }
+}
+
+// ใช้ได้ทุก endpoint ---> restricted token ถูกปฏิเสธด้วย password_change_required
+impl FromRequestParts<Arc<AppState>> for AuthUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let user = Self::authenticate_user(parts, state).await?;
+
+        if user.password_change_required {
+            return Err(AppError::PasswordChangeRequired);
+        }
+
+        Ok(user)
+    }
+}
+
+/*
+|---------------------------------
+| AuthUser ที่ยอมรับ restricted token ด้วย
+| - ใช้เฉพาะ endpoint เปลี่ยนรหัสผ่าน
+|---------------------------------
+*/
+#[derive(Clone, Debug)]
+pub struct PasswordChangeUser(pub AuthUser);
+
+impl FromRequestParts<Arc<AppState>> for PasswordChangeUser {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        AuthUser::authenticate_user(parts, state).await.map(PasswordChangeUser)
+    }
+}
+
+// route ของ service account เท่านั้น (token ของคน ---> 403)
+impl FromRequestParts<Arc<AppState>> for ServicePrincipal {
+    type Rejection = AppError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        match AuthUser::authenticate(parts, state).await? {
+            (user, Some(client_id)) => Ok(ServicePrincipal { id: user.id, client_id, scopes: user.scopes }),
+            (_, None) => Err(AppError::Forbidden),
+        }
     }
 }
 
@@ -96,6 +150,9 @@ pub async fn me(user: AuthUser) -> Json<serde_json::Value> {
     Json(serde_json::json!({
         "id": user.id,
         "username": user.username,
-        "role": user.role
+        "role": user.role,
+        "scopes": user.scopes,
+        "auth_time": user.auth_time,
+        "amr": user.amr
     }))
 }
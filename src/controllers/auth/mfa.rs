@@ -0,0 +1,342 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State, http::{HeaderMap, StatusCode}, response::{IntoResponse, Response}};
+use axum_extra::extract::cookie::CookieJar;
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+use webauthn_rs::prelude::{PasskeyAuthentication, PublicKeyCredential, RequestChallengeResponse};
+
+use crate::app::client_ip::ClientIp;
+use crate::app::{audit, ceremony::CeremonyKind, encryption::column_context, error::AppError, metrics, recovery_codes, result::AppResult, state::AppState, telemetry::db_span, totp, validation::ValidatedJson};
+use crate::controllers::auth::{amr, lockout};
+use crate::controllers::auth::issue::{issue_session, load_session_user};
+use crate::controllers::auth::webauthn::{load_passkeys, record_use};
+
+// รหัสผ่านผ่านแล้ว แต่ต้องยืนยัน factor ที่สองก่อนได้ token
+#[derive(Serialize)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    // ส่งกลับมาตอนยืนยัน factor ที่สอง (ใช้ได้ครั้งเดียว)
+    pub mfa_token: Uuid,
+    // วิธีที่ user คนนี้ใช้ได้ เช่น ["webauthn"]
+    pub methods: Vec<&'static str>,
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MfaWebauthnBeginRequest {
+    pub mfa_token: Uuid,
+}
+
+#[derive(Serialize)]
+pub struct MfaWebauthnBeginResponse {
+    // ส่งต่อให้ navigator.credentials.get()
+    pub options: RequestChallengeResponse,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MfaWebauthnFinishRequest {
+    pub mfa_token: Uuid,
+    pub credential: PublicKeyCredential,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct MfaCodeRequest {
+    pub mfa_token: Uuid,
+    #[validate(length(min = 1, max = 32))]
+    pub code: String,
+}
+
+// factor แรก (จำไว้ใน ceremony) + factor ที่สองที่เพิ่งผ่าน
+fn with_factor<'a>(first: &'a [String], second: &'a str) -> Vec<&'a str> {
+    first.iter().map(String::as_str).chain([second]).collect()
+}
+
+// factor ที่สองที่ user ลงทะเบียนไว้
+pub async fn available_methods(state: &AppState, user_id: Uuid) -> AppResult<Vec<&'static str>> {
+    let row = sqlx::query!(
+        r#"SELECT
+             EXISTS(SELECT 1 FROM webauthn_credentials WHERE user_id = $1) as "has_passkey!",
+             EXISTS(SELECT 1 FROM users WHERE id = $1 AND mfa_totp_secret IS NOT NULL) as "has_totp!",
+             EXISTS(SELECT 1 FROM mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL) as "has_recovery!""#,
+        user_id
+    )
+    .fetch_one(&state.db)
+    .instrument(db_span("SELECT mfa_methods"))
+    .await?;
+
+    let mut methods = Vec::new();
+    if row.has_totp {
+        methods.push(amr::TOTP);
+    }
+    if row.has_passkey {
+        methods.push(amr::WEBAUTHN);
+    }
+    if row.has_recovery {
+        methods.push(amr::RECOVERY_CODE);
+    }
+
+    Ok(methods)
+}
+
+/*
+|---------------------------------
+| เริ่มขั้นตอน MFA หลัง factor แรกผ่าน (/auth/login, ลิงก์ / รหัสทางอีเมล)
+| - remember_me + factor แรก (amr) ถูกจำไว้ใน ceremony ใช้ตอนออก token
+|---------------------------------
+*/
+pub async fn mfa_challenge(state: &AppState, user_id: Uuid, remember_me: bool, first: &[&str]) -> AppResult<Response> {
+    let methods = available_methods(state, user_id).await?;
+
+    let mfa_token = state
+        .ceremonies
+        .create::<()>(&state.db, CeremonyKind::Mfa, Some(user_id), None, remember_me, first)
+        .await?;
+
+    metrics::login("mfa_required");
+
+    let res = MfaChallengeResponse {
+        mfa_required: true,
+        mfa_token,
+        methods,
+        expires_in: state.ceremonies.ttl().num_seconds(),
+    };
+
+    Ok((StatusCode::OK, Json(res)).into_response())
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/webauthn/begin
+| - challenge ที่ allowCredentials = passkey ของ user คนนั้นเท่านั้น
+| - เรียกซ้ำได้ (challenge ใหม่ทับของเดิม) จนกว่า mfa_token จะหมดอายุ
+|---------------------------------
+*/
+pub async fn webauthn_begin(
+    State(state): State<Arc<AppState>>,
+    ValidatedJson(req): ValidatedJson<MfaWebauthnBeginRequest>,
+) -> AppResult<Json<MfaWebauthnBeginResponse>> {
+    let user_id = sqlx::query_scalar!(
+        "SELECT user_id FROM auth_ceremonies WHERE id = $1 AND kind = $2 AND expires_at > now()",
+        req.mfa_token,
+        CeremonyKind::Mfa.as_str()
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT auth_ceremonies"))
+    .await?
+    .flatten()
+    .ok_or(AppError::InvalidCredentials)?;
+
+    let passkeys = load_passkeys(&state, user_id).await?;
+    if passkeys.is_empty() {
+        return Err(AppError::BadRequest("no passkey registered".into()));
+    }
+
+    let (options, authentication) = state
+        .webauthn
+        .start_passkey_authentication(&passkeys)
+        .map_err(|e| AppError::InternalError(format!("webauthn: {e:?}")))?;
+
+    state
+        .ceremonies
+        .set_state(&state.db, req.mfa_token, CeremonyKind::Mfa, &authentication)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    Ok(Json(MfaWebauthnBeginResponse { options }))
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/webauthn/finish
+| - mfa_token ถูกใช้ไปแล้วไม่ว่าผลจะผ่านหรือไม่ (พลาด = login ด้วยรหัสผ่านใหม่)
+| - ผ่าน ---> ออก token / refresh cookie แบบเดียวกับ /auth/login
+|---------------------------------
+*/
+pub async fn webauthn_finish(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<MfaWebauthnFinishRequest>,
+) -> AppResult<Response> {
+    let ceremony = state
+        .ceremonies
+        .take::<PasskeyAuthentication>(&state.db, req.mfa_token, CeremonyKind::Mfa)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    let (Some(user_id), Some(authentication)) = (ceremony.user_id, ceremony.state) else {
+        return Err(AppError::InvalidCredentials);
+    };
+
+    if lockout::is_locked(&state, user_id).await? {
+        metrics::login("locked");
+        return Err(AppError::InvalidCredentials);
+    }
+
+    let result = match state.webauthn.finish_passkey_authentication(&req.credential, &authentication) {
+        Ok(r) => r,
+        Err(e) => {
+            lockout::record_failure(&state, Some(user_id)).await;
+            warn!(error = ?e, "passkey second factor failed");
+            metrics::login("mfa_failed");
+            return Err(AppError::InvalidCredentials);
+        }
+    };
+
+    record_use(&state, user_id, &result).await?;
+
+    let user = load_session_user(&state, user_id)
+        .await?
+        .filter(|u| u.is_active)
+        .ok_or(AppError::InvalidCredentials)?;
+
+    lockout::record_login(&state, user_id).await;
+    metrics::login("success");
+
+    issue_session(&state, &headers, ip, jar, user, ceremony.remember_me, &with_factor(&ceremony.amr, amr::WEBAUTHN)).await
+}
+
+/*
+|---------------------------------
+| ตรวจรหัส TOTP ของ user (ใช้ทั้งตอน login และ POST /auth/reauth)
+| - ไม่ได้ตั้ง TOTP / รหัสผิด / รหัสเดิมซ้ำ (step เดิมหรือเก่ากว่า) ---> false
+|---------------------------------
+*/
+pub async fn verify_totp(state: &AppState, user_id: Uuid, code: &str) -> AppResult<bool> {
+    let row = sqlx::query!(
+        "SELECT mfa_totp_secret, mfa_totp_last_step FROM users WHERE id = $1",
+        user_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT users"))
+    .await?;
+
+    let Some((secret, last_step)) = row.and_then(|r| Some((r.mfa_totp_secret?, r.mfa_totp_last_step))) else {
+        return Ok(false);
+    };
+
+    let secret = state.encryption.decrypt(&secret, &column_context("users", "mfa_totp_secret", user_id))?;
+
+    let Some(step) = totp::verify(&secret, code, last_step)? else {
+        return Ok(false);
+    };
+
+    let updated = sqlx::query!(
+        "UPDATE users SET mfa_totp_last_step = $2
+         WHERE id = $1 AND (mfa_totp_last_step IS NULL OR mfa_totp_last_step < $2)",
+        user_id,
+        step
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .rows_affected();
+
+    Ok(updated == 1)
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/totp
+| - mfa_token ใช้ได้ครั้งเดียวเหมือน webauthn (พลาด = login ใหม่)
+| - time step ที่ใช้แล้วใช้ซ้ำไม่ได้ (อัปเดตแบบมีเงื่อนไข กัน request ซ้อน)
+|---------------------------------
+*/
+pub async fn totp(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<MfaCodeRequest>,
+) -> AppResult<Response> {
+    // state อาจเป็น PasskeyAuthentication จาก webauthn_begin (เริ่ม passkey แล้วเปลี่ยนใจ) ---> ไม่ใช้
+    let ceremony = state
+        .ceremonies
+        .take::<serde_json::Value>(&state.db, req.mfa_token, CeremonyKind::Mfa)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    let user_id = ceremony.user_id.ok_or(AppError::InvalidCredentials)?;
+
+    if lockout::is_locked(&state, user_id).await? {
+        metrics::login("locked");
+        return Err(AppError::InvalidCredentials);
+    }
+
+    if !verify_totp(&state, user_id, &req.code).await? {
+        lockout::record_failure(&state, Some(user_id)).await;
+        warn!(%user_id, "totp second factor failed");
+        metrics::login("mfa_failed");
+        return Err(AppError::InvalidCredentials);
+    }
+
+    let user = load_session_user(&state, user_id)
+        .await?
+        .filter(|u| u.is_active)
+        .ok_or(AppError::InvalidCredentials)?;
+
+    lockout::record_login(&state, user_id).await;
+    metrics::login("success");
+
+    issue_session(&state, &headers, ip, jar, user, ceremony.remember_me, &with_factor(&ceremony.amr, amr::TOTP)).await
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/recovery
+| - ใช้แทน factor ที่สองเมื่อเครื่องหาย รหัสแต่ละตัวใช้ได้ครั้งเดียว
+| - บันทึก audit ทุกครั้งที่ใช้ (ควรแจ้ง user / สร้างชุดใหม่)
+|---------------------------------
+*/
+pub async fn recovery(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<MfaCodeRequest>,
+) -> AppResult<Response> {
+    // state อาจเป็น PasskeyAuthentication จาก webauthn_begin (เริ่ม passkey แล้วเปลี่ยนใจ) ---> ไม่ใช้
+    let ceremony = state
+        .ceremonies
+        .take::<serde_json::Value>(&state.db, req.mfa_token, CeremonyKind::Mfa)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    let user_id = ceremony.user_id.ok_or(AppError::InvalidCredentials)?;
+
+    if lockout::is_locked(&state, user_id).await? {
+        metrics::login("locked");
+        return Err(AppError::InvalidCredentials);
+    }
+
+    if !recovery_codes::consume(&state.db, user_id, &req.code, &state.refresh_secret).await? {
+        lockout::record_failure(&state, Some(user_id)).await;
+        warn!(%user_id, "recovery code rejected");
+        metrics::login("mfa_failed");
+        return Err(AppError::InvalidCredentials);
+    }
+
+    let remaining = recovery_codes::remaining(&state.db, user_id).await?;
+    audit::record(
+        &state.db,
+        Some(user_id),
+        "mfa.recovery_code_used",
+        Some(user_id),
+        serde_json::json!({ "remaining": remaining }),
+    )
+    .await?;
+
+    let user = load_session_user(&state, user_id)
+        .await?
+        .filter(|u| u.is_active)
+        .ok_or(AppError::InvalidCredentials)?;
+
+    lockout::record_login(&state, user_id).await;
+    metrics::login("success");
+
+    issue_session(&state, &headers, ip, jar, user, ceremony.remember_me, &with_factor(&ceremony.amr, amr::RECOVERY_CODE)).await
+}
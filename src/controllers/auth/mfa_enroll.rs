@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State};
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::app::{audit, ceremony::CeremonyKind, encryption::column_context, error::AppError, recovery_codes, result::AppResult, state::AppState, telemetry::db_span, totp, validation::ValidatedJson};
+use crate::controllers::auth::me::AuthUser;
+use crate::controllers::auth::mfa::available_methods;
+
+#[derive(Serialize)]
+pub struct MfaStatusResponse {
+    pub enabled: bool,
+    pub methods: Vec<&'static str>,
+    pub recovery_codes_remaining: i64,
+}
+
+#[derive(Serialize)]
+pub struct TotpEnrollResponse {
+    pub ceremony_id: Uuid,
+    // base32 สำหรับกรอกเองในแอป
+    pub secret: String,
+    // ทำเป็น QR code ให้แอปสแกน
+    pub otpauth_url: String,
+    pub expires_in: i64,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct TotpConfirmRequest {
+    pub ceremony_id: Uuid,
+    #[validate(length(min = 6, max = 16))]
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodesResponse {
+    // แสดงครั้งเดียว server เก็บแค่ hash
+    pub recovery_codes: Vec<String>,
+}
+
+fn invalid_code() -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add("code", ValidationError::new("invalid").with_message("code is incorrect or expired".into()));
+    errors.into()
+}
+
+async fn mfa_enabled(state: &AppState, user_id: Uuid) -> AppResult<(bool, bool)> {
+    let row = sqlx::query!(
+        r#"SELECT mfa_enabled, mfa_totp_secret IS NOT NULL as "has_totp!" FROM users WHERE id = $1"#,
+        user_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT users"))
+    .await?
+    .ok_or(AppError::Unauthorized)?;
+
+    Ok((row.mfa_enabled, row.has_totp))
+}
+
+/*
+|---------------------------------
+| GET /auth/mfa
+| - สถานะ MFA ของ user + จำนวน recovery code ที่ยังไม่ได้ใช้
+|---------------------------------
+*/
+pub async fn status(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> AppResult<Json<MfaStatusResponse>> {
+    let (enabled, _) = mfa_enabled(&state, user.id).await?;
+
+    Ok(Json(MfaStatusResponse {
+        enabled,
+        methods: available_methods(&state, user.id).await?,
+        recovery_codes_remaining: recovery_codes::remaining(&state.db, user.id).await?,
+    }))
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/totp/enroll
+| - สร้าง secret ใหม่ เก็บไว้ใน ceremony จนกว่าจะยืนยันด้วยรหัสแรก
+| - ยังไม่แตะ users จนกว่าจะ confirm (สแกนไม่สำเร็จก็ไม่ล็อกตัวเอง)
+|---------------------------------
+*/
+pub async fn totp_enroll(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> AppResult<Json<TotpEnrollResponse>> {
+    let (_, has_totp) = mfa_enabled(&state, user.id).await?;
+    if has_totp {
+        return Err(AppError::BadRequest("TOTP is already enrolled".into()));
+    }
+
+    let secret = totp::generate_secret()?;
+    let encoded = totp::to_base32(&secret);
+
+    // secret ที่รอยืนยันก็เข้ารหัสก่อนลง auth_ceremonies
+    let pending = state
+        .encryption
+        .encrypt_string(&encoded, &column_context("auth_ceremonies", "totp_enroll", user.id))?;
+
+    let ceremony_id = state
+        .ceremonies
+        .create(&state.db, CeremonyKind::TotpEnroll, Some(user.id), Some(&pending), false, &[])
+        .await?;
+
+    Ok(Json(TotpEnrollResponse {
+        ceremony_id,
+        otpauth_url: totp::otpauth_url(&secret, &user.username)?,
+        secret: encoded,
+        expires_in: state.ceremonies.ttl().num_seconds(),
+    }))
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/totp/confirm
+| - รหัสผิด ---> 422 ลองใหม่ได้จน ceremony หมดอายุ
+| - ผ่าน ---> เปิด MFA + สร้าง recovery code ชุดแรก (คืนให้ครั้งเดียว)
+|---------------------------------
+*/
+pub async fn totp_confirm(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    ValidatedJson(req): ValidatedJson<TotpConfirmRequest>,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    let ceremony = state
+        .ceremonies
+        .get::<String>(&state.db, req.ceremony_id, CeremonyKind::TotpEnroll)
+        .await?
+        .filter(|c| c.user_id == Some(user.id))
+        .ok_or(AppError::NotFound)?;
+
+    let pending = state.encryption.decrypt_string(
+        ceremony.state.as_deref().ok_or(AppError::NotFound)?,
+        &column_context("auth_ceremonies", "totp_enroll", user.id),
+    )?;
+
+    let secret = totp::from_base32(&pending)?;
+    let step = totp::verify(&secret, &req.code, None)?.ok_or_else(invalid_code)?;
+
+    let encrypted = state
+        .encryption
+        .encrypt(&secret, &column_context("users", "mfa_totp_secret", user.id))?;
+
+    // ยืนยันซ้อนกันสอง request ---> ผ่านแค่ request แรก
+    state
+        .ceremonies
+        .take::<String>(&state.db, req.ceremony_id, CeremonyKind::TotpEnroll)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let mut tx = state.db.begin().await?;
+
+    let updated = sqlx::query!(
+        "UPDATE users
+         SET mfa_totp_secret = $2, mfa_totp_last_step = $3, mfa_enabled = TRUE, updated_at = now()
+         WHERE id = $1 AND mfa_totp_secret IS NULL",
+        user.id,
+        encrypted,
+        step
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(AppError::BadRequest("TOTP is already enrolled".into()));
+    }
+
+    let recovery_codes = recovery_codes::replace(&mut tx, user.id, &state.refresh_secret).await?;
+
+    tx.commit().await?;
+
+    state.user_cache.invalidate(&state.db, user.id).await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/*
+|---------------------------------
+| POST /auth/mfa/recovery-codes
+| - สร้างชุดใหม่ รหัสเดิมทั้งหมด (รวมที่ยังไม่ใช้) ใช้ไม่ได้อีก
+| - ต้องเปิด MFA อยู่แล้ว
+| - บันทึก audit ใน transaction เดียวกัน (ออกชุดใหม่ได้ ---> มี log เสมอ)
+|---------------------------------
+*/
+pub async fn regenerate_recovery_codes(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> AppResult<Json<RecoveryCodesResponse>> {
+    let (enabled, _) = mfa_enabled(&state, user.id).await?;
+    if !enabled {
+        return Err(AppError::BadRequest("MFA is not enabled".into()));
+    }
+
+    let mut tx = state.db.begin().await?;
+    let recovery_codes = recovery_codes::replace(&mut tx, user.id, &state.refresh_secret).await?;
+
+    audit::record(
+        &mut *tx,
+        Some(user.id),
+        "mfa.recovery_codes_regenerated",
+        Some(user.id),
+        serde_json::json!({ "count": recovery_codes.len() }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
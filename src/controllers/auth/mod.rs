@@ -1,5 +1,16 @@
+pub mod amr;
+pub mod email_otp;
+pub mod issue;
+pub mod lockout;
 pub mod login;
+pub mod magic_link;
+pub mod mfa;
+pub mod mfa_enroll;
 pub mod me;
+pub mod password;
+pub mod reauth;
 pub mod refresh_token;
 pub mod utils;
-pub mod logout;
\ No newline at end of file
+pub mod logout;
+pub mod verify;
+pub mod webauthn;
\ No newline at end of file
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::{IntoResponse, Response}};
+use axum_extra::extract::cookie::CookieJar;
+use serde::Deserialize;
+use tracing::Instrument;
+use uuid::Uuid;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::app::{error::AppError, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson};
+use crate::controllers::auth::lockout;
+use crate::controllers::auth::me::PasswordChangeUser;
+
+// ไม่ derive Debug กัน password หลุดลง log
+// ความยาวจริงของรหัสใหม่ตรวจด้วย PasswordPolicy (ตรงนี้แค่กัน body ยักษ์)
+#[derive(Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, max = 1024))]
+    pub current_password: String,
+    #[validate(length(min = 1, max = 1024))]
+    pub new_password: String,
+}
+
+// ข้อมูลของ user ที่ต้องใช้ตอนตั้งรหัสผ่านใหม่
+pub struct PasswordOwner {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub password_hash: String,
+}
+
+pub async fn load_owner(state: &AppState, id: Uuid) -> AppResult<Option<PasswordOwner>> {
+    let row = sqlx::query_as!(
+        PasswordOwner,
+        r#"SELECT id, username::text as "username!", email::text as "email!", password_hash FROM users WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT users"))
+    .await?;
+
+    Ok(row)
+}
+
+/*
+|---------------------------------
+| ตั้งรหัสผ่านใหม่ (ใช้ร่วมกันระหว่างเปลี่ยนรหัสเอง / admin reset)
+| - นโยบายรหัสผ่าน + ห้ามซ้ำกับรหัส N อันล่าสุด (N = PASSWORD_HISTORY_DEPTH)
+|   = รหัสปัจจุบัน (users) + N - 1 อันล่าสุดใน password_history
+| - เก็บ hash เดิมลง password_history แล้วตัดให้เหลือ N - 1 อัน
+| - password_changed_at = now() ---> access token เดิมใช้ไม่ได้
+|   + revoke refresh token ทุกอุปกรณ์ ---> ต้อง login ใหม่
+| - must_change: ให้ user เปลี่ยนเองอีกรอบตอน login ครั้งถัดไปไหม (เช่น admin ตั้งให้)
+|---------------------------------
+*/
+pub async fn set_password(
+    state: &AppState,
+    owner: &PasswordOwner,
+    new_password: &str,
+    must_change: bool,
+) -> AppResult<()> {
+    state
+        .password_policy
+        .check("new_password", new_password, &owner.username, Some(&owner.email))?;
+
+    let depth = state.password_policy.history_depth;
+    // รหัสก่อนหน้าที่เก็บใน password_history (ไม่นับรหัสปัจจุบัน)
+    let kept = (depth - 1).max(0);
+
+    if depth > 0 {
+        let previous = sqlx::query_scalar!(
+            "SELECT password_hash FROM password_history
+             WHERE user_id = $1
+             ORDER BY created_at DESC
+             LIMIT $2",
+            owner.id,
+            kept
+        )
+        .fetch_all(&state.db)
+        .instrument(db_span("SELECT password_history"))
+        .await?;
+
+        for hash in std::iter::once(&owner.password_hash).chain(previous.iter()) {
+            if state.password_hasher.verify(new_password, hash)?.ok {
+                let mut errors = ValidationErrors::new();
+                errors.add(
+                    "new_password",
+                    ValidationError::new("reused")
+                        .with_message(format!("must not match any of your last {depth} passwords").into()),
+                );
+                return Err(errors.into());
+            }
+        }
+    }
+
+    let new_hash = state.password_hasher.hash(new_password)?;
+
+    let mut tx = state.db.begin().await?;
+
+    if kept > 0 {
+        sqlx::query!(
+            "INSERT INTO password_history (user_id, password_hash) VALUES ($1, $2)",
+            owner.id,
+            owner.password_hash
+        )
+        .execute(&mut *tx)
+        .instrument(db_span("INSERT password_history"))
+        .await?;
+    }
+
+    // เก็บไว้แค่ N - 1 อันล่าสุด (depth <= 1 ---> ลบทั้งหมด)
+    sqlx::query!(
+        "DELETE FROM password_history
+         WHERE user_id = $1
+           AND id NOT IN (
+             SELECT id FROM password_history
+             WHERE user_id = $1
+             ORDER BY created_at DESC
+             LIMIT $2
+           )",
+        owner.id,
+        kept
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("DELETE password_history"))
+    .await?;
+
+    sqlx::query!(
+        "UPDATE users
+         SET password_hash = $1,
+             password_changed_at = now(),
+             must_change_password = $3,
+             updated_at = now()
+         WHERE id = $2",
+        new_hash,
+        owner.id,
+        must_change
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("UPDATE users"))
+    .await?;
+
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = now()
+         WHERE user_id = $1 AND revoked_at IS NULL",
+        owner.id
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("UPDATE refresh_tokens"))
+    .await?;
+
+    tx.commit().await?;
+
+    state.user_cache.invalidate(&state.db, owner.id).await?;
+
+    Ok(())
+}
+
+/*
+|---------------------------------
+| POST /auth/password (ต้องมี access token, รับ restricted token ด้วย)
+| - ตรวจรหัสเดิม แล้วตั้งรหัสใหม่ผ่าน set_password (ผิด ---> 422 ราย field)
+| - รหัสเดิมผิดนับรวมกับ login (ครบ 5 ครั้งถูกล็อก 15 นาที) กันเดารหัสด้วย access token
+| - สำเร็จ ---> ล้าง refresh / csrf cookie ของ browser นี้ด้วย
+|---------------------------------
+*/
+pub async fn change_password(
+    State(state): State<Arc<AppState>>,
+    PasswordChangeUser(user): PasswordChangeUser,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<ChangePasswordRequest>,
+) -> AppResult<Response> {
+    let owner = load_owner(&state, user.id).await?.ok_or(AppError::Unauthorized)?;
+
+    let incorrect = || {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "current_password",
+            ValidationError::new("incorrect").with_message("is incorrect".into()),
+        );
+        AppError::from(errors)
+    };
+
+    // ถูกล็อก ---> ไม่ตรวจรหัสจริง (เสีย Argon2 เท่ากัน)
+    if lockout::is_locked(&state, owner.id).await? {
+        state.password_hasher.verify_dummy(&req.current_password)?;
+        return Err(incorrect());
+    }
+
+    if !state.password_hasher.verify(&req.current_password, &owner.password_hash)?.ok {
+        lockout::record_failure(&state, Some(owner.id)).await;
+        return Err(incorrect());
+    }
+
+    lockout::reset(&state, owner.id).await?;
+
+    set_password(&state, &owner, &req.new_password, false).await?;
+
+    let jar = jar
+        .add(state.cookie_policy.removal_cookie())
+        .add(state.cookie_policy.csrf_removal_cookie());
+
+    Ok((jar, StatusCode::NO_CONTENT).into_response())
+}
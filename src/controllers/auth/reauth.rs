@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::State};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, warn};
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::app::{error::AppError, metrics, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson};
+use crate::controllers::auth::{amr, lockout};
+use crate::controllers::auth::issue::{AuthContext, load_session_user, sign_access_token};
+use crate::controllers::auth::me::AuthUser;
+use crate::controllers::auth::mfa::verify_totp;
+use crate::controllers::auth::utils::hash_refresh_token;
+
+// ไม่ derive Debug กัน password หลุดลง log
+#[derive(Deserialize, Validate)]
+pub struct ReauthRequest {
+    #[validate(length(min = 1, max = 1024))]
+    pub password: String,
+    // รหัส TOTP ---> amr มี "mfa" ผ่าน route ที่ต้องการสอง factor (บังคับเมื่อเปิด MFA)
+    #[validate(length(min = 1, max = 16))]
+    pub code: Option<String>,
+}
+
+// ไม่ derive Debug กัน token หลุดลง log
+#[derive(Serialize)]
+pub struct ReauthResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub auth_time: DateTime<Utc>,
+    pub amr: Vec<String>,
+}
+
+fn invalid(field: &'static str, message: &'static str) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, ValidationError::new("invalid").with_message(message.into()));
+    errors.into()
+}
+
+/*
+|---------------------------------
+| ยืนยันตัวตนซ้ำสำเร็จ ---> access token ใหม่ (session / refresh cookie เดิม)
+| - auth_time = ตอนนี้, amr = วิธีที่ใช้ในครั้งนี้เท่านั้น (ไม่สืบจาก login เดิม)
+| - มี refresh cookie ของ session นี้ ---> บันทึกลง session ด้วย (refresh แล้วไม่หาย)
+|---------------------------------
+*/
+pub async fn complete_reauth(
+    state: &AppState,
+    user: &AuthUser,
+    jar: &CookieJar,
+    methods: &[&str],
+) -> AppResult<Json<ReauthResponse>> {
+    let auth = AuthContext { auth_time: Utc::now(), amr: amr::normalize(methods.iter().copied()) };
+
+    if let Some(refresh) = jar.get(state.cookie_policy.name()) {
+        sqlx::query!(
+            "UPDATE refresh_tokens SET auth_time = $3, amr = $4
+             WHERE token_hash = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > now()",
+            hash_refresh_token(refresh.value(), &state.refresh_secret)?,
+            user.id,
+            auth.auth_time,
+            &auth.amr
+        )
+        .execute(&state.db)
+        .instrument(db_span("UPDATE refresh_tokens"))
+        .await?;
+    }
+
+    let session_user = load_session_user(state, user.id)
+        .await?
+        .filter(|u| u.is_active)
+        .ok_or(AppError::Unauthorized)?;
+
+    let (access_token, expires_in) = sign_access_token(state, &session_user, false, &auth)?;
+
+    metrics::reauth("success");
+
+    Ok(Json(ReauthResponse {
+        access_token,
+        token_type: "Bearer".into(),
+        expires_in,
+        auth_time: auth.auth_time,
+        amr: auth.amr,
+    }))
+}
+
+/*
+|---------------------------------
+| POST /auth/reauth (ต้องมี access token)
+| - ยืนยันรหัสผ่านอีกครั้งก่อนเข้า route สำคัญ (require_recent_auth) โดยไม่ต้อง login ใหม่
+| - ส่ง code มาด้วย ---> ตรวจ TOTP เป็น factor ที่สอง
+| - เปิด MFA แต่ไม่ส่ง code ---> 422 (รหัสผ่านอย่างเดียวไม่ผ่าน require_recent_auth อยู่แล้ว)
+| - รหัสผ่าน / รหัส TOTP ผิดนับรวมกับ login (ครบ 5 ครั้งถูกล็อก 15 นาที)
+| - ผิด ---> 422 (ไม่ใช่ 401 ให้ client ไม่เข้าใจว่า token หมดอายุ)
+|---------------------------------
+*/
+pub async fn reauth(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<ReauthRequest>,
+) -> AppResult<Json<ReauthResponse>> {
+    let row = sqlx::query!(
+        "SELECT password_hash, locked_until, mfa_enabled FROM users WHERE id = $1 AND is_active",
+        user.id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT users"))
+    .await?
+    .ok_or(AppError::Unauthorized)?;
+
+    if row.mfa_enabled && req.code.is_none() {
+        metrics::reauth("second_factor_required");
+        return Err(invalid("code", "a second factor is required"));
+    }
+
+    if row.locked_until.is_some_and(|t| t > Utc::now()) {
+        state.password_hasher.verify_dummy(&req.password)?;
+        metrics::reauth("locked");
+        return Err(invalid("password", "password is incorrect"));
+    }
+
+    if !state.password_hasher.verify(&req.password, &row.password_hash)?.ok {
+        lockout::record_failure(&state, Some(user.id)).await;
+        warn!(user_id = %user.id, "re-authentication failed");
+        metrics::reauth("invalid_password");
+        return Err(invalid("password", "password is incorrect"));
+    }
+
+    let mut methods = vec![amr::PASSWORD];
+
+    if let Some(code) = &req.code {
+        if !verify_totp(&state, user.id, code).await? {
+            lockout::record_failure(&state, Some(user.id)).await;
+            warn!(user_id = %user.id, "re-authentication second factor failed");
+            metrics::reauth("invalid_code");
+            return Err(invalid("code", "code is incorrect or expired"));
+        }
+        methods.push(amr::TOTP);
+    }
+
+    // ผ่านครบทุก factor แล้วค่อยล้างตัวนับ
+    lockout::reset(&state, user.id).await?;
+
+    complete_reauth(&state, &user, &jar, &methods).await
+}
@@ -1,130 +1,226 @@
 use axum::{Json, extract::State, http::StatusCode, response::{IntoResponse, Response}};
 use axum_extra::extract::cookie::CookieJar;
-use jsonwebtoken::{EncodingKey, Header as JwtHeader};
-use chrono::{Utc, Duration};
-use uuid::Uuid;
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
-use cookie::time::Duration as CookieDuration;
-use crate::{app::{error::AppError, result::AppResult, state::AppState}, controllers::auth::{login::{Claims, LoginResponse}, utils::{generate_refresh_token, hash_refresh_token}}, utils::env::env_i64};
+use tracing::{Instrument, warn};
+use uuid::Uuid;
+use crate::{app::{audit, error::AppError, metrics, result::AppResult, state::AppState, telemetry::db_span}, controllers::auth::{issue::{AuthContext, SessionUser, sign_access_token}, login::LoginResponse, utils::{generate_csrf_token, generate_refresh_token, hash_refresh_token}}};
 
 pub async fn refresh(
     State(state): State<Arc<AppState>>,
     jar: CookieJar,
 ) -> AppResult<Response> {
-    let refresh_plain = jar.get("refresh_token")
-        .ok_or(AppError::Unauthorized)?
+    let refresh_plain = jar.get(state.cookie_policy.name())
+        .ok_or(AppError::InvalidRefreshToken)?
         .value()
         .to_string();
 
     let hash = hash_refresh_token(&refresh_plain, &state.refresh_secret)?;
 
+    let grace = state.session_policy.reuse_grace.num_seconds() as f64;
+
     // พยายามหา refresh token ที่ยังใช้ได้
+    // - รวม token ที่เพิ่งถูก rotate ภายใน grace (refresh ซ้อนกันหลาย tab) ถ้า session ยังไม่ถูกปิด
+    //   (logout / เปลี่ยนรหัสผ่าน / reuse ---> ไม่มี token ที่ยังใช้ได้เหลือใน session แล้ว)
     let rec_opt = sqlx::query!(
         r#"
-            SELECT rt.id, rt.user_id, u.username, u.role, u.token_version
+            SELECT rt.id, rt.user_id, rt.session_started_at, rt.remember_me, rt.auth_time, rt.amr,
+                   u.username, u.role, u.token_version, u.is_active, u.must_change_password,
+                   COALESCE(u.password_changed_at, u.created_at) as "password_set_at!"
             FROM refresh_tokens rt
             JOIN users u ON u.id = rt.user_id
             WHERE rt.token_hash = $1
-                AND rt.revoked_at IS NULL
                 AND rt.expires_at > now()
+                AND (
+                    rt.revoked_at IS NULL
+                    OR (
+                        rt.rotated_at > now() - make_interval(secs => $2)
+                        AND EXISTS(
+                            SELECT 1 FROM refresh_tokens s
+                            WHERE s.user_id = rt.user_id
+                                AND s.session_started_at = rt.session_started_at
+                                AND s.revoked_at IS NULL
+                                AND s.expires_at > now()
+                        )
+                    )
+                )
         "#,
-        hash
+        hash,
+        grace
     )
     .fetch_optional(&state.db)
+    .instrument(db_span("SELECT refresh_tokens"))
     .await?;
 
     let rec = match rec_opt {
         Some(r) => r,
         None => {
             // เช็คว่าเป็น "reuse" ไหม (ถูก revoke ไปแล้ว)
-            // ใส่ type ให้ชัดเป็น bool เพื่อตัด Option ออก
-            let reused: bool = sqlx::query_scalar!(
-                r#"
-                    SELECT EXISTS(
-                    SELECT 1 FROM refresh_tokens
-                    WHERE token_hash = $1 AND revoked_at IS NOT NULL
-                    ) as "exists!: bool"
-                "#,
+            let reused = sqlx::query!(
+                "SELECT user_id, session_started_at FROM refresh_tokens
+                 WHERE token_hash = $1 AND revoked_at IS NOT NULL
+                 LIMIT 1",
                 hash
             )
-            .fetch_one(&state.db)
+            .fetch_optional(&state.db)
+            .instrument(db_span("SELECT refresh_tokens"))
             .await?;
 
-            if reused {
-                // TODO: handle reuse (เช่น revoke ทั้ง user / log เหตุการณ์)
+            match reused {
+                Some(session) => {
+                    revoke_session(&state, session.user_id, session.session_started_at).await?;
+                    metrics::refresh("reuse_detected");
+                }
+                None => metrics::refresh("invalid"),
             }
 
-            return Err(AppError::Unauthorized);
+            return Err(AppError::InvalidRefreshToken);
         }
     };
 
+    // บัญชีถูกปิด ---> ไม่ต่ออายุ
+    if !rec.is_active {
+        metrics::refresh("inactive");
+        return Err(AppError::AccountDisabled);
+    }
+
+    // session เกินอายุสูงสุด (นับจาก login ครั้งแรก) ---> ต้อง login ใหม่
+    if Utc::now() >= state.session_policy.session_deadline(rec.session_started_at) {
+        metrics::refresh("session_expired");
+        return Err(AppError::SessionExpired);
+    }
+
+    // ต้องเปลี่ยนรหัสผ่านก่อน ---> ไม่ต่ออายุ ให้ login ใหม่เพื่อรับ restricted token
+    if rec.must_change_password || state.password_policy.is_expired(&rec.role, rec.password_set_at) {
+        metrics::refresh("password_change_required");
+        return Err(AppError::PasswordChangeRequired);
+    }
+
+    // เพิกถอน refresh เดิม + ออกตัวใหม่ใน transaction เดียว (INSERT พลาด ---> token เดิมยังใช้ได้)
+    let mut tx = state.db.begin().await?;
+
     // เพิกถอน refresh เดิมทันที (rotate)
-    sqlx::query!("UPDATE refresh_tokens SET revoked_at = now() WHERE id = $1", rec.id)
-        .execute(&state.db)
-        .await?;
+    // - มีเงื่อนไขเดียวกับตอนค้นหา: ถูก revoke ไประหว่างนี้ (logout / reuse) ---> ไม่ผ่าน
+    let rotated = sqlx::query!(
+        "UPDATE refresh_tokens
+         SET revoked_at = COALESCE(revoked_at, now()), rotated_at = COALESCE(rotated_at, now())
+         WHERE id = $1 AND (revoked_at IS NULL OR rotated_at > now() - make_interval(secs => $2))",
+        rec.id,
+        grace
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("UPDATE refresh_tokens"))
+    .await?
+    .rows_affected();
+
+    if rotated == 0 {
+        metrics::refresh("invalid");
+        return Err(AppError::InvalidRefreshToken);
+    }
 
     // ออก access token ใหม่
+    // auth_time / amr สืบจาก login (หรือ re-auth) ล่าสุดของ session ไม่ใช่เวลาที่ refresh
+    // (session ก่อนมีคอลัมน์ auth_time ---> ถือเวลาเริ่ม session)
     let now = Utc::now();
+    let auth = AuthContext {
+        auth_time: rec.auth_time.unwrap_or(rec.session_started_at),
+        amr: rec.amr,
+    };
 
-    // อ่านจาก ENV, ไม่มีก็ 15 นาที
-    let ttl_min = env_i64("ACCESS_TTL_MIN", 15)?;
-
-    // กันค่าพิสดารเล็กน้อย (เช่น ไม่ให้ติดลบ/ยาวเกิน)
-    let ttl_min = ttl_min.clamp(1, 120);
-    
-    let exp = now + chrono::Duration::minutes(ttl_min);
-
-    let claims = Claims {
-        sub: rec.user_id,
+    let user = SessionUser {
+        id: rec.user_id,
         username: rec.username,
         role: rec.role,
-        iat: now.timestamp() as usize,
-        exp: exp.timestamp() as usize,
-        iss: state.jwt_issuer.clone(),
-        aud: state.jwt_audience.clone(),
-        jti: Uuid::new_v4().to_string(),
         token_version: rec.token_version,
+        is_active: rec.is_active,
+        must_change_password: rec.must_change_password,
+        password_set_at: rec.password_set_at,
     };
 
-    let access_token = jsonwebtoken::encode(
-        &JwtHeader::default(),
-        &claims,
-        &EncodingKey::from_secret(&state.jwt_secret),
-    )?;
+    let (access_token, expires_in) = sign_access_token(&state, &user, false, &auth)?;
 
     // ออก refresh ใหม่
     let new_plain = generate_refresh_token()?;
     let new_hash  = hash_refresh_token(&new_plain, &state.refresh_secret)?;
 
-    // หมดอายุ 30 วัน
-    let new_exp = Utc::now() + Duration::days(30);
+    // ต่ออายุแบบ sliding แต่ไม่เกินอายุสูงสุดของ session เดิม
+    let new_exp = state
+        .session_policy
+        .expires_at(rec.session_started_at, rec.remember_me, now);
 
     sqlx::query!(
-        r#"INSERT INTO refresh_tokens (user_id, token_hash, expires_at)
-           VALUES ($1, $2, $3)"#,
-        rec.user_id, new_hash, new_exp
+        r#"INSERT INTO refresh_tokens (user_id, token_hash, expires_at, session_started_at, remember_me, auth_time, amr)
+           VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
+        user.id, new_hash, new_exp, rec.session_started_at, rec.remember_me, auth.auth_time, &auth.amr
     )
-    .execute(&state.db)
+    .execute(&mut *tx)
+    .instrument(db_span("INSERT refresh_tokens"))
     .await?;
 
-    // เซ็ตคุกกี้ใหม่
-    let mut refresh_cookie = cookie::Cookie::new("refresh_token", new_plain);
-    refresh_cookie.set_http_only(true);
-    refresh_cookie.set_secure(false); // dev = false
-    // refresh_cookie.set_secure(true);
-    refresh_cookie.set_same_site(cookie::SameSite::Lax);
-    refresh_cookie.set_path("/auth");
-    refresh_cookie.set_max_age(CookieDuration::days(30));
+    tx.commit().await?;
+
+    // เซ็ตคุกกี้ใหม่ (attribute เดียวกับตอน login)
+    // remember me ---> cookie อายุเท่ากับ refresh token, ไม่งั้นเป็น session cookie
+    let refresh_cookie = state
+        .cookie_policy
+        .refresh_cookie(new_plain, rec.remember_me.then_some(new_exp));
+
+    // CSRF token ใหม่ ผูกกับ refresh token ตัวใหม่ (อายุตาม refresh cookie ตัวใหม่)
+    let csrf_token = generate_csrf_token(&new_hash, &state.refresh_secret)?;
+    let csrf_cookie = state
+        .cookie_policy
+        .csrf_cookie(csrf_token.clone(), rec.remember_me.then_some(new_exp));
 
     // เตรียม response
-    let jar = jar.add(refresh_cookie);
+    let jar = jar.add(refresh_cookie).add(csrf_cookie);
 
     let body = LoginResponse {
-        access_token: access_token,
+        access_token,
         token_type: "Bearer".into(),
-        expires_in: (exp - now).num_seconds(),
+        expires_in,
+        csrf_token: Some(csrf_token),
+        password_change_required: false,
     };
 
+    metrics::refresh("rotated");
+
     // คืน (CookieJar, Response)
     Ok((jar, (StatusCode::OK, Json(body))).into_response())
 }
+
+/*
+|---------------------------------
+| refresh token ที่ถูก revoke แล้วถูกใช้ซ้ำ (เกิน grace) ---> ถือว่าหลุด เพิกถอนทั้ง session
+| - session เดียวกัน = user เดียวกัน + session_started_at เดียวกัน (ส่งต่อทุกครั้งที่ rotate)
+| - session อื่นของ user (อุปกรณ์อื่น) ยังใช้ได้
+|---------------------------------
+*/
+async fn revoke_session(state: &AppState, user_id: Uuid, session_started_at: DateTime<Utc>) -> AppResult<()> {
+    let mut tx = state.db.begin().await?;
+
+    let revoked = sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = now()
+         WHERE user_id = $1 AND session_started_at = $2 AND revoked_at IS NULL",
+        user_id,
+        session_started_at
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("UPDATE refresh_tokens"))
+    .await?
+    .rows_affected();
+
+    warn!(%user_id, revoked, "refresh token reuse detected");
+
+    audit::record(
+        &mut *tx,
+        None,
+        "session.refresh_reuse",
+        Some(user_id),
+        serde_json::json!({ "session_started_at": session_started_at, "revoked": revoked }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -9,7 +9,7 @@ pub fn generate_refresh_token() -> AppResult<String> {
     let mut bytes = [0u8; 32];
     // ใน getrandom 0.3.x ใช้ fill()
     getrandom::fill(&mut bytes).map_err(|e| AppError::InternalError(format!("RNG failed: {:?}", e)))?;
-    Ok(URL_SAFE_NO_PAD.encode(&bytes))
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
 }
 
 // แฮชฝั่งเซิร์ฟเวอร์ (HMAC-SHA256 ด้วย server secret) เก็บลง DB แทน token จริง
@@ -19,3 +19,85 @@ pub fn hash_refresh_token(token: &str, secret: &[u8]) -> AppResult<String> {
     let result = mac.finalize().into_bytes();
     Ok(URL_SAFE_NO_PAD.encode(result))
 }
+
+// เทียบ token กับ HMAC ที่เก็บไว้ (จาก hash_refresh_token) แบบ constant-time
+pub fn verify_token_hash(token: &str, expected: &str, secret: &[u8]) -> AppResult<bool> {
+    let Ok(expected) = URL_SAFE_NO_PAD.decode(expected) else {
+        return Ok(false);
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)?;
+    mac.update(token.as_bytes());
+    Ok(mac.verify_slice(&expected).is_ok())
+}
+
+/*
+|---------------------------------
+| CSRF token แบบ signed double-submit ผูกกับ session: "<nonce>.<hmac(nonce + refresh hash)>"
+| - attacker ฝัง cookie จาก subdomain ไม่ได้: เซ็นเองไม่ได้ และ token ของ session ตัวเอง
+|   ใช้กับ refresh cookie ของเหยื่อไม่ผ่าน
+| - session = hash_refresh_token ของ refresh token ตัวที่ออกพร้อมกัน (rotate ---> token ใหม่)
+|---------------------------------
+*/
+pub fn generate_csrf_token(session: &str, secret: &[u8]) -> AppResult<String> {
+    let nonce = generate_refresh_token()?;
+    let sig = URL_SAFE_NO_PAD.encode(csrf_mac(&nonce, session, secret)?.finalize().into_bytes());
+    Ok(format!("{nonce}.{sig}"))
+}
+
+pub fn verify_csrf_token(token: &str, session: &str, secret: &[u8]) -> AppResult<bool> {
+    let Some((nonce, sig)) = token.split_once('.') else {
+        return Ok(false);
+    };
+
+    let Ok(sig) = URL_SAFE_NO_PAD.decode(sig) else {
+        return Ok(false);
+    };
+
+    Ok(csrf_mac(nonce, session, secret)?.verify_slice(&sig).is_ok())
+}
+
+fn csrf_mac(nonce: &str, session: &str, secret: &[u8]) -> AppResult<Hmac<Sha256>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)?;
+    mac.update(b"csrf:");
+    mac.update(nonce.as_bytes());
+    mac.update(b":");
+    mac.update(session.as_bytes());
+    Ok(mac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn csrf_token_is_bound_to_session() {
+        let token = generate_csrf_token("session-a", SECRET).unwrap();
+
+        assert!(verify_csrf_token(&token, "session-a", SECRET).unwrap());
+        assert!(!verify_csrf_token(&token, "session-b", SECRET).unwrap());
+        assert!(!verify_csrf_token(&token, "session-a", b"another-secret-another-secret!!!").unwrap());
+    }
+
+    #[test]
+    fn csrf_token_rejects_tampering() {
+        let token = generate_csrf_token("session-a", SECRET).unwrap();
+        let (nonce, sig) = token.split_once('.').unwrap();
+        let other = generate_refresh_token().unwrap();
+
+        assert!(!verify_csrf_token(&format!("{other}.{sig}"), "session-a", SECRET).unwrap());
+        assert!(!verify_csrf_token(nonce, "session-a", SECRET).unwrap());
+        assert!(!verify_csrf_token(&format!("{nonce}.!!"), "session-a", SECRET).unwrap());
+    }
+
+    #[test]
+    fn token_hash_compares_hmac() {
+        let hash = hash_refresh_token("api-key", SECRET).unwrap();
+
+        assert!(verify_token_hash("api-key", &hash, SECRET).unwrap());
+        assert!(!verify_token_hash("api-kez", &hash, SECRET).unwrap());
+        assert!(!verify_token_hash("api-key", "not base64!", SECRET).unwrap());
+    }
+}
@@ -0,0 +1,109 @@
+use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::app::error::AppError;
+use crate::app::result::AppResult;
+use crate::app::state::AppState;
+use crate::app::telemetry::db_span;
+use crate::app::user_cache::UserSecurity;
+use crate::controllers::auth::login::Claims;
+
+/*
+|---------------------------------
+| ตรวจ access token แบบครบชุด (ใช้ร่วมกันระหว่าง AuthUser และ /oauth/introspect)
+| - signature / exp / iss / aud
+| - is_active / token_version / password_changed_at
+| - รองรับทั้ง user และ service account (client_credentials)
+|---------------------------------
+*/
+pub async fn verify_access_token(
+    state: &AppState,
+    token: &str,
+) -> AppResult<(Claims, UserSecurity)> {
+    // 1) ตั้งค่า Validation ให้ตรวจ exp/iss/aud/leeway
+    let mut v = Validation::new(Algorithm::HS256);
+    v.validate_exp = true;
+    v.leeway = 30;
+    v.set_issuer(&[&state.jwt_issuer]);
+    v.set_audience(std::slice::from_ref(&state.jwt_audience));
+
+    // 2) decode + verify
+    let data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(&state.jwt_secret), // Vec<u8> ---> &[u8]
+        &v,
+    )?;
+
+    let claims = data.claims;
+
+    // 3) (ออปชัน) เช็ค jti blocklist ถ้ามีระบบเก็บ
+    // if state.is_blocklisted(&claims.jti).await? {
+    //     return Err(AppError::Unauthorized);
+    // }
+
+    // 4) โหลดสถานะเจ้าของ token เพื่อตรวจ token_version / is_active / password_changed_at
+    // - token ของ service account (มี client_id) ---> oauth_clients
+    // - token ของคน ---> users (ผ่าน cache)
+    let user = match &claims.client_id {
+        Some(_) => load_client_security(state, claims.sub).await?,
+        None => state.user_cache.get(&state.db, claims.sub).await?,
+    }
+    .ok_or(AppError::Unauthorized)?;
+
+    if !user.is_active {
+        return Err(AppError::AccountDisabled);
+    }
+
+    // ตรวจ token_version ให้ตรงกับ DB
+    if claims.token_version != user.token_version {
+        return Err(AppError::TokenRevoked);
+    }
+
+    // ตรวจ iat กับ password_changed_at (ถ้ามี)
+    if let Some(changed_at) = user.password_changed_at
+        && (claims.iat as i64) < changed_at.timestamp()
+    {
+        return Err(AppError::TokenRevoked);
+    }
+
+    Ok((claims, user))
+}
+
+/*
+|---------------------------------
+| token นี้ใช้ได้แค่เปลี่ยนรหัสผ่านหรือไม่
+| - ออกมาเป็น restricted token ตั้งแต่ตอน login
+| - หรือหลังออก token แล้ว admin เพิ่งสั่ง / รหัสผ่านเพิ่งหมดอายุ (token ปกติก็ถูกจำกัดทันที)
+|---------------------------------
+*/
+pub fn password_change_required(state: &AppState, claims: &Claims, user: &UserSecurity) -> bool {
+    claims.password_change_required
+        || user.must_change_password
+        || state.password_policy.is_expired(&user.role, user.password_set_at)
+}
+
+// service account ไม่มีรหัสผ่าน ---> password_changed_at เป็น None เสมอ
+async fn load_client_security(state: &AppState, id: Uuid) -> AppResult<Option<UserSecurity>> {
+    let row = sqlx::query_as!(
+        UserSecurity,
+        r#"
+        SELECT
+        client_id as username,
+        'service' as "role!",
+        is_active, token_version,
+        NULL::timestamptz as "password_changed_at: chrono::DateTime<chrono::Utc>",
+        FALSE as "must_change_password!",
+        created_at as "password_set_at: chrono::DateTime<chrono::Utc>",
+        FALSE as "mfa_enabled!"
+        FROM oauth_clients
+        WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT oauth_clients"))
+    .await?;
+
+    Ok(row)
+}
@@ -0,0 +1,333 @@
+use std::sync::Arc;
+
+use axum::{Json, extract::{Path, State}, http::{HeaderMap, StatusCode}, response::Response};
+use axum_extra::extract::cookie::CookieJar;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+use webauthn_rs::prelude::{
+    AuthenticationResult, CreationChallengeResponse, DiscoverableAuthentication, DiscoverableKey,
+    Passkey, PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential, RequestChallengeResponse,
+};
+
+use crate::app::client_ip::ClientIp;
+use crate::app::{ceremony::CeremonyKind, error::AppError, metrics, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson};
+use crate::controllers::auth::amr;
+use crate::controllers::auth::issue::{issue_session, load_session_user};
+use crate::controllers::auth::me::AuthUser;
+
+fn default_name() -> String {
+    "Passkey".into()
+}
+
+#[derive(Serialize)]
+pub struct RegisterBeginResponse {
+    pub ceremony_id: Uuid,
+    // ส่งต่อให้ navigator.credentials.create()
+    pub options: CreationChallengeResponse,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct RegisterFinishRequest {
+    pub ceremony_id: Uuid,
+    #[serde(default = "default_name")]
+    #[validate(length(min = 1, max = 64))]
+    pub name: String,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasskeyResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct LoginBeginResponse {
+    pub ceremony_id: Uuid,
+    // ส่งต่อให้ navigator.credentials.get() (ไม่มี allowCredentials = ให้ authenticator เลือกเอง)
+    pub options: RequestChallengeResponse,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct LoginFinishRequest {
+    pub ceremony_id: Uuid,
+    pub credential: PublicKeyCredential,
+    #[serde(default)]
+    pub remember_me: bool,
+}
+
+// passkey ทั้งหมดของ user (ใช้ทั้งตอน exclude ตอนลงทะเบียน และเป็น factor ที่สอง)
+pub async fn load_passkeys(state: &AppState, user_id: Uuid) -> AppResult<Vec<Passkey>> {
+    let rows = sqlx::query_scalar!(
+        "SELECT passkey FROM webauthn_credentials WHERE user_id = $1 ORDER BY created_at",
+        user_id
+    )
+    .fetch_all(&state.db)
+    .instrument(db_span("SELECT webauthn_credentials"))
+    .await?;
+
+    rows.into_iter()
+        .map(|v| serde_json::from_value(v).map_err(AppError::from))
+        .collect()
+}
+
+/*
+|---------------------------------
+| บันทึกการใช้ passkey หลัง verify ผ่าน
+| - อัปเดต sign counter / backup state ตามผลล่าสุด (ใช้ตรวจ authenticator ถูก clone)
+| - last_used_at
+|---------------------------------
+*/
+pub async fn record_use(state: &AppState, user_id: Uuid, result: &AuthenticationResult) -> AppResult<()> {
+    let cred_id: &[u8] = result.cred_id().as_ref();
+
+    let stored = sqlx::query_scalar!(
+        "SELECT passkey FROM webauthn_credentials WHERE user_id = $1 AND credential_id = $2",
+        user_id,
+        cred_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT webauthn_credentials"))
+    .await?
+    .ok_or(AppError::InvalidCredentials)?;
+
+    let mut passkey: Passkey = serde_json::from_value(stored)?;
+    passkey.update_credential(result);
+
+    sqlx::query!(
+        "UPDATE webauthn_credentials SET passkey = $3, last_used_at = now()
+         WHERE user_id = $1 AND credential_id = $2",
+        user_id,
+        cred_id,
+        serde_json::to_value(&passkey)?
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE webauthn_credentials"))
+    .await?;
+
+    Ok(())
+}
+
+/*
+|---------------------------------
+| POST /auth/webauthn/register/begin (ต้องมี access token)
+| - user handle = users.id (ใช้หา user ตอน login แบบไม่กรอก username)
+| - passkey ที่มีอยู่แล้วถูกใส่ใน excludeCredentials กันลงทะเบียนซ้ำ
+|---------------------------------
+*/
+pub async fn register_begin(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> AppResult<Json<RegisterBeginResponse>> {
+    let exclude = load_passkeys(&state, user.id)
+        .await?
+        .iter()
+        .map(|p| p.cred_id().clone())
+        .collect::<Vec<_>>();
+
+    let (options, registration) = state
+        .webauthn
+        .start_passkey_registration(user.id, &user.username, &user.username, Some(exclude))
+        .map_err(|e| AppError::InternalError(format!("webauthn: {e:?}")))?;
+
+    let ceremony_id = state
+        .ceremonies
+        .create(&state.db, CeremonyKind::PasskeyRegister, Some(user.id), Some(&registration), false, &[])
+        .await?;
+
+    Ok(Json(RegisterBeginResponse { ceremony_id, options }))
+}
+
+/*
+|---------------------------------
+| POST /auth/webauthn/register/finish (ต้องมี access token)
+| - ceremony ต้องเป็นของ user คนเดียวกับที่เริ่ม
+| - ผ่าน ---> 201 + ข้อมูล passkey ที่เพิ่ม
+|---------------------------------
+*/
+pub async fn register_finish(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    ValidatedJson(req): ValidatedJson<RegisterFinishRequest>,
+) -> AppResult<(StatusCode, Json<PasskeyResponse>)> {
+    let expired = || AppError::BadRequest("passkey registration expired, start again".into());
+
+    let ceremony = state
+        .ceremonies
+        .take::<PasskeyRegistration>(&state.db, req.ceremony_id, CeremonyKind::PasskeyRegister)
+        .await?
+        .filter(|c| c.user_id == Some(user.id))
+        .ok_or_else(expired)?;
+
+    let registration = ceremony.state.ok_or_else(expired)?;
+
+    let passkey = state
+        .webauthn
+        .finish_passkey_registration(&req.credential, &registration)
+        .map_err(|e| AppError::BadRequest(format!("passkey registration failed: {e}")))?;
+
+    let cred_id: &[u8] = passkey.cred_id().as_ref();
+
+    let row = sqlx::query_as!(
+        PasskeyResponse,
+        "INSERT INTO webauthn_credentials (user_id, credential_id, passkey, name)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (credential_id) DO NOTHING
+         RETURNING id, name, created_at, last_used_at",
+        user.id,
+        cred_id,
+        serde_json::to_value(&passkey)?,
+        req.name
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("INSERT webauthn_credentials"))
+    .await?
+    .ok_or_else(|| AppError::BadRequest("passkey is already registered".into()))?;
+
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+// GET /auth/webauthn/credentials
+pub async fn list_passkeys(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> AppResult<Json<Vec<PasskeyResponse>>> {
+    let rows = sqlx::query_as!(
+        PasskeyResponse,
+        "SELECT id, name, created_at, last_used_at
+         FROM webauthn_credentials
+         WHERE user_id = $1
+         ORDER BY created_at",
+        user.id
+    )
+    .fetch_all(&state.db)
+    .instrument(db_span("SELECT webauthn_credentials"))
+    .await?;
+
+    Ok(Json(rows))
+}
+
+// DELETE /auth/webauthn/credentials/{id} (ลบได้เฉพาะของตัวเอง)
+pub async fn delete_passkey(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let deleted = sqlx::query!(
+        "DELETE FROM webauthn_credentials WHERE id = $1 AND user_id = $2",
+        id,
+        user.id
+    )
+    .execute(&state.db)
+    .instrument(db_span("DELETE webauthn_credentials"))
+    .await?
+    .rows_affected();
+
+    if deleted == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/*
+|---------------------------------
+| POST /auth/webauthn/login/begin (ไม่ต้องกรอก username)
+|---------------------------------
+*/
+pub async fn login_begin(State(state): State<Arc<AppState>>) -> AppResult<Json<LoginBeginResponse>> {
+    let (options, authentication) = state
+        .webauthn
+        .start_discoverable_authentication()
+        .map_err(|e| AppError::InternalError(format!("webauthn: {e:?}")))?;
+
+    let ceremony_id = state
+        .ceremonies
+        .create(&state.db, CeremonyKind::PasskeyLogin, None, Some(&authentication), false, &[])
+        .await?;
+
+    Ok(Json(LoginBeginResponse { ceremony_id, options }))
+}
+
+/*
+|---------------------------------
+| POST /auth/webauthn/login/finish
+| - หา user จาก user handle + credential id ที่ authenticator ส่งมา
+| - passkey ยืนยันทั้งสิ่งที่มี + user verification ---> ไม่ถาม MFA ซ้ำ
+| - ไม่ผ่านทุกกรณีตอบ InvalidCredentials เหมือน /auth/login
+| - ผ่าน ---> ออก token / refresh cookie แบบเดียวกับ /auth/login
+|---------------------------------
+*/
+pub async fn login_finish(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ClientIp(ip): ClientIp,
+    jar: CookieJar,
+    ValidatedJson(req): ValidatedJson<LoginFinishRequest>,
+) -> AppResult<Response> {
+    let authentication = state
+        .ceremonies
+        .take::<DiscoverableAuthentication>(&state.db, req.ceremony_id, CeremonyKind::PasskeyLogin)
+        .await?
+        .and_then(|c| c.state)
+        .ok_or(AppError::InvalidCredentials)?;
+
+    let (user_id, cred_id) = state
+        .webauthn
+        .identify_discoverable_authentication(&req.credential)
+        .map_err(|_| AppError::InvalidCredentials)?;
+
+    let stored = sqlx::query_scalar!(
+        "SELECT passkey FROM webauthn_credentials WHERE user_id = $1 AND credential_id = $2",
+        user_id,
+        cred_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT webauthn_credentials"))
+    .await?;
+
+    let Some(stored) = stored else {
+        metrics::login("not_found");
+        return Err(AppError::InvalidCredentials);
+    };
+
+    let passkey: Passkey = serde_json::from_value(stored)?;
+
+    let result = match state.webauthn.finish_discoverable_authentication(
+        &req.credential,
+        authentication,
+        &[DiscoverableKey::from(&passkey)],
+    ) {
+        Ok(r) => r,
+        Err(e) => {
+            warn!(error = ?e, "passkey authentication failed");
+            metrics::login("invalid_credentials");
+            return Err(AppError::InvalidCredentials);
+        }
+    };
+
+    let user = load_session_user(&state, user_id)
+        .await?
+        .ok_or(AppError::InvalidCredentials)?;
+
+    if !user.is_active {
+        metrics::login("inactive");
+        return Err(AppError::InvalidCredentials);
+    }
+
+    record_use(&state, user_id, &result).await?;
+
+    let _ = sqlx::query!("UPDATE users SET last_login_at = now() WHERE id = $1", user_id)
+        .execute(&state.db)
+        .instrument(db_span("UPDATE users"))
+        .await;
+
+    metrics::login("success");
+
+    issue_session(&state, &headers, ip, jar, user, req.remember_me, &[amr::WEBAUTHN]).await
+}
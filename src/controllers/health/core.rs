@@ -0,0 +1,133 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{Json, extract::State, http::StatusCode};
+use serde::Serialize;
+use serde_json::json;
+use tracing::Instrument;
+
+use crate::app::{lifecycle::REQUIRED_SCHEMA, result::AppResult, state::AppState, telemetry::db_span};
+use crate::utils::env::env_i64;
+
+#[derive(Debug, Serialize)]
+pub struct ReadyChecks {
+    pub shutting_down: bool,
+    pub database: bool,
+    pub schema: bool,
+    pub signing_keys: bool,
+    // /readyz เปิดสาธารณะ ---> ไม่ส่งชื่อ table/column ออกไป (ดูได้ที่ /api/status)
+    #[serde(skip)]
+    pub missing_schema: Vec<String>,
+}
+
+/*
+|---------------------------------
+| GET /healthz
+| - process ยังตอบได้ = ผ่าน (ไม่แตะ DB)
+|---------------------------------
+*/
+pub async fn healthz() -> Json<serde_json::Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/*
+|---------------------------------
+| GET /readyz
+| - ไม่อยู่ระหว่าง shutdown
+| - ping DB ได้ภายใน READINESS_DB_TIMEOUT_MS
+| - table/column ที่ต้องใช้มีครบ (REQUIRED_SCHEMA)
+| - มี JWT / refresh secret
+|---------------------------------
+*/
+pub async fn readyz(
+    State(state): State<Arc<AppState>>,
+) -> AppResult<(StatusCode, Json<ReadyChecks>)> {
+    let checks = run_checks(&state).await?;
+
+    let ready = !checks.shutting_down && checks.database && checks.schema && checks.signing_keys;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    Ok((status, Json(checks)))
+}
+
+/*
+|---------------------------------
+| GET /api/status (admin)
+| - readiness แบบละเอียด (รวม table/column ที่ขาด) + สถิติ connection pool
+|---------------------------------
+*/
+pub async fn status(
+    State(state): State<Arc<AppState>>,
+) -> AppResult<Json<serde_json::Value>> {
+    let checks = run_checks(&state).await?;
+
+    let pool = &state.db;
+
+    Ok(Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_secs": state.lifecycle.uptime().as_secs(),
+        "checks": checks,
+        "missing_schema": checks.missing_schema,
+        "db_pool": {
+            "size": pool.size(),
+            "idle": pool.num_idle(),
+            "in_use": (pool.size() as usize).saturating_sub(pool.num_idle()),
+            "max_connections": pool.options().get_max_connections(),
+            "min_connections": pool.options().get_min_connections(),
+            "closed": pool.is_closed(),
+        }
+    })))
+}
+
+async fn run_checks(state: &AppState) -> AppResult<ReadyChecks> {
+    let timeout_ms = env_i64("READINESS_DB_TIMEOUT_MS", 1000)?.clamp(50, 30_000) as u64;
+    let timeout = Duration::from_millis(timeout_ms);
+
+    let database = matches!(
+        tokio::time::timeout(timeout, sqlx::query!("SELECT 1 as one").fetch_one(&state.db).instrument(db_span("SELECT 1"))).await,
+        Ok(Ok(_))
+    );
+
+    // ping ไม่ผ่านก็ไม่ต้องเช็ค schema ต่อ
+    let missing_schema = if database {
+        let (tables, columns): (Vec<String>, Vec<String>) = REQUIRED_SCHEMA
+            .iter()
+            .map(|(t, c)| (t.to_string(), c.to_string()))
+            .unzip();
+
+        let missing = tokio::time::timeout(
+            timeout,
+            sqlx::query_scalar!(
+                r#"
+                    SELECT r.tbl || '.' || r.col as "missing!"
+                    FROM unnest($1::text[], $2::text[]) AS r(tbl, col)
+                    WHERE NOT EXISTS (
+                        SELECT 1 FROM information_schema.columns c
+                        WHERE c.table_schema = current_schema()
+                          AND c.table_name = r.tbl
+                          AND c.column_name = r.col
+                    )
+                "#,
+                &tables,
+                &columns
+            )
+            .fetch_all(&state.db)
+            .instrument(db_span("SELECT information_schema.columns")),
+        )
+        .await;
+
+        match missing {
+            Ok(Ok(m)) => m,
+            _ => vec!["<schema check failed>".into()],
+        }
+    } else {
+        vec![]
+    };
+
+    Ok(ReadyChecks {
+        shutting_down: state.lifecycle.is_shutting_down(),
+        database,
+        schema: database && missing_schema.is_empty(),
+        signing_keys: !state.jwt_secret.is_empty() && !state.refresh_secret.is_empty(),
+        missing_schema,
+    })
+}
@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::{HeaderMap, header}, response::{IntoResponse, Response}};
+
+use crate::app::{error::AppError, metrics, result::AppResult, state::AppState};
+use crate::controllers::auth::utils::verify_token_hash;
+
+/*
+|---------------------------------
+| GET /metrics (Prometheus text format)
+| - ถ้าตั้ง METRICS_TOKEN ต้องส่ง Authorization: Bearer <token>
+|---------------------------------
+*/
+pub async fn metrics(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> AppResult<Response> {
+    if let Some(expected) = &state.metrics_token {
+        let presented = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .ok_or(AppError::Unauthorized)?;
+
+        // เทียบ HMAC แบบ constant-time
+        if !verify_token_hash(presented, expected, &state.refresh_secret)? {
+            return Err(AppError::Unauthorized);
+        }
+    }
+
+    let pool = &state.db;
+    metrics::db_pool(pool.size(), pool.num_idle(), pool.options().get_max_connections());
+
+    let body = metrics::render().ok_or(AppError::NotFound)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    ).into_response())
+}
@@ -0,0 +1,3 @@
+pub mod core;
+#[cfg(feature = "metrics")]
+pub mod metrics;
@@ -1,2 +1,4 @@
 pub mod auth;
+pub mod health;
+pub mod oauth;
 pub mod users;
\ No newline at end of file
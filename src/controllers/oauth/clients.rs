@@ -0,0 +1,125 @@
+use axum::http::HeaderMap;
+use headers::{Authorization, HeaderMapExt, authorization::Basic};
+use tracing::{Instrument, debug, warn};
+use uuid::Uuid;
+
+use crate::app::{error::AppError, result::AppResult, state::AppState, telemetry::db_span};
+use crate::controllers::auth::utils::verify_token_hash;
+
+#[derive(Clone, Debug)]
+pub struct OAuthClient {
+    pub id: Uuid,
+    pub client_id: String,
+    pub scopes: Vec<String>,
+    pub token_version: i32,
+}
+
+impl OAuthClient {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+// ดึง client_id / client_secret จาก Authorization: Basic (client_secret_basic)
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    headers
+        .typed_get::<Authorization<Basic>>()
+        .map(|Authorization(b)| (b.username().to_string(), b.password().to_string()))
+}
+
+/*
+|---------------------------------
+| ตรวจ client_id + client_secret กับตาราง oauth_clients
+| - ไม่พบ / ปิดใช้งาน / secret ผิด ---> InvalidClient เหมือนกันหมด
+| - ไม่พบ ---> verify กับ dummy hash ให้เวลาตอบไม่บอกว่ามี client_id นี้หรือไม่
+| - hash ผ่าน PasswordHasher (params / pepper เดียวกับรหัสผ่าน) ---> hash เก่า rehash ให้
+|---------------------------------
+*/
+pub async fn authenticate_client(
+    state: &AppState,
+    client_id: &str,
+    client_secret: &str,
+) -> AppResult<OAuthClient> {
+    let row = sqlx::query!(
+        r#"
+            SELECT id, client_id, client_secret_hash, scopes, is_active, token_version
+            FROM oauth_clients
+            WHERE client_id = $1
+        "#,
+        client_id
+    )
+    .fetch_optional(&state.db)
+    .instrument(db_span("SELECT oauth_clients"))
+    .await?;
+
+    let Some(row) = row else {
+        state.password_hasher.verify_dummy(client_secret)?;
+        return Err(AppError::InvalidClient);
+    };
+
+    let verified = state.password_hasher.verify(client_secret, &row.client_secret_hash)?;
+
+    // ตรวจ is_active หลัง verify (ทุกกรณีเสีย Argon2 เท่ากัน)
+    if !verified.ok || !row.is_active {
+        return Err(AppError::InvalidClient);
+    }
+
+    if verified.needs_rehash {
+        match state.password_hasher.hash(client_secret) {
+            Ok(new_hash) => {
+                let _ = sqlx::query!(
+                    "UPDATE oauth_clients SET client_secret_hash = $1 WHERE id = $2 AND client_secret_hash = $3",
+                    new_hash,
+                    row.id,
+                    row.client_secret_hash
+                )
+                .execute(&state.db)
+                .instrument(db_span("UPDATE oauth_clients"))
+                .await
+                .inspect_err(|e| warn!(error = ?e, "client secret rehash failed"));
+            }
+            Err(e) => warn!(error = ?e, "client secret rehash failed"),
+        }
+    }
+
+    Ok(OAuthClient {
+        id: row.id,
+        client_id: row.client_id,
+        scopes: row.scopes,
+        token_version: row.token_version,
+    })
+}
+
+/*
+|---------------------------------
+| ตรวจว่าผู้เรียกเป็น resource server ที่อนุญาตให้ introspect
+| - Authorization: Basic (client ต้องมี scope "introspect")
+| - หรือ X-Api-Key ที่ตั้งไว้ใน INTROSPECTION_API_KEYS
+|---------------------------------
+*/
+pub async fn authorize_resource_server(state: &AppState, headers: &HeaderMap) -> AppResult<()> {
+    if let Some((client_id, client_secret)) = basic_credentials(headers) {
+        let client = authenticate_client(state, &client_id, &client_secret).await?;
+
+        if !client.has_scope("introspect") {
+            return Err(AppError::Forbidden);
+        }
+
+        debug!(client_id = %client.client_id, "introspection by client");
+        return Ok(());
+    }
+
+    if let Some(key) = headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+        // เทียบ HMAC ของ key แบบ constant-time ทุกตัว (ไม่หยุดที่ตัวแรกที่ตรง)
+        let mut matched = false;
+        for expected in &state.introspection_api_keys {
+            matched |= verify_token_hash(key, expected, &state.refresh_secret)?;
+        }
+
+        if matched {
+            return Ok(());
+        }
+    }
+
+    Err(AppError::Unauthorized)
+}
@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use axum::{Form, Json, extract::State, http::HeaderMap};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::{result::AppResult, state::AppState};
+use crate::controllers::auth::verify::{password_change_required, verify_access_token};
+use crate::controllers::oauth::clients::authorize_resource_server;
+
+// ไม่ derive Debug กัน token หลุดลง log
+#[derive(Deserialize)]
+pub struct IntrospectRequest {
+    pub token: String,
+    #[allow(dead_code)]
+    pub token_type_hint: Option<String>,
+}
+
+// RFC 7662 section 2.2 (token ไม่ active ---> ส่งแค่ {"active": false})
+#[derive(Debug, Default, Serialize)]
+pub struct IntrospectResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+/*
+|---------------------------------
+| POST /oauth/introspect
+| - ใช้ตรรกะเดียวกับ AuthUser (verify_access_token)
+| - token ไม่ผ่าน ---> 200 {"active": false} ไม่ใช่ 401
+|---------------------------------
+*/
+pub async fn introspect(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Form(req): Form<IntrospectRequest>,
+) -> AppResult<Json<IntrospectResponse>> {
+    authorize_resource_server(&state, &headers).await?;
+
+    let (claims, user) = match verify_access_token(&state, &req.token).await {
+        Ok(v) => v,
+        Err(e) if e.is_token_rejection() => {
+            return Ok(Json(IntrospectResponse::default()));
+        }
+        Err(e) => return Err(e),
+    };
+
+    // restricted token ใช้กับ resource server ไม่ได้
+    if password_change_required(&state, &claims, &user) {
+        return Ok(Json(IntrospectResponse::default()));
+    }
+
+    Ok(Json(IntrospectResponse {
+        active: true,
+        sub: Some(claims.sub),
+        username: Some(user.username),
+        role: Some(user.role),
+        scope: claims.scope,
+        client_id: claims.client_id,
+        token_type: Some("Bearer".into()),
+        exp: Some(claims.exp),
+        iat: Some(claims.iat),
+        iss: Some(claims.iss),
+        aud: Some(claims.aud),
+        jti: Some(claims.jti),
+    }))
+}
@@ -0,0 +1,18 @@
+use axum::Json;
+
+use crate::controllers::auth::me::ServicePrincipal;
+
+/*
+|---------------------------------
+| GET /oauth/me
+| - ข้อมูลของ service account เจ้าของ token (client_credentials)
+| - token ของคน ---> 403 (ใช้ /auth/me แทน)
+|---------------------------------
+*/
+pub async fn me(service: ServicePrincipal) -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "id": service.id,
+        "client_id": service.client_id,
+        "scopes": service.scopes
+    }))
+}
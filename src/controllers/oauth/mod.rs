@@ -0,0 +1,4 @@
+pub mod clients;
+pub mod introspect;
+pub mod me;
+pub mod token;
@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use axum::{Form, Json, extract::{State, rejection::FormRejection}, http::{HeaderMap, StatusCode, header}, response::{IntoResponse, Response}};
+use chrono::Utc;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::{error::AppError, state::AppState};
+use crate::controllers::auth::login::Claims;
+use crate::controllers::oauth::clients::{authenticate_client, basic_credentials};
+use crate::utils::env::env_i64;
+
+// ไม่ derive Debug กัน client_secret หลุดลง log
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    pub grant_type: String,
+    pub scope: Option<String>,
+    // client_secret_post (ถ้าไม่ได้ส่งมาทาง Authorization: Basic)
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+// RFC 6749 section 5.1 (client_credentials ไม่มี refresh_token)
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String, // "Bearer"
+    pub expires_in: i64, // วินาที
+    pub scope: String,
+}
+
+// RFC 6749 section 5.2
+#[derive(Serialize)]
+struct TokenErrorBody {
+    error: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error_description: Option<String>,
+}
+
+/*
+|---------------------------------
+| error ของ /oauth/token ในรูป RFC 6749 section 5.2 ({"error": "..."}) แทน problem+json
+| - client library มาตรฐานอ่าน field "error"
+| - invalid_client ---> 401 + WWW-Authenticate: Basic
+| - 4xx อื่นที่ไม่มีชื่อเฉพาะ (form เสีย / ขาด field) ---> invalid_request
+| - 5xx ---> problem+json ตามปกติ (log รายละเอียดที่เดิม)
+|---------------------------------
+*/
+pub struct TokenError(AppError);
+
+impl<E: Into<AppError>> From<E> for TokenError {
+    fn from(e: E) -> Self {
+        Self(e.into())
+    }
+}
+
+impl IntoResponse for TokenError {
+    fn into_response(self) -> Response {
+        let (status, error) = match &self.0 {
+            AppError::InvalidClient => (StatusCode::UNAUTHORIZED, "invalid_client"),
+            AppError::UnsupportedGrantType => (StatusCode::BAD_REQUEST, "unsupported_grant_type"),
+            AppError::InvalidScope => (StatusCode::BAD_REQUEST, "invalid_scope"),
+            e if e.catalog().0.is_client_error() => (StatusCode::BAD_REQUEST, "invalid_request"),
+            _ => return self.0.into_response(),
+        };
+
+        let error_description = match self.0 {
+            AppError::FormRejection(r) => Some(r.body_text()),
+            AppError::BadRequest(msg) => Some(msg),
+            _ => None,
+        };
+
+        let mut res = (status, Json(TokenErrorBody { error, error_description })).into_response();
+
+        if status == StatusCode::UNAUTHORIZED {
+            res.headers_mut().insert(header::WWW_AUTHENTICATE, header::HeaderValue::from_static(r#"Basic realm="oauth""#));
+        }
+
+        res
+    }
+}
+
+/*
+|---------------------------------
+| POST /oauth/token
+| - รองรับเฉพาะ grant_type=client_credentials
+| - scope ที่ขอต้องเป็น subset ของ scope ที่ client ได้รับอนุญาต
+|   (ไม่ระบุ ---> ได้ทั้งหมด)
+| - error ตาม RFC 6749 section 5.2 (ดู TokenError)
+|---------------------------------
+*/
+pub async fn token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    form: Result<Form<TokenRequest>, FormRejection>,
+) -> Result<Json<TokenResponse>, TokenError> {
+    let Form(req) = form?;
+
+    if req.grant_type != "client_credentials" {
+        return Err(AppError::UnsupportedGrantType.into());
+    }
+
+    let (client_id, client_secret) = match basic_credentials(&headers) {
+        Some(creds) => creds,
+        None => match (req.client_id, req.client_secret) {
+            (Some(id), Some(secret)) => (id, secret),
+            _ => return Err(AppError::InvalidClient.into()),
+        },
+    };
+
+    let client = authenticate_client(&state, &client_id, &client_secret).await?;
+
+    let scopes: Vec<String> = match req.scope.as_deref() {
+        Some(requested) => {
+            let requested: Vec<String> = requested.split_whitespace().map(String::from).collect();
+
+            if requested.iter().any(|s| !client.has_scope(s)) {
+                return Err(AppError::InvalidScope.into());
+            }
+
+            requested
+        }
+        None => client.scopes.clone(),
+    };
+
+    let scope = scopes.join(" ");
+
+    let now = Utc::now();
+
+    // อ่านจาก ENV, ไม่มีก็ 15 นาที (ใช้ค่าเดียวกับ token ของ user)
+    let ttl_min = env_i64("ACCESS_TTL_MIN", 15)?;
+
+    // กันค่าพิสดารเล็กน้อย (เช่น ไม่ให้ติดลบ/ยาวเกิน)
+    let ttl_min = ttl_min.clamp(1, 120);
+
+    let exp = now + chrono::Duration::minutes(ttl_min);
+
+    let claims = Claims {
+        sub: client.id,
+        username: client.client_id.clone(),
+        role: "service".into(),
+        iat: now.timestamp() as usize,
+        exp: exp.timestamp() as usize,
+        iss: state.jwt_issuer.clone(),
+        aud: state.jwt_audience.clone(),
+        jti: Uuid::new_v4().to_string(),
+        token_version: client.token_version,
+        scope: Some(scope.clone()),
+        client_id: Some(client.client_id),
+        password_change_required: false,
+        auth_time: None,
+        amr: Vec::new(),
+    };
+
+    let access_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&state.jwt_secret),
+    )?;
+
+    Ok(Json(TokenResponse {
+        access_token,
+        token_type: "Bearer".into(),
+        expires_in: (exp - now).num_seconds(),
+        scope,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body(res: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn invalid_client_is_401_with_basic_challenge() {
+        let res = TokenError::from(AppError::InvalidClient).into_response();
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert!(res.headers()[header::WWW_AUTHENTICATE].to_str().unwrap().starts_with("Basic"));
+        assert_eq!(body(res).await, serde_json::json!({ "error": "invalid_client" }));
+    }
+
+    #[tokio::test]
+    async fn other_client_errors_are_invalid_request() {
+        let res = TokenError::from(AppError::BadRequest("missing grant_type".into())).into_response();
+
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body(res).await,
+            serde_json::json!({ "error": "invalid_request", "error_description": "missing grant_type" })
+        );
+
+        let res = TokenError::from(AppError::InvalidScope).into_response();
+        assert_eq!(body(res).await["error"], "invalid_scope");
+    }
+
+    #[tokio::test]
+    async fn server_errors_stay_problem_json() {
+        let res = TokenError::from(AppError::InternalError("boom".into())).into_response();
+
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body(res).await["code"], "internal_error");
+    }
+}
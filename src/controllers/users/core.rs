@@ -1,11 +1,15 @@
 use std::sync::Arc;
 
-use axum::{Json, extract::State};
-use serde::Serialize;
+use axum::{Json, extract::{Path, State}, http::StatusCode};
+use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
+use tracing::Instrument;
 use uuid::Uuid;
+use validator::Validate;
 
-use crate::app::{result::AppResult, state::AppState};
+use crate::app::{audit, error::AppError, result::AppResult, state::AppState, telemetry::db_span, validation::ValidatedJson};
+use crate::controllers::auth::me::AuthUser;
+use crate::controllers::auth::password::{load_owner, set_password};
 
 #[derive(Debug, Serialize, FromRow)]
 pub struct UsersResponse {
@@ -21,7 +25,202 @@ pub async fn list_users(
         r#"SELECT id, username, COALESCE(role, 'user') AS role FROM users"#
     )
     .fetch_all(&state.db)
+    .instrument(db_span("SELECT users"))
     .await?;
 
     Ok(Json(rows))
-}
\ No newline at end of file
+}
+
+/*
+|---------------------------------
+| ปิดบัญชีผู้ใช้ + revoke refresh token ทั้งหมด
+|---------------------------------
+*/
+pub async fn deactivate_user(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let updated = sqlx::query!(
+        "UPDATE users SET is_active = FALSE, updated_at = now() WHERE id = $1",
+        id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = now()
+         WHERE user_id = $1 AND revoked_at IS NULL",
+        id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE refresh_tokens"))
+    .await?;
+
+    state.user_cache.invalidate(&state.db, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/*
+|---------------------------------
+| force logout ทุกอุปกรณ์: เพิ่ม token_version + revoke refresh token ทั้งหมด
+|---------------------------------
+*/
+pub async fn force_logout(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let updated = sqlx::query!(
+        "UPDATE users SET token_version = token_version + 1, updated_at = now() WHERE id = $1",
+        id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = now()
+         WHERE user_id = $1 AND revoked_at IS NULL",
+        id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE refresh_tokens"))
+    .await?;
+
+    state.user_cache.invalidate(&state.db, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ไม่ derive Debug กัน password หลุดลง log
+#[derive(Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 1024))]
+    pub new_password: String,
+}
+
+/*
+|---------------------------------
+| admin ตั้งรหัสผ่านใหม่ให้ user (ไม่ต้องรู้รหัสเดิม)
+| - ผ่านนโยบายรหัสผ่าน + password history เหมือนเปลี่ยนเอง
+| - session เดิมของ user ถูก revoke ทั้งหมด
+| - user ต้องเปลี่ยนรหัสเองอีกรอบตอน login ครั้งถัดไป (admin รู้รหัสนี้)
+|---------------------------------
+*/
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(req): ValidatedJson<ResetPasswordRequest>,
+) -> AppResult<StatusCode> {
+    let owner = load_owner(&state, id).await?.ok_or(AppError::NotFound)?;
+
+    set_password(&state, &owner, &req.new_password, true).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/*
+|---------------------------------
+| บังคับให้ user เปลี่ยนรหัสผ่าน (เช่น หลังเกิด incident)
+| - access token ที่มีอยู่ถูกจำกัดให้เปลี่ยนรหัสผ่านได้อย่างเดียวทันที
+| - login ครั้งถัดไปได้ restricted token จนกว่าจะเปลี่ยนเสร็จ
+|---------------------------------
+*/
+pub async fn require_password_change(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let updated = sqlx::query!(
+        "UPDATE users SET must_change_password = TRUE, updated_at = now() WHERE id = $1",
+        id
+    )
+    .execute(&state.db)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(AppError::NotFound);
+    }
+
+    state.user_cache.invalidate(&state.db, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/*
+|---------------------------------
+| ล้าง MFA ของ user (เครื่องหาย + recovery code หมด)
+| - ลบ TOTP secret / recovery code ทั้งหมด (passkey ยังอยู่ ใช้ login แบบ passkey ได้)
+| - force logout ทุกอุปกรณ์ (token_version + revoke refresh token) ---> session ที่อาจถูกขโมยไปใช้ต่อไม่ได้
+| - บันทึก audit ว่า admin คนไหนเป็นคนล้าง ใน transaction เดียวกัน
+|---------------------------------
+*/
+pub async fn reset_mfa(
+    State(state): State<Arc<AppState>>,
+    admin: AuthUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let mut tx = state.db.begin().await?;
+
+    let row = sqlx::query!(
+        r#"UPDATE users u
+           SET mfa_enabled = FALSE, mfa_totp_secret = NULL, mfa_totp_last_step = NULL,
+               token_version = u.token_version + 1, updated_at = now()
+           FROM users old
+           WHERE u.id = $1 AND old.id = u.id
+           RETURNING old.mfa_enabled, old.mfa_totp_secret IS NOT NULL as "had_totp!""#,
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .instrument(db_span("UPDATE users"))
+    .await?
+    .ok_or(AppError::NotFound)?;
+
+    let recovery_codes = sqlx::query!("DELETE FROM mfa_recovery_codes WHERE user_id = $1", id)
+        .execute(&mut *tx)
+        .instrument(db_span("DELETE mfa_recovery_codes"))
+        .await?
+        .rows_affected();
+
+    let sessions = sqlx::query!(
+        "UPDATE refresh_tokens SET revoked_at = now()
+         WHERE user_id = $1 AND revoked_at IS NULL",
+        id
+    )
+    .execute(&mut *tx)
+    .instrument(db_span("UPDATE refresh_tokens"))
+    .await?
+    .rows_affected();
+
+    audit::record(
+        &mut *tx,
+        Some(admin.id),
+        "mfa.reset",
+        Some(id),
+        serde_json::json!({
+            "was_enabled": row.mfa_enabled,
+            "had_totp": row.had_totp,
+            "recovery_codes_deleted": recovery_codes,
+            "sessions_revoked": sessions,
+        }),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    state.user_cache.invalidate(&state.db, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
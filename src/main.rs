@@ -1,4 +1,5 @@
 mod app;
+mod cli;
 mod routers;
 mod server;
 mod controllers;
@@ -9,7 +10,12 @@ use crate::app::result::AppResult;
 
 #[tokio::main]
 async fn main() -> AppResult<()> {
-    server::run().await?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        None | Some("serve") => server::run().await?,
+        Some(_) => cli::run(&args).await?,
+    }
 
     Ok(())
 }
\ No newline at end of file
@@ -2,24 +2,44 @@ use std::sync::Arc;
 use axum::{
     extract::{State, FromRequestParts},
     body::Body,
-    http::{Request},
+    http::{Request, request::Parts},
     middleware::Next,
     response::Response,
 };
 use crate::app::{result::AppResult, state::AppState};
-use crate::controllers::auth::me::AuthUser;
+use crate::controllers::auth::me::{AuthUser, PasswordChangeUser};
+use crate::middleware::trace::record_user_id;
 
 pub async fn auth_mw(
     State(app): State<Arc<AppState>>,
-    mut req: Request<Body>,
+    req: Request<Body>,
     next: Next,
 ) -> AppResult<Response> {
     let (mut parts, body) = req.into_parts();
 
     let user = AuthUser::from_request_parts(&mut parts, &app).await?;
 
-    req = Request::<Body>::from_parts(parts, body);
-    req.extensions_mut().insert(user);
+    Ok(next.run(with_user(parts, body, user)).await)
+}
+
+// เหมือน auth_mw แต่ยอมรับ restricted token (ใช้กับ POST /auth/password เท่านั้น)
+pub async fn password_change_auth_mw(
+    State(app): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> AppResult<Response> {
+    let (mut parts, body) = req.into_parts();
+
+    let PasswordChangeUser(user) = PasswordChangeUser::from_request_parts(&mut parts, &app).await?;
+
+    Ok(next.run(with_user(parts, body, user)).await)
+}
+
+fn with_user(parts: Parts, body: Body, user: AuthUser) -> Request<Body> {
+    // ผูก user id เข้ากับ span ของ request (log ทุกบรรทัดหลังจากนี้จะมี user_id)
+    record_user_id(user.id);
 
-    Ok(next.run(req).await)
+    let mut req = Request::<Body>::from_parts(parts, body);
+    req.extensions_mut().insert(user);
+    req
 }
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+use axum::{
+    extract::State,
+    body::Body,
+    http::{HeaderMap, Request, Method, header},
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::extract::cookie::CookieJar;
+use crate::app::{error::AppError, result::AppResult, state::AppState};
+use crate::controllers::auth::utils::{hash_refresh_token, verify_csrf_token};
+
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/*
+|---------------------------------
+| CSRF สำหรับ route ที่ยืนยันตัวตนด้วย cookie (เช่น /auth/refresh, /auth/logout)
+| 1) Sec-Fetch-Site / Origin ต้องมาจาก origin ที่เชื่อถือ
+| 2) X-CSRF-Token ต้องตรงกับ csrf cookie และเซ็นโดยเซิร์ฟเวอร์ให้ refresh cookie ตัวเดียวกัน (signed double-submit)
+|---------------------------------
+*/
+pub async fn csrf_mw(
+    State(app): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> AppResult<Response> {
+    // method ที่ไม่เปลี่ยน state ไม่ต้องตรวจ
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Ok(next.run(req).await);
+    }
+
+    check(&app, req.headers())?;
+
+    Ok(next.run(req).await)
+}
+
+/*
+|---------------------------------
+| CSRF ของ /auth/logout
+| - ไม่มี refresh cookie = ไม่มี session ให้ปกป้อง ---> ผ่านไปลบ cookie ที่ค้าง (เช่น csrf cookie ที่หลงเหลือ)
+| - มี refresh cookie ---> ตรวจเหมือน csrf_mw (กันเว็บอื่นสั่ง logout แทน user)
+|---------------------------------
+*/
+pub async fn logout_csrf_mw(
+    State(app): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> AppResult<Response> {
+    let has_session = CookieJar::from_headers(req.headers())
+        .get(app.cookie_policy.name())
+        .is_some();
+
+    if has_session {
+        check(&app, req.headers())?;
+    }
+
+    Ok(next.run(req).await)
+}
+
+fn check(app: &AppState, headers: &HeaderMap) -> AppResult<()> {
+    let origin = headers
+        .get(header::ORIGIN)
+        .and_then(|v| v.to_str().ok());
+
+    let fetch_site = headers
+        .get("sec-fetch-site")
+        .and_then(|v| v.to_str().ok());
+
+    let trusted_origin = origin
+        .map(|o| app.csrf_trusted_origins.iter().any(|t| t.matches(o)))
+        .unwrap_or(false);
+
+    // browser รุ่นใหม่ส่ง Sec-Fetch-Site มาเสมอ: cross-site ได้เฉพาะ origin ที่อนุญาต
+    if fetch_site == Some("cross-site") && !trusted_origin {
+        return Err(AppError::CsrfFailed);
+    }
+
+    // มี Origin แต่ไม่ใช่ที่อนุญาต และไม่ได้มาจาก origin เดียวกัน ---> ปฏิเสธ
+    if origin.is_some() && !trusted_origin && fetch_site != Some("same-origin") {
+        return Err(AppError::CsrfFailed);
+    }
+
+    // double-submit: header ต้องตรงกับ cookie + ลายเซ็นถูกต้อง + ผูกกับ refresh cookie
+    let jar = CookieJar::from_headers(headers);
+
+    let cookie_token = jar
+        .get(app.cookie_policy.csrf_name())
+        .map(|c| c.value().to_string())
+        .ok_or(AppError::CsrfFailed)?;
+
+    let header_token = headers
+        .get(CSRF_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(AppError::CsrfFailed)?;
+
+    // token ต้องออกให้ session เดียวกับ refresh cookie ที่ส่งมา
+    let session = jar
+        .get(app.cookie_policy.name())
+        .map(|c| hash_refresh_token(c.value(), &app.refresh_secret))
+        .transpose()?
+        .ok_or(AppError::CsrfFailed)?;
+
+    if header_token != cookie_token || !verify_csrf_token(header_token, &session, &app.refresh_secret)? {
+        return Err(AppError::CsrfFailed);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use std::time::Instant;
+use axum::{
+    body::Body,
+    extract::MatchedPath,
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use crate::app::metrics;
+
+/*
+|---------------------------------
+| จับเวลาทุก request แยกตาม route pattern (ไม่ใช่ path จริง กัน label ระเบิด)
+|---------------------------------
+*/
+pub async fn metrics_mw(req: Request<Body>, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().clone();
+    let path = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".into());
+
+    let res = next.run(req).await;
+
+    metrics::http_request(method.as_str(), &path, res.status().as_u16(), start.elapsed());
+
+    res
+}
@@ -0,0 +1,32 @@
+use std::{sync::Arc, time::Instant};
+use axum::{
+    body::Body,
+    extract::State,
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use crate::app::state::AppState;
+
+/*
+|---------------------------------
+| ยืดเวลาตอบของ /auth/login, /auth/magic-link, /auth/email-otp ให้ไม่ต่ำกว่า LOGIN_MIN_RESPONSE_MS (ค่าเริ่มต้น 250)
+| - กลบความต่างของเวลาที่เหลือ (DB / ออก token) หลัง Argon2
+| - 0 = ปิด
+|---------------------------------
+*/
+pub async fn min_response_mw(
+    State(app): State<Arc<AppState>>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+
+    let res = next.run(req).await;
+
+    if !app.login_min_response.is_zero() {
+        tokio::time::sleep_until((started + app.login_min_response).into()).await;
+    }
+
+    res
+}
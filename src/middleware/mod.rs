@@ -1,2 +1,8 @@
 pub mod auth;
-pub mod require_role;
\ No newline at end of file
+pub mod csrf;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod min_response;
+pub mod require_recent_auth;
+pub mod require_role;
+pub mod trace;
\ No newline at end of file
@@ -0,0 +1,41 @@
+use axum::{body::Body, http::Request, middleware::Next, response::Response};
+use chrono::{Duration, Utc};
+use futures::future::BoxFuture;
+use crate::app::error::AppError;
+use crate::controllers::auth::amr;
+use crate::controllers::auth::me::AuthUser;
+
+/*
+|---------------------------------
+| สร้าง middleware checker สำหรับ route สำคัญ (ใช้คู่กับ auth_mw เหมือน require_role)
+| - auth_time ของ token ต้องไม่เก่ากว่า max_age
+| - methods ไม่ว่าง ---> amr ต้องมีอย่างน้อยหนึ่งวิธีในนี้ (เช่น &[amr::MFA] = ต้องผ่านสอง factor)
+| - user ที่เปิด MFA ---> amr ต้องมี "mfa" เสมอ (รหัสทางอีเมลอย่างเดียว / passkey อย่างเดียวไม่ผ่าน)
+| - ไม่ผ่าน ---> 401 insufficient_user_authentication ให้ client เรียก POST /auth/reauth แล้วลองใหม่
+| - service account ไม่มี auth_time ---> ไม่ผ่านเสมอ
+|---------------------------------
+*/
+pub fn require_recent_auth(
+    max_age: Duration,
+    methods: &'static [&'static str],
+) -> impl Fn(Request<Body>, Next) -> BoxFuture<'static, Result<Response, AppError>> + Clone {
+    move |req: Request<Body>, next: Next| {
+        Box::pin(async move {
+            let user = req
+                .extensions()
+                .get::<AuthUser>()
+                .cloned()
+                .ok_or(AppError::Unauthorized)?;
+
+            let recent = user.auth_time.is_some_and(|t| Utc::now() - t <= max_age);
+            let method_ok = methods.is_empty() || user.amr.iter().any(|m| methods.contains(&m.as_str()));
+            let factors_ok = !user.mfa_enabled || user.amr.iter().any(|m| m == amr::MFA);
+
+            if !recent || !method_ok || !factors_ok {
+                return Err(AppError::InsufficientUserAuthentication { max_age: max_age.num_seconds() });
+            }
+
+            Ok(next.run(req).await)
+        })
+    }
+}
@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use axum::{Router, body::Body, http::{HeaderName, Request, Response, header}, middleware::{Next, from_fn}};
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    sensitive_headers::SetSensitiveHeadersLayer,
+    trace::{DefaultOnResponse, OnResponse, TraceLayer},
+};
+use tracing::{Level, Span, field};
+
+use crate::middleware::csrf::CSRF_HEADER;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/*
+|---------------------------------
+| span ต่อ request
+| - log เฉพาะ path (ไม่เอา query string เผื่อมี token)
+| - user_id ว่างไว้ก่อน ---> auth_mw เติมหลังตรวจ token ผ่าน
+| - feature "otel": เป็น server span ต่อจาก traceparent ของผู้เรียก
+|---------------------------------
+*/
+fn make_span(req: &Request<Body>) -> Span {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        path = %req.uri().path(),
+        request_id = %request_id,
+        user_id = field::Empty,
+        http.response.status_code = field::Empty,
+        otel.name = field::Empty,
+        otel.kind = field::Empty,
+        otel.status_code = field::Empty,
+    );
+
+    #[cfg(feature = "otel")]
+    {
+        span.record("otel.name", format!("{} {}", req.method(), req.uri().path()));
+        span.record("otel.kind", "server");
+        crate::app::telemetry::set_remote_parent(&span, req.headers());
+    }
+
+    span
+}
+
+fn on_response(res: &Response<Body>, latency: Duration, span: &Span) {
+    #[cfg(feature = "otel")]
+    {
+        let status = res.status();
+        span.record("http.response.status_code", status.as_u16());
+        if status.is_server_error() {
+            span.record("otel.status_code", "ERROR");
+        }
+    }
+
+    DefaultOnResponse::new().level(Level::INFO).on_response(res, latency, span);
+}
+
+pub fn record_user_id(id: impl std::fmt::Display) {
+    Span::current().record("user_id", field::display(id));
+}
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// เก็บ request id ไว้ให้ AppError ใส่ใน problem+json
+async fn scope_request_id(req: Request<Body>, next: Next) -> Response<Body> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    REQUEST_ID.scope(request_id, next.run(req)).await
+}
+
+// trace id ของ request ปัจจุบัน: OTel trace id (feature "otel") ไม่มีก็ใช้ X-Request-Id
+pub fn current_trace_id() -> Option<String> {
+    #[cfg(feature = "otel")]
+    if let Some(id) = crate::app::telemetry::current_trace_id() {
+        return Some(id);
+    }
+
+    REQUEST_ID
+        .try_with(|id| id.clone())
+        .ok()
+        .filter(|id| !id.is_empty())
+}
+
+/*
+|---------------------------------
+| ครอบ router ด้วย
+| 1) ซ่อน header ที่มีความลับ (Authorization / Cookie / Set-Cookie / CSRF / API key)
+| 2) X-Request-Id: ใช้ของ client ถ้ามี ไม่มีก็สร้าง UUID ใหม่ แล้วส่งกลับใน response
+| 3) TraceLayer (log ตอนจบ request ที่ระดับ INFO)
+|---------------------------------
+*/
+pub fn layer(router: Router) -> Router {
+    let request_id = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    let sensitive = [
+        header::AUTHORIZATION,
+        header::COOKIE,
+        header::SET_COOKIE,
+        header::PROXY_AUTHORIZATION,
+        HeaderName::from_static(CSRF_HEADER),
+        HeaderName::from_static("x-api-key"),
+    ];
+
+    // .layer() ตัวหลังสุดอยู่นอกสุด (ทำงานก่อน)
+    router
+        .layer(from_fn(scope_request_id))
+        .layer(PropagateRequestIdLayer::new(request_id.clone()))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(make_span)
+                .on_response(on_response),
+        )
+        .layer(SetRequestIdLayer::new(request_id, MakeRequestUuid))
+        .layer(SetSensitiveHeadersLayer::new(sensitive))
+}
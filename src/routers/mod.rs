@@ -1,37 +1,134 @@
-use axum::{Router, http::{HeaderValue, Method, header}, middleware::{from_fn_with_state, from_fn}};
-use tower_http::cors::CorsLayer;
+use axum::{Router, extract::DefaultBodyLimit, middleware::{from_fn_with_state, from_fn}};
 use std::sync::Arc;
-use crate::{app::state::AppState, controllers::auth::{logout::logout, refresh_token::refresh}, middleware::{auth::auth_mw, require_role::require_role}};
-use axum::routing::{post, get};
+use crate::{app::state::AppState, controllers::auth::{logout::logout, refresh_token::refresh}, middleware::{auth::{auth_mw, password_change_auth_mw}, csrf::{csrf_mw, logout_csrf_mw}, require_recent_auth::require_recent_auth, require_role::require_role}};
+use axum::routing::{delete, get, post};
 use crate::controllers::auth::login::login;
 use crate::controllers::auth::me;
-use crate::controllers::users::core::list_users;
+use crate::controllers::auth::password::change_password;
+use crate::controllers::auth::{email_otp, magic_link, mfa, mfa_enroll, reauth, webauthn};
+use crate::middleware::{min_response::min_response_mw, trace};
+use crate::controllers::health::core::{healthz, readyz, status};
+use crate::controllers::oauth::{self, introspect::introspect, token::token};
+use crate::controllers::users::core::{deactivate_user, force_logout, list_users, require_password_change, reset_mfa, reset_password};
 
-pub fn api(state: Arc<AppState>) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(HeaderValue::from_static("http://localhost:3000"))
-        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS])
-        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
-        .allow_credentials(true);
+// route group ที่ override CORS ได้ (CORS_AUTH_*, CORS_OAUTH_*, CORS_API_*)
+pub const CORS_GROUPS: &[&str] = &["auth", "oauth", "api"];
 
+pub fn api(state: Arc<AppState>) -> Router {
     let public = Router::new()
         .route("/auth/login", post(login))
+        .route("/auth/magic-link", post(magic_link::request))
+        .route("/auth/email-otp", post(email_otp::request))
+        .route_layer(from_fn_with_state(state.clone(), min_response_mw))
+        ;
+
+    // passkey login (ไม่ต้องกรอก username) + ลิงก์ / รหัสทางอีเมล + factor ที่สองหลังรหัสผ่านผ่าน
+    let ceremonies = Router::new()
+        .route("/auth/magic-link/consume", post(magic_link::consume))
+        .route("/auth/email-otp/verify", post(email_otp::verify))
+        .route("/auth/webauthn/login/begin", post(webauthn::login_begin))
+        .route("/auth/webauthn/login/finish", post(webauthn::login_finish))
+        .route("/auth/mfa/webauthn/begin", post(mfa::webauthn_begin))
+        .route("/auth/mfa/webauthn/finish", post(mfa::webauthn_finish))
+        .route("/auth/mfa/totp", post(mfa::totp))
+        .route("/auth/mfa/recovery", post(mfa::recovery))
+        ;
+
+    // route ที่ยืนยันตัวตนด้วย refresh cookie ---> ต้องผ่าน CSRF (logout ที่ไม่มี refresh cookie ผ่านได้)
+    let cookie_authed = Router::new()
         .route("/auth/refresh", post(refresh))
-        .route("/auth/logout", post(logout))
+        .route_layer(from_fn_with_state(state.clone(), csrf_mw))
+        .merge(
+            Router::new()
+                .route("/auth/logout", post(logout))
+                .route_layer(from_fn_with_state(state.clone(), logout_csrf_mw)),
+        )
+        ;
+
+    let authed = Router::new()
+        .route("/auth/me", get(me::me))
+        .route("/auth/webauthn/credentials", get(webauthn::list_passkeys))
+        .route("/auth/mfa", get(mfa_enroll::status))
+        .route("/auth/reauth", post(reauth::reauth))
+        .route("/auth/step-up/email-otp", post(email_otp::step_up_request))
+        .route("/auth/step-up/email-otp/verify", post(email_otp::step_up_verify))
+        .route_layer(from_fn_with_state(state.clone(), auth_mw))
+        ;
+
+    // เพิ่ม / เปลี่ยน factor / ลบ passkey ---> ต้องยืนยันตัวตนภายใน REAUTH_MAX_AGE_SECS (POST /auth/reauth)
+    // (เปิด MFA ---> ต้องยืนยันด้วยสอง factor)
+    let sensitive = Router::new()
+        .route("/auth/webauthn/register/begin", post(webauthn::register_begin))
+        .route("/auth/webauthn/register/finish", post(webauthn::register_finish))
+        .route("/auth/webauthn/credentials/{id}", delete(webauthn::delete_passkey))
+        .route("/auth/mfa/totp/enroll", post(mfa_enroll::totp_enroll))
+        .route("/auth/mfa/totp/confirm", post(mfa_enroll::totp_confirm))
+        .route("/auth/mfa/recovery-codes", post(mfa_enroll::regenerate_recovery_codes))
+        .route_layer(from_fn(require_recent_auth(state.session_policy.reauth_max_age, &[])))
+        .route_layer(from_fn_with_state(state.clone(), auth_mw))
+        ;
+
+    // restricted token (ต้องเปลี่ยนรหัสผ่านก่อน) เข้าได้แค่กลุ่มนี้
+    let password_change = Router::new()
+        .route("/auth/password", post(change_password))
+        .route_layer(from_fn_with_state(state.clone(), password_change_auth_mw))
+        ;
+
+    let auth = public
+        .merge(ceremonies)
+        .merge(cookie_authed)
+        .merge(authed)
+        .merge(sensitive)
+        .merge(password_change)
+        .layer(DefaultBodyLimit::max(state.auth_body_limit))
+        .layer(state.cors.layer("auth"))
+        ;
+
+    // ฝั่ง service-to-service
+    let oauth = Router::new()
+        .route("/oauth/introspect", post(introspect))
+        .route("/oauth/token", post(token))
+        .route("/oauth/me", get(oauth::me::me))
+        .layer(state.cors.layer("oauth"))
+        ;
+
+    // probe ของ orchestrator (ไม่มี CORS / auth)
+    let health = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
         ;
 
     let admin = Router::new()
+        .route("/status", get(status))
         .route("/users", get(list_users))
+        .route("/users/{id}/deactivate", post(deactivate_user))
+        .route("/users/{id}/force-logout", post(force_logout))
+        .route("/users/{id}/reset-password", post(reset_password))
+        .route("/users/{id}/require-password-change", post(require_password_change))
+        .route("/users/{id}/reset-mfa", post(reset_mfa))
         .route_layer(from_fn(require_role(&["admin"])))
         ;
 
-    let authed = Router::new()
-        .route("/auth/me", get(me::me))
+    let api = Router::new()
         .nest("/api", admin)
         .route_layer(from_fn_with_state(state.clone(), auth_mw))
+        .layer(state.cors.layer("api"))
         ;
 
-    public.merge(authed)
-        .layer(cors)
+    let app = auth.merge(oauth)
+        .merge(api)
+        .merge(health);
+
+    #[cfg(feature = "metrics")]
+    let app = app.layer(from_fn(crate::middleware::metrics::metrics_mw));
+
+    trace::layer(app.with_state(state))
+}
+
+// GET /metrics (mount บน router หลัก หรือแยก listener ตาม METRICS_ADDR)
+#[cfg(feature = "metrics")]
+pub fn metrics(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/metrics", get(crate::controllers::health::metrics::metrics))
         .with_state(state)
-} 
+}
@@ -1,9 +1,25 @@
 use std::{env, net::SocketAddr, sync::Arc};
 use tokio::signal;
+use tracing::info;
+use crate::app::ceremony::CeremonyStore;
+use crate::app::client_ip::TrustedProxies;
+use crate::app::cookies::CookiePolicy;
+use crate::app::cors::{CorsPolicies, OriginPattern};
+use crate::app::email_otp::EmailOtpPolicy;
+use crate::app::encryption::Encryption;
 use crate::app::error::AppError;
+use crate::app::lifecycle::Lifecycle;
+use crate::app::magic_link::MagicLinkPolicy;
+use crate::app::password::PasswordHasher;
+use crate::app::password_policy::PasswordPolicy;
+use crate::app::{logging, mailer, telemetry, webauthn};
 use crate::app::result::AppResult;
+use crate::app::session::SessionPolicy;
 use crate::app::state::AppState;
+use crate::app::user_cache::{self, UserSecurityCache};
 use crate::routers;
+use crate::controllers::auth::utils::hash_refresh_token;
+use crate::utils::env::{env_i64, env_list, is_production};
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 
@@ -15,6 +31,14 @@ pub async fn run() -> AppResult<()> {
         dotenv::dotenv()?;
     }
 
+    // APP_ENV (ค่าเริ่มต้น: development ตอน debug build, production ตอน release build)
+    let production = is_production();
+
+    // -----------------------
+    // Logging (LOG_FORMAT / RUST_LOG)
+    // -----------------------
+    logging::init(production)?;
+
     let database_url = std::env::var("DATABASE_URL")
         .map_err(|_| AppError::BadRequest("DATABASE_URL is not set".into()))?;
 
@@ -40,21 +64,122 @@ pub async fn run() -> AppResult<()> {
         .parse()
         .unwrap_or(7200);
 
+    // Argon2id params + pepper ของรหัสผ่าน user
+    let password_hasher = PasswordHasher::from_env()?;
+
+    // นโยบายรหัสผ่านใหม่ (ความยาว / ความแข็งแรง / รายการที่หลุด)
+    let password_policy = PasswordPolicy::from_env()?;
+
+    // WebAuthn Relying Party (passkey) + สถานะ ceremony ที่ค้างระหว่าง request
+    let webauthn = Arc::new(webauthn::from_env()?);
+    let ceremonies = CeremonyStore::from_env()?;
+
+    // KEK สำหรับคอลัมน์ที่เข้ารหัส (TOTP secret ฯลฯ)
+    let encryption = Encryption::from_env(production)?;
+
+    // ส่งอีเมล (MAILER) + ลิงก์ login ทางอีเมล
+    let mailer = mailer::from_env(production)?;
+    let magic_link = MagicLinkPolicy::from_env()?;
+    let email_otp = EmailOtpPolicy::from_env()?;
+
+    // อายุ refresh token / session (idle + absolute)
+    let session_policy = SessionPolicy::from_env()?;
+
+    // นโยบาย refresh cookie (production ต้อง Secure ไม่งั้นไม่ยอม start)
+    let cookie_policy = CookiePolicy::from_env(production)?;
+
+    // CORS (ค่าหลัก + override ราย route group)
+    let cors = CorsPolicies::from_env(routers::CORS_GROUPS)?;
+
+    // origin ที่อนุญาตให้เรียก route ที่ใช้ cookie แบบ cross-site (CSRF)
+    // ไม่ตั้ง ---> ใช้ origin เดียวกับ CORS (ยกเว้น "*")
+    let csrf_trusted_origins = match env_list("CSRF_TRUSTED_ORIGINS") {
+        v if v.is_empty() => cors
+            .default
+            .origins
+            .iter()
+            .filter(|o| **o != OriginPattern::Any)
+            .cloned()
+            .collect(),
+        v if v.iter().any(|o| o.trim() == "*") => {
+            return Err(AppError::BadRequest("CSRF_TRUSTED_ORIGINS must not contain \"*\"".into()));
+        }
+        v => v.iter().map(|o| OriginPattern::parse(o)).collect::<AppResult<_>>()?,
+    };
+
+    // reverse proxy ที่เชื่อ X-Forwarded-For (IP ของ client ใน session)
+    let trusted_proxies = TrustedProxies::from_env()?;
+
+    // API key สำหรับ resource server ที่เรียก /oauth/introspect (เก็บเป็น HMAC)
+    let introspection_api_keys = env_list("INTROSPECTION_API_KEYS")
+        .iter()
+        .map(|k| hash_refresh_token(k, &refresh_secret))
+        .collect::<AppResult<Vec<_>>>()?;
+
     // -----------------------
     // เชื่อมต่อ Database
     // -----------------------
     let db = AppState::connect(&database_url).await?;
 
+    // -----------------------
+    // Cache สถานะ security ของ user + LISTEN invalidation
+    // -----------------------
+    let user_cache = UserSecurityCache::from_env()?;
+    user_cache::spawn_listener(db.clone(), user_cache.clone()).await?;
+
+    // -----------------------
+    // Metrics (cargo feature "metrics")
+    // -----------------------
+    crate::app::metrics::install()?;
+
+    // ถ้าตั้ง METRICS_TOKEN ---> /metrics ต้องส่ง Bearer token (เก็บเป็น HMAC)
+    let metrics_token = std::env::var("METRICS_TOKEN")
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| hash_refresh_token(t.trim(), &refresh_secret))
+        .transpose()?;
+
+    // -----------------------
+    // Request body limit (กลุ่ม /auth)
+    // -----------------------
+    let auth_body_limit = env_i64("AUTH_BODY_LIMIT_BYTES", 16 * 1024)?.clamp(1024, 1024 * 1024) as usize;
+
+    // เวลาตอบขั้นต่ำของ /auth/login, /auth/magic-link, /auth/email-otp (0 = ปิด)
+    let login_min_response = std::time::Duration::from_millis(
+        env_i64("LOGIN_MIN_RESPONSE_MS", 250)?.clamp(0, 10_000) as u64,
+    );
+
     // -----------------------
     // Shared AppState
     // -----------------------
+    let lifecycle = Arc::new(Lifecycle::new());
+
     let state = Arc::new(AppState {
         db,
         jwt_secret,
         jwt_issuer,
         jwt_audience,
         access_token_ttl,
-        refresh_secret
+        refresh_secret,
+        user_cache,
+        introspection_api_keys,
+        session_policy,
+        cookie_policy,
+        cors,
+        csrf_trusted_origins,
+        trusted_proxies,
+        lifecycle: lifecycle.clone(),
+        metrics_token,
+        auth_body_limit,
+        login_min_response,
+        password_hasher,
+        password_policy,
+        webauthn,
+        ceremonies,
+        encryption,
+        mailer,
+        magic_link,
+        email_otp,
     });
   
     // -----------------------
@@ -70,17 +195,78 @@ pub async fn run() -> AppResult<()> {
         .parse()
         .map_err(|e| AppError::BadRequest(format!("invalid address: {e}")))?;
 
-    let app = routers::api(state);
-    println!("App running on: {addr}");
+    // เวลารอหลังเริ่ม shutdown ก่อนหยุดรับ connection (ให้ orchestrator เห็น /readyz = 503 ก่อน)
+    let drain_secs = env_i64("SHUTDOWN_DRAIN_SECS", 0)?.clamp(0, 300) as u64;
+
+    let app = routers::api(state.clone());
+
+    // METRICS_ADDR ตั้งไว้ ---> แยก /metrics ไปอีก listener (เช่น 127.0.0.1:9100)
+    #[cfg(feature = "metrics")]
+    let app = match env::var("METRICS_ADDR").ok().filter(|a| !a.trim().is_empty()) {
+        Some(metrics_addr) => {
+            let metrics_addr: SocketAddr = metrics_addr
+                .trim()
+                .parse()
+                .map_err(|e| AppError::BadRequest(format!("invalid METRICS_ADDR: {e}")))?;
+            let metrics_listener = tokio::net::TcpListener::bind(metrics_addr).await?;
+            let metrics_app = routers::metrics(state.clone());
+
+            info!(%metrics_addr, "metrics listening");
+            tokio::spawn(async move {
+                if let Err(e) = axum::serve(metrics_listener, metrics_app).await {
+                    tracing::error!(error = ?e, "metrics listener stopped");
+                }
+            });
+
+            app
+        }
+        None => app.merge(routers::metrics(state.clone())),
+    };
+
+    info!(%addr, "app running");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async {
-            let _ = signal::ctrl_c().await;
-            println!("App offline");
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+
+            // /readyz เริ่มตอบ 503 ทันที
+            lifecycle.begin_shutdown();
+            info!(drain_secs, "app shutting down");
+
+            tokio::time::sleep(std::time::Duration::from_secs(drain_secs)).await;
+            info!("app offline");
         })
         .await?;
 
+    // ส่ง span ที่ค้างใน batch ก่อนออก
+    let _ = tokio::task::spawn_blocking(telemetry::shutdown).await;
+
     Ok(())
 }
+
+// Ctrl+C (dev) หรือ SIGTERM (container/orchestrator)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
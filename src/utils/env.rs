@@ -19,3 +19,63 @@ pub fn env_i64(name: &str, default: i64) -> AppResult<i64> {
         _ => Ok(default), // พบแต่ค่าว่าง -> ใช้ default
     }
 }
+
+/*
+| ----------------------------
+| fn env_list
+| - คั่นด้วย comma, ตัดช่องว่าง, ข้ามค่าว่าง
+| - let keys = env_list("INTROSPECTION_API_KEYS");
+| ----------------------------
+*/
+pub fn env_list(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/*
+| ----------------------------
+| fn env_string
+| - ไม่มี/ค่าว่าง -> ใช้ default
+| ----------------------------
+*/
+pub fn env_string(name: &str, default: &str) -> String {
+    std::env::var(name)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/*
+| ----------------------------
+| fn env_bool
+| - รับ 1/0, true/false, yes/no, on/off
+| - let secure = env_bool("COOKIE_SECURE", true)?;
+| ----------------------------
+*/
+pub fn env_bool(name: &str, default: bool) -> AppResult<bool> {
+    use crate::app::error::AppError;
+
+    match std::env::var(name).ok().map(|s| s.trim().to_ascii_lowercase()) {
+        None => Ok(default),
+        Some(s) if s.is_empty() => Ok(default),
+        Some(s) if matches!(s.as_str(), "1" | "true" | "yes" | "on") => Ok(true),
+        Some(s) if matches!(s.as_str(), "0" | "false" | "no" | "off") => Ok(false),
+        Some(s) => Err(AppError::BadRequest(format!("invalid {name}: {s}"))),
+    }
+}
+
+/*
+| ----------------------------
+| fn is_production
+| - APP_ENV (ค่าเริ่มต้น: development ตอน debug build, production ตอน release build)
+| ----------------------------
+*/
+pub fn is_production() -> bool {
+    let default_env = if cfg!(debug_assertions) { "development" } else { "production" };
+    env_string("APP_ENV", default_env).eq_ignore_ascii_case("production")
+}
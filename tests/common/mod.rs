@@ -0,0 +1,76 @@
+// helper ร่วมของเทสต์ที่ยิง server จริง (แต่ละไฟล์ใช้ไม่ครบทุกตัว)
+#![allow(dead_code)]
+
+use std::env;
+
+use axum::{Json, Router, extract::State, routing::post};
+use serde_json::Value;
+use tokio::sync::{Mutex, MutexGuard, mpsc};
+
+/*
+|---------------------------------
+| URL ของ server ที่รันไว้
+| - TEST_URL (ถ้าตั้ง) ไม่งั้นใช้ HOST/PORT จาก .env
+|---------------------------------
+*/
+pub fn base_url() -> String {
+    dotenv::dotenv().ok();
+    let host = env::var("HOST").unwrap_or_else(|_| "127.0.0.1".into());
+    let port = env::var("PORT").unwrap_or_else(|_| "3000".into());
+    env::var("TEST_URL").unwrap_or_else(|_| format!("http://{host}:{port}"))
+}
+
+pub fn url(path: &str) -> String {
+    format!("{}{path}", base_url())
+}
+
+// POST JSON (+ Bearer token) ---> (status, body)
+pub async fn call(path: &str, token: Option<&str>, body: Value) -> (u16, Value) {
+    let mut req = reqwest::Client::new().post(url(path)).json(&body);
+    if let Some(token) = token {
+        req = req.bearer_auth(token);
+    }
+
+    let res = req.send().await.expect("server not reachable");
+    (res.status().as_u16(), res.json().await.unwrap_or(Value::Null))
+}
+
+// cookie จาก Set-Cookie ---> header Cookie (name=value; ...)
+pub fn cookies(res: &reqwest::Response) -> String {
+    res.headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .filter_map(|v| v.split(';').next())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/*
+|---------------------------------
+| รับ JSON ที่ WebhookMailer POST มา
+| - TEST_WEBHOOK_ADDR (ค่าเริ่มต้น 127.0.0.1:3998) ---> รัน server ด้วย MAILER_WEBHOOK_URL=http://<addr>/
+|---------------------------------
+*/
+pub async fn start_inbox() -> mpsc::UnboundedReceiver<Value> {
+    let addr = env::var("TEST_WEBHOOK_ADDR").unwrap_or_else(|_| "127.0.0.1:3998".into());
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    let app = Router::new()
+        .route("/", post(|State(tx): State<mpsc::UnboundedSender<Value>>, Json(mail): Json<Value>| async move {
+            tx.send(mail).ok();
+        }))
+        .with_state(tx);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await.expect("cannot bind webhook addr");
+    tokio::spawn(async move { axum::serve(listener, app).await.ok() });
+
+    rx
+}
+
+// เทสต์ที่ใช้ user / inbox เดียวกัน ---> รันทีละตัว (cargo test รันเทสต์ในไฟล์เดียวกันพร้อมกัน)
+static SERIAL: Mutex<()> = Mutex::const_new(());
+
+pub async fn serial() -> MutexGuard<'static, ()> {
+    SERIAL.lock().await
+}
@@ -0,0 +1,130 @@
+use std::{env, time::Duration};
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+mod common;
+
+use common::{call, serial, start_inbox};
+
+/*
+|---------------------------------
+| รหัส 6 หลักทางอีเมล (รับเมลผ่าน webhook mailer)
+| - ต้องรัน server ด้วย MAILER=webhook MAILER_WEBHOOK_URL=http://<TEST_WEBHOOK_ADDR>/
+| - EMAIL_OTP_TEST_EMAIL = อีเมลของ user ที่มีอยู่ (ไม่เปิด MFA)
+| - EMAIL_OTP_MAX_PER_EMAIL ให้ตรงกับ server (ค่าเริ่มต้น 5), DATABASE_URL ใช้ล้างรหัสของ user
+|---------------------------------
+*/
+
+// ดึงรหัส 6 หลักจากเนื้อเมล
+async fn receive_code(inbox: &mut mpsc::UnboundedReceiver<Value>) -> String {
+    let mail = tokio::time::timeout(Duration::from_secs(5), inbox.recv())
+        .await
+        .expect("no mail received")
+        .unwrap();
+
+    mail["text"]
+        .as_str()
+        .unwrap()
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|w| w.len() == 6)
+        .expect("code in mail")
+        .to_string()
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_email_otp_login_and_step_up() {
+    let _serial = serial().await;
+    let email = env::var("EMAIL_OTP_TEST_EMAIL").expect("EMAIL_OTP_TEST_EMAIL must be set");
+    let mut inbox = start_inbox().await;
+
+    // อีเมลที่ไม่มีในระบบ ---> ตอบเหมือนกัน แต่ไม่มีเมล และ otp_token ใช้ไม่ได้
+    let (status, body) = call("/auth/email-otp", None, json!({ "email": "nobody-here@example.invalid" })).await;
+    assert_eq!(status, 202, "{body}");
+    assert!(body["otp_token"].is_string());
+    assert!(tokio::time::timeout(Duration::from_secs(1), inbox.recv()).await.is_err());
+
+    let (status, body) = call("/auth/email-otp", None, json!({ "email": email })).await;
+    assert_eq!(status, 202, "{body}");
+    let otp_token = body["otp_token"].as_str().unwrap().to_string();
+    let code = receive_code(&mut inbox).await;
+
+    // รหัสผิด ---> 401 (รหัสจริงยังใช้ได้ถ้ายังไม่ครบจำนวนครั้ง)
+    let wrong = if code == "000000" { "111111" } else { "000000" };
+    let (status, body) = call("/auth/email-otp/verify", None, json!({ "otp_token": otp_token, "code": wrong })).await;
+    assert_eq!(status, 401, "{body}");
+
+    let (status, body) = call("/auth/email-otp/verify", None, json!({ "otp_token": otp_token, "code": code })).await;
+    assert_eq!(status, 200, "{body}");
+    let access_token = body["access_token"].as_str().unwrap().to_string();
+
+    // ใช้ได้ครั้งเดียว
+    let (status, _) = call("/auth/email-otp/verify", None, json!({ "otp_token": otp_token, "code": code })).await;
+    assert_eq!(status, 401);
+
+    // step-up: ได้ access token ใหม่ที่ auth_time ใหม่ + amr มี email_otp
+    let (status, body) = call("/auth/step-up/email-otp", Some(&access_token), json!({})).await;
+    assert_eq!(status, 202, "{body}");
+    let otp_token = body["otp_token"].as_str().unwrap().to_string();
+    let code = receive_code(&mut inbox).await;
+
+    let (status, body) = call(
+        "/auth/step-up/email-otp/verify",
+        Some(&access_token),
+        json!({ "otp_token": otp_token, "code": wrong }),
+    )
+    .await;
+    assert_eq!(status, 422, "{body}");
+
+    let (status, body) = call(
+        "/auth/step-up/email-otp/verify",
+        Some(&access_token),
+        json!({ "otp_token": otp_token, "code": code }),
+    )
+    .await;
+    assert_eq!(status, 200, "{body}");
+    assert!(body["access_token"].is_string());
+    assert!(body["amr"].as_array().unwrap().iter().any(|m| m == "email_otp"));
+}
+
+// ขอพร้อมกันเกินจำนวน ---> สร้างรหัสได้ไม่เกิน EMAIL_OTP_MAX_PER_EMAIL (ล็อกแถว user ก่อนนับ)
+#[tokio::test]
+#[ignore]
+async fn test_email_otp_limit_under_concurrency() {
+    let _serial = serial().await;
+    let email = env::var("EMAIL_OTP_TEST_EMAIL").expect("EMAIL_OTP_TEST_EMAIL must be set");
+    let max: i64 = env::var("EMAIL_OTP_MAX_PER_EMAIL").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+    let pool = PgPool::connect(&env::var("DATABASE_URL").expect("DATABASE_URL must be set")).await.unwrap();
+
+    let clear = || {
+        sqlx::query("DELETE FROM email_otp_codes WHERE user_id = (SELECT id FROM users WHERE email = $1::text::citext)")
+            .bind(&email)
+            .execute(&pool)
+    };
+    clear().await.unwrap();
+    let mut inbox = start_inbox().await;
+
+    let requests = (0..max + 5).map(|_| call("/auth/email-otp", None, json!({ "email": email })));
+    for (status, body) in futures::future::join_all(requests).await {
+        assert_eq!(status, 202, "{body}");
+    }
+
+    let created: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM email_otp_codes WHERE user_id = (SELECT id FROM users WHERE email = $1::text::citext)",
+    )
+    .bind(&email)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    clear().await.unwrap();
+    assert_eq!(created, max);
+
+    // ส่งเมลเท่าที่สร้างได้เท่านั้น
+    for _ in 0..max {
+        tokio::time::timeout(Duration::from_secs(5), inbox.recv()).await.expect("no mail received");
+    }
+    assert!(tokio::time::timeout(Duration::from_secs(1), inbox.recv()).await.is_err());
+}
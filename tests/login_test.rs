@@ -0,0 +1,50 @@
+use std::{env, time::{Duration, Instant}};
+
+use serde_json::{Value, json};
+
+mod common;
+
+use common::url;
+
+// ต้องรัน server ไว้ก่อน (ใช้ TEST_URL หรือ HOST/PORT จาก .env) และมี user ตาม LOGIN_TEST_USERNAME
+
+// (status, body ไม่รวม trace_id, เวลาที่ใช้)
+async fn login(username: &str, password: &str) -> (u16, Value, Duration) {
+    let started = Instant::now();
+    let res = reqwest::Client::new()
+        .post(url("/auth/login"))
+        .json(&json!({ "username": username, "password": password }))
+        .send()
+        .await
+        .expect("server not reachable");
+
+    let status = res.status().as_u16();
+    let mut body = res.json::<Value>().await.unwrap_or(Value::Null);
+    if let Some(body) = body.as_object_mut() {
+        body.remove("trace_id");
+    }
+    (status, body, started.elapsed())
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_login_failures_are_uniform() {
+    let known = env::var("LOGIN_TEST_USERNAME").expect("LOGIN_TEST_USERNAME must be set");
+
+    // ต้องตรงกับ LOGIN_MIN_RESPONSE_MS ของ server (ค่าเริ่มต้น 250)
+    let min_response = Duration::from_millis(
+        env::var("LOGIN_MIN_RESPONSE_MS").ok().and_then(|v| v.parse().ok()).unwrap_or(250),
+    );
+
+    let (unknown_status, unknown_body, unknown_elapsed) = login("no-such-user-7f3a9c", "whatever-password").await;
+    let (wrong_status, wrong_body, wrong_elapsed) = login(&known, "definitely-not-the-password").await;
+
+    assert_eq!(unknown_status, 401);
+    assert_eq!(unknown_status, wrong_status);
+    assert_eq!(unknown_body["code"], "invalid_credentials");
+    assert_eq!(unknown_body, wrong_body);
+
+    // padding ---> ทั้งสองกรณีไม่เร็วกว่า LOGIN_MIN_RESPONSE_MS
+    assert!(unknown_elapsed >= min_response, "{unknown_elapsed:?} < {min_response:?}");
+    assert!(wrong_elapsed >= min_response, "{wrong_elapsed:?} < {min_response:?}");
+}
@@ -0,0 +1,136 @@
+use std::{env, time::Duration};
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+mod common;
+
+use common::{serial, start_inbox, url};
+
+/*
+|---------------------------------
+| ลิงก์ login ทางอีเมล (รับเมลผ่าน webhook mailer)
+| - ต้องรัน server ด้วย MAILER=webhook MAILER_WEBHOOK_URL=http://<TEST_WEBHOOK_ADDR>/
+| - MAGIC_LINK_TEST_EMAIL = อีเมลของ user ที่มีอยู่ (ไม่เปิด MFA)
+| - MAGIC_LINK_MAX_PER_EMAIL ให้ตรงกับ server (ค่าเริ่มต้น 5), DATABASE_URL ใช้ล้างลิงก์ของ user
+|---------------------------------
+*/
+async fn request_link(email: &str) -> (u16, Option<String>, Value) {
+    let res = reqwest::Client::new()
+        .post(url("/auth/magic-link"))
+        .json(&json!({ "email": email }))
+        .send()
+        .await
+        .expect("server not reachable");
+
+    let status = res.status().as_u16();
+    let nonce_cookie = res
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find(|v| v.contains("magic_link_nonce="))
+        .and_then(|v| v.split(';').next())
+        .map(String::from);
+
+    (status, nonce_cookie, res.json().await.unwrap_or(Value::Null))
+}
+
+async fn consume(token: &str, cookie: Option<&str>) -> (u16, Value) {
+    let mut req = reqwest::Client::new()
+        .post(url("/auth/magic-link/consume"))
+        .json(&json!({ "token": token }));
+
+    if let Some(cookie) = cookie {
+        req = req.header("cookie", cookie);
+    }
+
+    let res = req.send().await.expect("server not reachable");
+    (res.status().as_u16(), res.json().await.unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_magic_link_login() {
+    let _serial = serial().await;
+    let email = env::var("MAGIC_LINK_TEST_EMAIL").expect("MAGIC_LINK_TEST_EMAIL must be set");
+    let mut inbox = start_inbox().await;
+
+    // อีเมลที่ไม่มีในระบบ ---> ตอบเหมือนกัน แต่ไม่มีเมล
+    let (status, cookie, body) = request_link("nobody-here@example.invalid").await;
+    assert_eq!(status, 202, "{body}");
+    assert!(cookie.is_some());
+    assert!(tokio::time::timeout(Duration::from_secs(1), inbox.recv()).await.is_err());
+
+    let (status, cookie, body) = request_link(&email).await;
+    assert_eq!(status, 202, "{body}");
+    let cookie = cookie.expect("nonce cookie");
+
+    let mail = tokio::time::timeout(Duration::from_secs(5), inbox.recv())
+        .await
+        .expect("no mail received")
+        .unwrap();
+    assert_eq!(mail["to"].as_str().unwrap().to_lowercase(), email.to_lowercase());
+
+    let text = mail["text"].as_str().unwrap();
+    let token = text
+        .split("token=")
+        .nth(1)
+        .and_then(|t| t.split_whitespace().next())
+        .expect("link in mail");
+
+    // browser อื่น (ไม่มี nonce / nonce ไม่ตรง) ใช้ลิงก์ไม่ได้
+    let (status, _) = consume(token, None).await;
+    assert_eq!(status, 401);
+    let (status, _) = consume(token, Some("magic_link_nonce=forged")).await;
+    assert_eq!(status, 401);
+
+    let (status, body) = consume(token, Some(&cookie)).await;
+    assert_eq!(status, 200, "{body}");
+    assert!(body["access_token"].is_string());
+
+    // ใช้ได้ครั้งเดียว
+    let (status, body) = consume(token, Some(&cookie)).await;
+    assert_eq!(status, 401, "{body}");
+    assert_eq!(body["code"], "invalid_credentials");
+}
+
+// ขอพร้อมกันเกินจำนวน ---> สร้างลิงก์ได้ไม่เกิน MAGIC_LINK_MAX_PER_EMAIL (ล็อกแถว user ก่อนนับ)
+#[tokio::test]
+#[ignore]
+async fn test_magic_link_limit_under_concurrency() {
+    let _serial = serial().await;
+    let email = env::var("MAGIC_LINK_TEST_EMAIL").expect("MAGIC_LINK_TEST_EMAIL must be set");
+    let max: i64 = env::var("MAGIC_LINK_MAX_PER_EMAIL").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+    let pool = PgPool::connect(&env::var("DATABASE_URL").expect("DATABASE_URL must be set")).await.unwrap();
+
+    let clear = || {
+        sqlx::query("DELETE FROM magic_link_tokens WHERE user_id = (SELECT id FROM users WHERE email = $1::text::citext)")
+            .bind(&email)
+            .execute(&pool)
+    };
+    clear().await.unwrap();
+    let mut inbox = start_inbox().await;
+
+    let requests = (0..max + 5).map(|_| request_link(&email));
+    for (status, _, body) in futures::future::join_all(requests).await {
+        assert_eq!(status, 202, "{body}");
+    }
+
+    let created: i64 = sqlx::query_scalar(
+        "SELECT count(*) FROM magic_link_tokens WHERE user_id = (SELECT id FROM users WHERE email = $1::text::citext)",
+    )
+    .bind(&email)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    clear().await.unwrap();
+    assert_eq!(created, max);
+
+    // ส่งเมลเท่าที่สร้างได้เท่านั้น
+    for _ in 0..max {
+        tokio::time::timeout(Duration::from_secs(5), inbox.recv()).await.expect("no mail received");
+    }
+    assert!(tokio::time::timeout(Duration::from_secs(1), inbox.recv()).await.is_err());
+}
@@ -0,0 +1,152 @@
+use std::env;
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+
+mod common;
+
+use common::{call, url};
+
+/*
+|---------------------------------
+| ลงทะเบียน TOTP + recovery code
+| - ต้องรัน server ไว้ก่อน และมี user ตาม MFA_TEST_USERNAME / MFA_TEST_PASSWORD (ยังไม่เปิด TOTP)
+| - MFA_TEST_ADMIN_USERNAME / MFA_TEST_ADMIN_PASSWORD ใช้ล้าง MFA ตอนจบ
+| - DATABASE_URL ใช้ปลดล็อก user หลังเทสต์ lockout
+|---------------------------------
+*/
+async fn login(user_var: &str, pass_var: &str) -> Value {
+    let username = env::var(user_var).unwrap_or_else(|_| panic!("{user_var} must be set"));
+    let password = env::var(pass_var).unwrap_or_else(|_| panic!("{pass_var} must be set"));
+
+    let (status, body) = call("/auth/login", None, json!({ "username": username, "password": password })).await;
+    assert_eq!(status, 200, "{body}");
+    body
+}
+
+fn current_code(secret: &str) -> String {
+    let secret = Secret::Encoded(secret.into()).to_bytes().unwrap();
+    TOTP::new_unchecked(Algorithm::SHA1, 6, 0, 30, secret, None, String::new()).generate_current().unwrap()
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_totp_enroll_and_recovery_codes() {
+    let session = login("MFA_TEST_USERNAME", "MFA_TEST_PASSWORD").await;
+    let token = session["access_token"].as_str().expect("access_token").to_string();
+
+    let me: Value = reqwest::Client::new()
+        .get(url("/auth/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    // 1) ลงทะเบียน TOTP
+    let (status, enroll) = call("/auth/mfa/totp/enroll", Some(&token), json!({})).await;
+    assert_eq!(status, 200, "{enroll}");
+    assert!(enroll["otpauth_url"].as_str().unwrap().starts_with("otpauth://totp/"));
+
+    let secret = enroll["secret"].as_str().unwrap().to_string();
+
+    // รหัสผิดลองใหม่ได้
+    let (status, body) = call(
+        "/auth/mfa/totp/confirm",
+        Some(&token),
+        json!({ "ceremony_id": enroll["ceremony_id"], "code": "000000x" }),
+    )
+    .await;
+    assert_eq!(status, 422, "{body}");
+
+    let (status, confirmed) = call(
+        "/auth/mfa/totp/confirm",
+        Some(&token),
+        json!({ "ceremony_id": enroll["ceremony_id"], "code": current_code(&secret) }),
+    )
+    .await;
+    assert_eq!(status, 200, "{confirmed}");
+
+    let codes: Vec<String> = serde_json::from_value(confirmed["recovery_codes"].clone()).unwrap();
+    // ต้องตรงกับ MFA_RECOVERY_CODE_COUNT ของ server (ค่าเริ่มต้น 10, จำกัด 4-20)
+    let expected = env::var("MFA_RECOVERY_CODE_COUNT").ok().and_then(|v| v.parse().ok()).unwrap_or(10usize).clamp(4, 20);
+    assert_eq!(codes.len(), expected);
+
+    // เปิด MFA แล้ว ---> token ที่ผ่านรหัสผ่านอย่างเดียวเข้า route สำคัญไม่ได้ และ reauth ต้องมี factor ที่สอง
+    let (status, body) = call("/auth/mfa/recovery-codes", Some(&token), json!({})).await;
+    assert_eq!(status, 401, "{body}");
+    assert_eq!(body["code"], "insufficient_user_authentication");
+
+    let password = env::var("MFA_TEST_PASSWORD").unwrap();
+    let (status, body) = call("/auth/reauth", Some(&token), json!({ "password": password })).await;
+    assert_eq!(status, 422, "{body}");
+
+    // 2) login ต้องผ่าน factor ที่สอง ---> ใช้ recovery code แทน TOTP
+    let challenge = login("MFA_TEST_USERNAME", "MFA_TEST_PASSWORD").await;
+    assert_eq!(challenge["mfa_required"], true);
+    let methods = challenge["methods"].as_array().unwrap();
+    assert!(methods.contains(&json!("totp")));
+    assert!(methods.contains(&json!("recovery_code")));
+
+    // ไม่สนตัวพิมพ์ / ขีด
+    let typed = codes[0].replace('-', " ").to_uppercase();
+    let (status, body) = call("/auth/mfa/recovery", None, json!({ "mfa_token": challenge["mfa_token"], "code": typed })).await;
+    assert_eq!(status, 200, "{body}");
+    let mfa_token = body["access_token"].as_str().unwrap().to_string();
+
+    // รหัสเดิมใช้ซ้ำไม่ได้
+    let challenge = login("MFA_TEST_USERNAME", "MFA_TEST_PASSWORD").await;
+    let (status, body) = call("/auth/mfa/recovery", None, json!({ "mfa_token": challenge["mfa_token"], "code": codes[0] })).await;
+    assert_eq!(status, 401, "{body}");
+
+    // 3) สร้างชุดใหม่ (token ที่ผ่านสอง factor) ---> ชุดเก่าใช้ไม่ได้
+    let (status, regenerated) = call("/auth/mfa/recovery-codes", Some(&mfa_token), json!({})).await;
+    assert_eq!(status, 200, "{regenerated}");
+
+    let challenge = login("MFA_TEST_USERNAME", "MFA_TEST_PASSWORD").await;
+    let (status, _) = call("/auth/mfa/recovery", None, json!({ "mfa_token": challenge["mfa_token"], "code": codes[1] })).await;
+    assert_eq!(status, 401);
+
+    // 4) factor ที่สองผิดนับรวมกับรหัสผ่าน (รวม recovery code ที่ผิดด้านบน)
+    //    ---> ครบ 5 ครั้งถูกล็อก รหัสผ่านถูกก็ไม่ได้ mfa_token
+    let username = env::var("MFA_TEST_USERNAME").unwrap();
+    let mut locked_status = 200;
+    for _ in 0..=5 {
+        let (status, challenge) = call("/auth/login", None, json!({ "username": username, "password": password })).await;
+        locked_status = status;
+        if status != 200 {
+            break;
+        }
+
+        let (status, _) = call("/auth/mfa/totp", None, json!({ "mfa_token": challenge["mfa_token"], "code": "000000" })).await;
+        assert_eq!(status, 401);
+    }
+
+    let pool = PgPool::connect(&env::var("DATABASE_URL").expect("DATABASE_URL must be set")).await.unwrap();
+    sqlx::query("UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE id = $1::uuid")
+        .bind(me["id"].as_str().unwrap())
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert_eq!(locked_status, 401);
+
+    // 5) admin ล้าง MFA ---> login ด้วยรหัสผ่านอย่างเดียวได้อีกครั้ง
+    let admin = login("MFA_TEST_ADMIN_USERNAME", "MFA_TEST_ADMIN_PASSWORD").await;
+    let (status, body) = call(
+        &format!("/api/users/{}/reset-mfa", me["id"].as_str().unwrap()),
+        admin["access_token"].as_str(),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, 204, "{body}");
+
+    // session เดิมถูก logout ไปด้วย
+    let res = reqwest::Client::new().get(url("/auth/me")).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+
+    let session = login("MFA_TEST_USERNAME", "MFA_TEST_PASSWORD").await;
+    assert!(session["access_token"].is_string());
+}
@@ -0,0 +1,111 @@
+use std::env;
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+mod common;
+
+use common::{serial, url};
+
+/*
+|---------------------------------
+| POST /auth/password + PASSWORD_HISTORY_DEPTH (N นับรวมรหัสปัจจุบัน)
+| - PASSWORD_HISTORY_TEST_USERNAME / PASSWORD_HISTORY_TEST_PASSWORD = user ที่ไม่เปิด MFA
+| - เปลี่ยนรหัส N ครั้งแล้วกลับมาใช้รหัสเดิม (จบแล้วรหัสเหมือนก่อนรัน)
+| - DATABASE_URL ใช้ปลดล็อก user หลังเทสต์ lockout
+|---------------------------------
+*/
+async fn login(username: &str, password: &str) -> String {
+    let res = reqwest::Client::new()
+        .post(url("/auth/login"))
+        .json(&json!({ "username": username, "password": password }))
+        .send()
+        .await
+        .expect("server not reachable");
+    assert_eq!(res.status().as_u16(), 200, "login as {username}");
+
+    let body: Value = res.json().await.unwrap();
+    body["access_token"].as_str().unwrap().to_string()
+}
+
+// เปลี่ยนรหัส (login ใหม่ทุกครั้ง เพราะเปลี่ยนแล้ว token เดิมใช้ไม่ได้) ---> status
+async fn change(username: &str, current: &str, new: &str) -> u16 {
+    let token = login(username, current).await;
+    change_with(&token, current, new).await
+}
+
+async fn change_with(token: &str, current: &str, new: &str) -> u16 {
+    reqwest::Client::new()
+        .post(url("/auth/password"))
+        .bearer_auth(token)
+        .json(&json!({ "current_password": current, "new_password": new }))
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .as_u16()
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_history_depth_counts_current_password() {
+    let _serial = serial().await;
+    let username = env::var("PASSWORD_HISTORY_TEST_USERNAME").expect("PASSWORD_HISTORY_TEST_USERNAME must be set");
+    let original = env::var("PASSWORD_HISTORY_TEST_PASSWORD").expect("PASSWORD_HISTORY_TEST_PASSWORD must be set");
+    let depth: usize = env::var("PASSWORD_HISTORY_DEPTH").ok().and_then(|v| v.parse().ok()).unwrap_or(5);
+    assert!(depth > 0, "PASSWORD_HISTORY_DEPTH=0 disables the check");
+
+    let next = |i: usize| format!("{original}-rotated-{i}-plum");
+
+    // ซ้ำกับรหัสปัจจุบัน ---> ไม่ได้
+    assert_eq!(change(&username, &original, &original).await, 422);
+
+    // เปลี่ยน N - 1 ครั้ง ---> รหัสเดิมยังอยู่ใน N อันล่าสุด
+    let mut current = original.clone();
+    for i in 1..depth {
+        assert_eq!(change(&username, &current, &next(i)).await, 204);
+        current = next(i);
+    }
+    assert_eq!(change(&username, &current, &original).await, 422);
+
+    // ครั้งที่ N ---> รหัสเดิมหลุดจาก N อันล่าสุด ใช้ซ้ำได้
+    assert_eq!(change(&username, &current, &next(depth)).await, 204);
+    assert_eq!(change(&username, &next(depth), &original).await, 204);
+
+    login(&username, &original).await;
+}
+
+// รหัสเดิมผิดนับรวมกับ login ---> ครบ 5 ครั้งถูกล็อก (รหัสถูกก็ไม่ผ่านจนกว่าจะหมดเวลา)
+#[tokio::test]
+#[ignore]
+async fn test_wrong_current_password_locks_account() {
+    let _serial = serial().await;
+    let username = env::var("PASSWORD_HISTORY_TEST_USERNAME").expect("PASSWORD_HISTORY_TEST_USERNAME must be set");
+    let original = env::var("PASSWORD_HISTORY_TEST_PASSWORD").expect("PASSWORD_HISTORY_TEST_PASSWORD must be set");
+    let pool = PgPool::connect(&env::var("DATABASE_URL").expect("DATABASE_URL must be set")).await.unwrap();
+
+    let token = login(&username, &original).await;
+    let new = format!("{original}-never-set-quartz");
+
+    for _ in 0..5 {
+        assert_eq!(change_with(&token, "definitely-not-the-password", &new).await, 422);
+    }
+    let locked_status = change_with(&token, &original, &new).await;
+
+    let res = reqwest::Client::new()
+        .post(url("/auth/login"))
+        .json(&json!({ "username": username, "password": original }))
+        .send()
+        .await
+        .unwrap();
+    let login_status = res.status().as_u16();
+
+    sqlx::query("UPDATE users SET failed_login_attempts = 0, locked_until = NULL WHERE username = $1")
+        .bind(&username)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(locked_status, 422);
+    assert_eq!(login_status, 401);
+}
@@ -24,3 +24,23 @@ async fn test_verify_password() {
     // เพิ่ม assert เพื่อให้ test fail ถ้า verify ไม่ผ่าน
     assert!(argon2.verify_password(password, &parsed_hash).is_ok());
 }
+
+#[tokio::test]
+#[ignore]
+async fn test_pepper_and_params() {
+    use argon2::{Algorithm, Params, Version};
+
+    let password = b"password";
+    let salt = SaltString::generate(&mut OsRng);
+    let params = Params::new(19 * 1024, 2, 1, None).unwrap();
+    let pepper = b"server-side-pepper";
+
+    let peppered = Argon2::new_with_secret(pepper, Algorithm::Argon2id, Version::V0x13, params.clone()).unwrap();
+    let password_hash = peppered.hash_password(password, &salt).unwrap().to_string();
+    let parsed_hash = PasswordHash::new(&password_hash).unwrap();
+
+    // params ถูกเก็บใน hash แต่ pepper ไม่ถูกเก็บ
+    assert_eq!(Params::try_from(&parsed_hash).unwrap().t_cost(), 2);
+    assert!(peppered.verify_password(password, &parsed_hash).is_ok());
+    assert!(Argon2::default().verify_password(password, &parsed_hash).is_err());
+}
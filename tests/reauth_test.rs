@@ -0,0 +1,114 @@
+use std::{env, time::Duration};
+
+use serde_json::{Value, json};
+
+mod common;
+
+use common::{cookies, url};
+
+/*
+|---------------------------------
+| auth_time / amr + route สำคัญ (require_recent_auth) + POST /auth/reauth
+| - REAUTH_TEST_USERNAME / REAUTH_TEST_PASSWORD = user ที่ไม่เปิด MFA
+| - ตั้ง REAUTH_TEST_MAX_AGE_SECS ให้ตรงกับ REAUTH_MAX_AGE_SECS ของ server (เช่น 30)
+|   ---> รอจน token เก่าเกิน แล้วตรวจว่าถูกปฏิเสธ (ไม่ตั้ง = ข้ามส่วนนี้)
+|---------------------------------
+*/
+
+async fn me(token: &str) -> Value {
+    reqwest::Client::new()
+        .get(url("/auth/me"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .expect("server not reachable")
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_auth_time_survives_refresh_and_reauth() {
+    let username = env::var("REAUTH_TEST_USERNAME").expect("REAUTH_TEST_USERNAME must be set");
+    let password = env::var("REAUTH_TEST_PASSWORD").expect("REAUTH_TEST_PASSWORD must be set");
+    let client = reqwest::Client::new();
+
+    let res = client
+        .post(url("/auth/login"))
+        .json(&json!({ "username": username, "password": password }))
+        .send()
+        .await
+        .expect("server not reachable");
+    assert_eq!(res.status().as_u16(), 200);
+    let cookie = cookies(&res);
+    let body: Value = res.json().await.unwrap();
+    let token = body["access_token"].as_str().unwrap().to_string();
+
+    let first = me(&token).await;
+    assert_eq!(first["amr"], json!(["password"]));
+    assert!(first["auth_time"].is_string());
+
+    // refresh ---> auth_time / amr เดิมจาก login (ไม่ใช่เวลาที่ refresh)
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let res = client
+        .post(url("/auth/refresh"))
+        .header("cookie", &cookie)
+        .header("x-csrf-token", body["csrf_token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+    let cookie = cookies(&res);
+    let body: Value = res.json().await.unwrap();
+    let token = body["access_token"].as_str().unwrap().to_string();
+
+    let refreshed = me(&token).await;
+    assert_eq!(refreshed["auth_time"], first["auth_time"]);
+    assert_eq!(refreshed["amr"], first["amr"]);
+
+    // token ยังใหม่ ---> route สำคัญผ่าน
+    let res = client.post(url("/auth/mfa/recovery-codes")).bearer_auth(&token).send().await.unwrap();
+    assert_ne!(res.status().as_u16(), 401);
+
+    if let Ok(max_age) = env::var("REAUTH_TEST_MAX_AGE_SECS") {
+        let max_age: u64 = max_age.parse().unwrap();
+        tokio::time::sleep(Duration::from_secs(max_age + 1)).await;
+
+        let res = client.post(url("/auth/mfa/recovery-codes")).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(res.status().as_u16(), 401);
+        assert!(res.headers()["www-authenticate"].to_str().unwrap().contains("insufficient_user_authentication"));
+        let body: Value = res.json().await.unwrap();
+        assert_eq!(body["code"], "insufficient_user_authentication");
+    }
+
+    // รหัสผิด ---> 422 (token เดิมยังใช้ได้)
+    let res = client
+        .post(url("/auth/reauth"))
+        .bearer_auth(&token)
+        .json(&json!({ "password": "definitely-not-the-password" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 422);
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    let res = client
+        .post(url("/auth/reauth"))
+        .bearer_auth(&token)
+        .header("cookie", &cookie)
+        .json(&json!({ "password": password }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 200);
+    let body: Value = res.json().await.unwrap();
+    let token = body["access_token"].as_str().unwrap().to_string();
+    assert_eq!(body["amr"], json!(["password"]));
+
+    let reauthed = me(&token).await;
+    assert_ne!(reauthed["auth_time"], first["auth_time"]);
+
+    let res = client.post(url("/auth/mfa/recovery-codes")).bearer_auth(&token).send().await.unwrap();
+    assert_ne!(res.status().as_u16(), 401);
+}
@@ -0,0 +1,136 @@
+use std::{env, time::Duration};
+
+use serde_json::{Value, json};
+
+mod common;
+
+use common::{cookies, url};
+
+/*
+|---------------------------------
+| rotate refresh token + ตรวจจับการใช้ซ้ำ
+| - REFRESH_TEST_USERNAME / REFRESH_TEST_PASSWORD = user ที่ไม่เปิด MFA
+| - REFRESH_REUSE_GRACE_SECS ให้ตรงกับ server (ค่าเริ่มต้น 5)
+|---------------------------------
+*/
+
+// POST /auth/refresh ด้วย cookie + CSRF token ของ session ---> (status, cookie ใหม่, body)
+async fn refresh(cookie: &str, csrf: &str) -> (u16, String, Value) {
+    let res = reqwest::Client::new()
+        .post(url("/auth/refresh"))
+        .header("cookie", cookie)
+        .header("x-csrf-token", csrf)
+        .send()
+        .await
+        .expect("server not reachable");
+
+    let status = res.status().as_u16();
+    let cookie = cookies(&res);
+    (status, cookie, res.json().await.unwrap_or(Value::Null))
+}
+
+async fn login() -> (String, String) {
+    let username = env::var("REFRESH_TEST_USERNAME").expect("REFRESH_TEST_USERNAME must be set");
+    let password = env::var("REFRESH_TEST_PASSWORD").expect("REFRESH_TEST_PASSWORD must be set");
+
+    let res = reqwest::Client::new()
+        .post(url("/auth/login"))
+        .json(&json!({ "username": username, "password": password }))
+        .send()
+        .await
+        .expect("server not reachable");
+    assert_eq!(res.status().as_u16(), 200);
+
+    let cookie = cookies(&res);
+    let body: Value = res.json().await.unwrap();
+    (cookie, body["csrf_token"].as_str().unwrap().to_string())
+}
+
+fn grace() -> Duration {
+    Duration::from_secs(env::var("REFRESH_REUSE_GRACE_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(5))
+}
+
+// ใช้ token ที่ rotate ไปแล้วซ้ำ (เกิน grace) ---> ทั้ง session ถูกเพิกถอน (token ตัวล่าสุดก็ใช้ไม่ได้)
+#[tokio::test]
+#[ignore]
+async fn test_refresh_reuse_revokes_session() {
+    let (old_cookie, old_csrf) = login().await;
+
+    let (status, cookie, body) = refresh(&old_cookie, &old_csrf).await;
+    assert_eq!(status, 200, "{body}");
+    let csrf = body["csrf_token"].as_str().unwrap().to_string();
+
+    tokio::time::sleep(grace() + Duration::from_secs(1)).await;
+
+    let (status, _, _) = refresh(&old_cookie, &old_csrf).await;
+    assert_eq!(status, 401);
+
+    let (status, _, body) = refresh(&cookie, &csrf).await;
+    assert_eq!(status, 401, "{body}");
+
+    // session อื่นของ user เดียวกันไม่โดนด้วย
+    let (other_cookie, other_csrf) = login().await;
+    let (status, _, body) = refresh(&other_cookie, &other_csrf).await;
+    assert_eq!(status, 200, "{body}");
+}
+
+// หลาย tab refresh ด้วย token เดียวกันพร้อมกัน ---> ผ่านทุกตัว (ภายใน grace) และ session ยังอยู่
+#[tokio::test]
+#[ignore]
+async fn test_concurrent_refresh_is_not_reuse() {
+    let (cookie, csrf) = login().await;
+
+    let attempts = (0..5).map(|_| refresh(&cookie, &csrf));
+    let results = futures::future::join_all(attempts).await;
+    for (status, _, body) in &results {
+        assert_eq!(*status, 200, "{body}");
+    }
+
+    let (_, cookie, body) = &results[0];
+    let (status, _, body) = refresh(cookie, body["csrf_token"].as_str().unwrap()).await;
+    assert_eq!(status, 200, "{body}");
+}
+
+// logout แล้ว ---> token ที่เพิ่ง rotate ไม่ได้รับ grace (session ถูกปิดแล้ว)
+#[tokio::test]
+#[ignore]
+async fn test_grace_ends_with_logout() {
+    let (old_cookie, old_csrf) = login().await;
+
+    let (status, cookie, body) = refresh(&old_cookie, &old_csrf).await;
+    assert_eq!(status, 200, "{body}");
+
+    let res = reqwest::Client::new()
+        .post(url("/auth/logout"))
+        .header("cookie", &cookie)
+        .header("x-csrf-token", body["csrf_token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success(), "{}", res.status());
+
+    let (status, _, _) = refresh(&old_cookie, &old_csrf).await;
+    assert_eq!(status, 401);
+}
+
+// logout ไม่มี refresh cookie ---> ไม่ต้องมี CSRF token แต่ยังลบ cookie ให้ / มี refresh cookie ---> ยังต้องผ่าน CSRF
+#[tokio::test]
+#[ignore]
+async fn test_logout_without_session_clears_cookies() {
+    let res = reqwest::Client::new()
+        .post(url("/auth/logout"))
+        .send()
+        .await
+        .expect("server not reachable");
+    assert_eq!(res.status().as_u16(), 204);
+    assert!(res.headers().get_all("set-cookie").iter().count() >= 2);
+
+    let (cookie, _) = login().await;
+    let res = reqwest::Client::new()
+        .post(url("/auth/logout"))
+        .header("cookie", &cookie)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 403);
+}
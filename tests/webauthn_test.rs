@@ -0,0 +1,210 @@
+use std::{env, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use totp_rs::{Algorithm, Secret, TOTP};
+use webauthn_authenticator_rs::{WebauthnAuthenticator, softpasskey::SoftPasskey};
+use webauthn_rs::prelude::{
+    CreationChallengeResponse, RegisterPublicKeyCredential, RequestChallengeResponse, Url, Uuid,
+};
+
+mod common;
+
+use common::{call, url};
+
+/*
+|---------------------------------
+| ceremony ของ passkey ด้วย software authenticator
+| - ต้องรัน server ไว้ก่อน และมี user ตาม WEBAUTHN_TEST_USERNAME / WEBAUTHN_TEST_PASSWORD
+| - WEBAUTHN_TEST_ORIGIN ต้องตรงกับ WEBAUTHN_RP_ORIGIN ของ server
+|---------------------------------
+*/
+fn origin() -> Url {
+    let origin = env::var("WEBAUTHN_TEST_ORIGIN").unwrap_or_else(|_| "http://localhost:3000".into());
+    Url::parse(&origin).expect("invalid WEBAUTHN_TEST_ORIGIN")
+}
+
+fn current_code(secret: &str) -> String {
+    let secret = Secret::Encoded(secret.into()).to_bytes().unwrap();
+    TOTP::new_unchecked(Algorithm::SHA1, 6, 0, 30, secret, None, String::new()).generate_current().unwrap()
+}
+
+// เริ่ม passkey เป็น factor ที่สองแล้วไม่ทำต่อ (ceremony มี state ของ passkey ค้างอยู่)
+async fn abandoned_passkey_challenge() -> Value {
+    let challenge = password_login().await;
+    assert_eq!(challenge["mfa_required"], true);
+
+    let (status, begin) = call("/auth/mfa/webauthn/begin", None, json!({ "mfa_token": challenge["mfa_token"] })).await;
+    assert_eq!(status, 200, "{begin}");
+
+    challenge
+}
+
+async fn password_login() -> Value {
+    let username = env::var("WEBAUTHN_TEST_USERNAME").expect("WEBAUTHN_TEST_USERNAME must be set");
+    let password = env::var("WEBAUTHN_TEST_PASSWORD").expect("WEBAUTHN_TEST_PASSWORD must be set");
+
+    let (status, body) = call("/auth/login", None, json!({ "username": username, "password": password })).await;
+    assert_eq!(status, 200, "{body}");
+    body
+}
+
+#[tokio::test]
+#[ignore]
+async fn test_passkey_register_login_and_second_factor() {
+    let mut authenticator = WebauthnAuthenticator::new(SoftPasskey::new(true));
+
+    let login = password_login().await;
+    let token = login["access_token"].as_str().expect("access_token").to_string();
+
+    let me: Value = reqwest::Client::new()
+        .get(url("/auth/me"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let user_id: Uuid = me["id"].as_str().unwrap().parse().unwrap();
+
+    // 1) ลงทะเบียน passkey
+    let (status, begin) = call("/auth/webauthn/register/begin", Some(&token), json!({})).await;
+    assert_eq!(status, 200, "{begin}");
+
+    let options: CreationChallengeResponse = serde_json::from_value(begin["options"].clone()).unwrap();
+    let credential: RegisterPublicKeyCredential = authenticator
+        .do_registration(origin(), options)
+        .expect("soft authenticator registration failed");
+    let raw_id = serde_json::to_value(&credential).unwrap()["rawId"].clone();
+
+    let (status, passkey) = call(
+        "/auth/webauthn/register/finish",
+        Some(&token),
+        json!({ "ceremony_id": begin["ceremony_id"], "name": "soft passkey", "credential": credential }),
+    )
+    .await;
+    assert_eq!(status, 201, "{passkey}");
+
+    // 2) login แบบไม่กรอก username
+    // SoftPasskey ไม่เก็บ resident key ---> จำลองฝั่ง browser: ใส่ allowCredentials + userHandle เอง
+    // (userHandle ไม่อยู่ในส่วนที่ถูกเซ็น ค่าที่ browser ส่งจริงก็มาจาก authenticator แบบเดียวกัน)
+    let (status, begin) = call("/auth/webauthn/login/begin", None, json!({})).await;
+    assert_eq!(status, 200, "{begin}");
+
+    let mut options = begin["options"].clone();
+    options["mediation"] = Value::Null;
+    options["publicKey"]["allowCredentials"] = json!([{ "type": "public-key", "id": raw_id }]);
+    let options: RequestChallengeResponse = serde_json::from_value(options).unwrap();
+
+    let mut assertion = authenticator
+        .do_authentication(origin(), options)
+        .expect("soft authenticator authentication failed");
+    assertion.response.user_handle = Some(user_id.as_bytes().to_vec().into());
+
+    let (status, body) = call(
+        "/auth/webauthn/login/finish",
+        None,
+        json!({ "ceremony_id": begin["ceremony_id"], "credential": assertion }),
+    )
+    .await;
+    assert_eq!(status, 200, "{body}");
+    assert!(body["access_token"].is_string());
+    assert!(body["csrf_token"].is_string());
+
+    // ceremony ใช้ได้ครั้งเดียว
+    let (status, body) = call(
+        "/auth/webauthn/login/finish",
+        None,
+        json!({ "ceremony_id": begin["ceremony_id"], "credential": assertion }),
+    )
+    .await;
+    assert_eq!(status, 401, "{body}");
+    assert_eq!(body["code"], "invalid_credentials");
+
+    // 3) passkey เป็น factor ที่สองเมื่อเปิด MFA
+    let pool = PgPool::connect(&env::var("DATABASE_URL").expect("DATABASE_URL must be set"))
+        .await
+        .unwrap();
+    sqlx::query("UPDATE users SET mfa_enabled = TRUE WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let challenge = password_login().await;
+    assert_eq!(challenge["mfa_required"], true);
+    assert!(challenge["access_token"].is_null());
+    assert_eq!(challenge["methods"], json!(["webauthn"]));
+
+    let (status, begin) = call("/auth/mfa/webauthn/begin", None, json!({ "mfa_token": challenge["mfa_token"] })).await;
+    assert_eq!(status, 200, "{begin}");
+
+    let options: RequestChallengeResponse = serde_json::from_value(begin["options"].clone()).unwrap();
+    let assertion = authenticator
+        .do_authentication(origin(), options)
+        .expect("soft authenticator authentication failed");
+
+    let (status, body) = call(
+        "/auth/mfa/webauthn/finish",
+        None,
+        json!({ "mfa_token": challenge["mfa_token"], "credential": assertion }),
+    )
+    .await;
+
+    sqlx::query("UPDATE users SET mfa_enabled = FALSE WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(status, 200, "{body}");
+    assert!(body["access_token"].is_string());
+
+    // 4) เริ่ม passkey แล้วเปลี่ยนไปใช้ TOTP / recovery code ---> mfa_token เดิมยังใช้ได้
+    let (status, enroll) = call("/auth/mfa/totp/enroll", Some(&token), json!({})).await;
+    assert_eq!(status, 200, "{enroll}");
+    let secret = enroll["secret"].as_str().unwrap().to_string();
+
+    let (status, confirmed) = call(
+        "/auth/mfa/totp/confirm",
+        Some(&token),
+        json!({ "ceremony_id": enroll["ceremony_id"], "code": current_code(&secret) }),
+    )
+    .await;
+    assert_eq!(status, 200, "{confirmed}");
+    let recovery_code = confirmed["recovery_codes"][0].clone();
+
+    let challenge = abandoned_passkey_challenge().await;
+    let (status, body) = call("/auth/mfa/recovery", None, json!({ "mfa_token": challenge["mfa_token"], "code": recovery_code })).await;
+    assert_eq!(status, 200, "{body}");
+
+    // time step ที่ใช้ยืนยันแล้วใช้ซ้ำไม่ได้ ---> รอ step ถัดไป
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    tokio::time::sleep(Duration::from_secs(30 - now % 30 + 1)).await;
+
+    let challenge = abandoned_passkey_challenge().await;
+    let (status, body) = call("/auth/mfa/totp", None, json!({ "mfa_token": challenge["mfa_token"], "code": current_code(&secret) })).await;
+    assert_eq!(status, 200, "{body}");
+    let mfa_token = body["access_token"].as_str().unwrap().to_string();
+
+    // ลบ passkey ที่สร้างในเทสต์ (เปิด MFA แล้ว ---> ต้องใช้ token ที่ผ่านสอง factor) + ปิด TOTP
+    let res = reqwest::Client::new()
+        .delete(url(&format!("/auth/webauthn/credentials/{}", passkey["id"].as_str().unwrap())))
+        .bearer_auth(&mfa_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 204);
+
+    sqlx::query("UPDATE users SET mfa_enabled = FALSE, mfa_totp_secret = NULL, mfa_totp_last_step = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("DELETE FROM mfa_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+}